@@ -4,6 +4,10 @@ USE `sandwich-recipes`;
 CREATE TABLE sandwich (
     id BIGINT PRIMARY KEY AUTO_INCREMENT,
     name VARCHAR(256) NOT NULL UNIQUE,
-    ingredients VARCHAR(1024) NOT NULL,
-    stars INT
+    ingredients TEXT NOT NULL,
+    stars INT,
+    servings INT NOT NULL DEFAULT 1,
+    steps TEXT NOT NULL DEFAULT '[]',
+    sandwich_type VARCHAR(16) NOT NULL DEFAULT 'Undefined',
+    tags TEXT NOT NULL DEFAULT '[]'
 );
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- Preparation steps of the sandwiches, for a database created before they were stored.
+-- init.sql only runs on an empty data directory; apply to an existing one with
+--   docker compose exec -T mariadb mariadb -uroot -p"$MYSQL_ROOT_PASSWORD" sandwich-recipes < mariadb-migrations/002_add_steps.sql
+ALTER TABLE sandwich ADD COLUMN IF NOT EXISTS steps TEXT NOT NULL DEFAULT '[]';
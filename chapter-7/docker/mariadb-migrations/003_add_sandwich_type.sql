@@ -0,0 +1,4 @@
+-- Type of the sandwiches, for a database created before it was stored.
+-- init.sql only runs on an empty data directory; apply to an existing one with
+--   docker compose exec -T mariadb mariadb -uroot -p"$MYSQL_ROOT_PASSWORD" sandwich-recipes < mariadb-migrations/003_add_sandwich_type.sql
+ALTER TABLE sandwich ADD COLUMN IF NOT EXISTS sandwich_type VARCHAR(16) NOT NULL DEFAULT 'Undefined';
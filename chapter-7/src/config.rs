@@ -1,9 +1,12 @@
 use std::env;
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
+use crate::domain::classification::ClaimPolicy;
+use crate::driven::persisted_queries::in_memory_store::DEFAULT_CAPACITY;
+
 const MONGODB_HOST: &str = "MONGODB_HOST";
 const MONGODB_PORT: &str = "MONGODB_PORT";
 const MONGODB_USER: &str = "MONGODB_USER";
@@ -18,10 +21,31 @@ const MARIADB_USER: &str = "MARIADB_USER";
 const MARIADB_PWD: &str ="MARIADB_PWD";
 const MARIADB_DB: &str = "MARIADB_DB";
 
+const GRAPHQL_MAX_DEPTH: &str = "GRAPHQL_MAX_DEPTH";
+const GRAPHQL_MAX_COMPLEXITY: &str = "GRAPHQL_MAX_COMPLEXITY";
+const GRAPHQL_TIMEOUT_MS: &str = "GRAPHQL_TIMEOUT_MS";
+const GRAPHQL_ALLOW_LIST: &str = "GRAPHQL_ALLOW_LIST";
+const GRAPHQL_MAX_PERSISTED_QUERIES: &str = "GRAPHQL_MAX_PERSISTED_QUERIES";
+
+const CLASSIFICATION_DICTIONARY: &str = "CLASSIFICATION_DICTIONARY";
+const CLASSIFICATION_VEGGIE_CLAIM: &str = "CLASSIFICATION_VEGGIE_CLAIM";
+
+const PRICING_CURRENCY: &str = "PRICING_CURRENCY";
+const PRICING_PRICE_LIST: &str = "PRICING_PRICE_LIST";
+
+const INGREDIENTS_CATALOG: &str = "INGREDIENTS_CATALOG";
+
 #[derive(Deserialize)]
 pub struct Config {
     pub mongo_db: MongoDBConfig,
-    pub maria_db: MariaDBConfig
+    pub maria_db: MariaDBConfig,
+    pub graphql: GraphQLConfig,
+    #[serde(default)]
+    pub classification: ClassificationConfig,
+    #[serde(default)]
+    pub pricing: PricingConfig,
+    #[serde(default)]
+    pub ingredients: IngredientsConfig,
 }
 
 #[derive(Deserialize, Clone)]
@@ -44,6 +68,59 @@ pub struct MariaDBConfig {
     pub database: String,
 }
 
+#[derive(Deserialize, Clone)]
+pub struct GraphQLConfig {
+    pub max_depth: usize,
+    pub max_complexity: usize,
+    pub timeout_ms: u64,
+    // when set, only the persisted queries listed in this file can be executed
+    pub allow_list_path: Option<String>,
+    // persisted queries registered by the clients kept in memory, the oldest being evicted first
+    #[serde(default = "default_max_persisted_queries")]
+    pub max_persisted_queries: usize,
+}
+
+fn default_max_persisted_queries() -> usize {
+    DEFAULT_CAPACITY
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct ClassificationConfig {
+    // ingredient categories used to infer the sandwich types, the bundled ones when not set
+    pub dictionary_path: Option<String>,
+    // what to do with a sandwich claimed veggie while listing meat or fish
+    #[serde(default)]
+    pub veggie_claim: ClaimPolicy,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PricingConfig {
+    // ISO 4217 code of the currency the prices are in
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    // TOML or CSV price list loaded at startup, the catalog starts empty when not set
+    pub price_list_path: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct IngredientsConfig {
+    // TOML catalog of the canonical ingredient names and their synonyms, stored in MongoDB when none is stored yet
+    pub catalog_path: Option<String>,
+}
+
+fn default_currency() -> String {
+    String::from("EUR")
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        PricingConfig {
+            currency: default_currency(),
+            price_list_path: None,
+        }
+    }
+}
+
 impl MongoDBConfig {
     pub fn validate(&self) -> Result<(), String> {
         if self.user.is_empty() {
@@ -96,6 +173,38 @@ impl MariaDBConfig {
     }
 }
 
+impl GraphQLConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_depth == 0 {
+            return Err("GraphQL max depth must be greater than 0".to_string());
+        }
+
+        if self.max_complexity == 0 {
+            return Err("GraphQL max complexity must be greater than 0".to_string());
+        }
+
+        if self.timeout_ms == 0 {
+            return Err("GraphQL timeout must be greater than 0".to_string());
+        }
+
+        if self.max_persisted_queries == 0 {
+            return Err("GraphQL max persisted queries must be greater than 0".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl PricingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.currency.len() != 3 || !self.currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(format!("The currency must be an ISO 4217 code, as EUR: {}", self.currency));
+        }
+
+        Ok(())
+    }
+}
+
 pub fn parse_local_config() -> Config {
     let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     d.push("resources/config.toml");
@@ -108,14 +217,45 @@ pub fn parse_config(path_buf: PathBuf) -> Config {
 }
 
 fn parse_config_from_file(path_buf: PathBuf) -> Config {
+    let directory = path_buf.parent().map(Path::to_path_buf).unwrap_or_default();
     let config_file = path_buf.into_os_string().into_string().unwrap();
-    toml::from_str(read_to_string(config_file).unwrap().as_str()).unwrap()
+    let config = toml::from_str(read_to_string(config_file).unwrap().as_str()).unwrap();
+    resolve_paths(config, &directory)
+}
+
+/// the files named in a config file are found relative to its directory, whatever the working directory.
+/// Those named by the env vars stay relative to the working directory
+fn resolve_paths(config: Config, directory: &Path) -> Config {
+    let resolve = |path: Option<String>| path.map(|p| directory.join(p).to_string_lossy().into_owned());
+
+    Config {
+        graphql: GraphQLConfig {
+            allow_list_path: resolve(config.graphql.allow_list_path),
+            ..config.graphql
+        },
+        classification: ClassificationConfig {
+            dictionary_path: resolve(config.classification.dictionary_path),
+            ..config.classification
+        },
+        pricing: PricingConfig {
+            price_list_path: resolve(config.pricing.price_list_path),
+            ..config.pricing
+        },
+        ingredients: IngredientsConfig {
+            catalog_path: resolve(config.ingredients.catalog_path),
+        },
+        ..config
+    }
 }
 
 fn override_config_with_env_vars(config: Config) -> Config {
 
     let mongo_db = config.mongo_db;
     let maria_db = config.maria_db;
+    let graphql = config.graphql;
+    let classification = config.classification;
+    let pricing = config.pricing;
+    let ingredients = config.ingredients;
 
     Config {
         mongo_db: MongoDBConfig {
@@ -133,6 +273,24 @@ fn override_config_with_env_vars(config: Config) -> Config {
             user: env::var(MARIADB_USER).unwrap_or(maria_db.user),
             password: env::var(MARIADB_PWD).unwrap_or(maria_db.password),
             database: env::var(MARIADB_DB).unwrap_or(maria_db.database)
+        },
+        graphql: GraphQLConfig {
+            max_depth: env::var(GRAPHQL_MAX_DEPTH).map(|d| d.parse::<usize>().expect("Cannot parse the received GraphQL max depth")).unwrap_or(graphql.max_depth),
+            max_complexity: env::var(GRAPHQL_MAX_COMPLEXITY).map(|c| c.parse::<usize>().expect("Cannot parse the received GraphQL max complexity")).unwrap_or(graphql.max_complexity),
+            timeout_ms: env::var(GRAPHQL_TIMEOUT_MS).map(|t| t.parse::<u64>().expect("Cannot parse the received GraphQL timeout")).unwrap_or(graphql.timeout_ms),
+            allow_list_path: env::var(GRAPHQL_ALLOW_LIST).ok().or(graphql.allow_list_path),
+            max_persisted_queries: env::var(GRAPHQL_MAX_PERSISTED_QUERIES).map(|m| m.parse::<usize>().expect("Cannot parse the received GraphQL max persisted queries")).unwrap_or(graphql.max_persisted_queries),
+        },
+        classification: ClassificationConfig {
+            dictionary_path: env::var(CLASSIFICATION_DICTIONARY).ok().or(classification.dictionary_path),
+            veggie_claim: env::var(CLASSIFICATION_VEGGIE_CLAIM).map(|p| ClaimPolicy::parse(&p).expect("Cannot parse the received veggie claim policy")).unwrap_or(classification.veggie_claim),
+        },
+        pricing: PricingConfig {
+            currency: env::var(PRICING_CURRENCY).unwrap_or(pricing.currency),
+            price_list_path: env::var(PRICING_PRICE_LIST).ok().or(pricing.price_list_path),
+        },
+        ingredients: IngredientsConfig {
+            catalog_path: env::var(INGREDIENTS_CATALOG).ok().or(ingredients.catalog_path),
         }
     }
 }
@@ -171,6 +329,28 @@ mod tests {
         assert_eq!("root", maria_db.user);
         assert_eq!("m4r14dbs4ndw1ch3s", maria_db.password);
         assert_eq!("sandwich-recipes", maria_db.database);
+
+        let graphql = config.graphql;
+
+        assert_eq!(8, graphql.max_depth);
+        assert_eq!(100, graphql.max_complexity);
+        assert_eq!(5000, graphql.timeout_ms);
+        assert_eq!(None, graphql.allow_list_path);
+        assert_eq!(1000, graphql.max_persisted_queries);
+
+        let classification = config.classification;
+
+        assert_eq!(None, classification.dictionary_path);
+        assert_eq!(ClaimPolicy::Warn, classification.veggie_claim);
+
+        let pricing = config.pricing;
+
+        assert_eq!("EUR", pricing.currency);
+        assert_eq!(None, pricing.price_list_path);
+
+        let ingredients = config.ingredients;
+
+        assert_eq!(None, ingredients.catalog_path);
     }
 
     #[test]
@@ -191,6 +371,20 @@ mod tests {
         env::set_var(MARIADB_PWD, "such_a_pwd");
         env::set_var(MARIADB_DB, "your_db");
 
+        env::set_var(GRAPHQL_MAX_DEPTH, "3");
+        env::set_var(GRAPHQL_MAX_COMPLEXITY, "33");
+        env::set_var(GRAPHQL_TIMEOUT_MS, "333");
+        env::set_var(GRAPHQL_ALLOW_LIST, "allowed.json");
+        env::set_var(GRAPHQL_MAX_PERSISTED_QUERIES, "50");
+
+        env::set_var(CLASSIFICATION_DICTIONARY, "categories.toml");
+        env::set_var(CLASSIFICATION_VEGGIE_CLAIM, "reject");
+
+        env::set_var(PRICING_CURRENCY, "CHF");
+        env::set_var(PRICING_PRICE_LIST, "prices.csv");
+
+        env::set_var(INGREDIENTS_CATALOG, "ingredients.toml");
+
         let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         d.push("src/tests/test_config.toml");
         let config = parse_config(d);
@@ -212,6 +406,28 @@ mod tests {
         assert_eq!("such_a_pwd", maria_db.password);
         assert_eq!("your_db", maria_db.database);
 
+        let graphql = config.graphql;
+
+        assert_eq!(3, graphql.max_depth);
+        assert_eq!(33, graphql.max_complexity);
+        assert_eq!(333, graphql.timeout_ms);
+        assert_eq!("allowed.json", graphql.allow_list_path.unwrap());
+        assert_eq!(50, graphql.max_persisted_queries);
+
+        let classification = config.classification;
+
+        assert_eq!("categories.toml", classification.dictionary_path.unwrap());
+        assert_eq!(ClaimPolicy::Reject, classification.veggie_claim);
+
+        let pricing = config.pricing;
+
+        assert_eq!("CHF", pricing.currency);
+        assert_eq!("prices.csv", pricing.price_list_path.unwrap());
+
+        let ingredients = config.ingredients;
+
+        assert_eq!("ingredients.toml", ingredients.catalog_path.unwrap());
+
         // reset env vars
         env::remove_var(MONGODB_HOST);
         env::remove_var(MONGODB_PORT);
@@ -226,5 +442,37 @@ mod tests {
         env::remove_var(MARIADB_USER);
         env::remove_var(MARIADB_PWD);
         env::remove_var(MARIADB_DB);
+
+        env::remove_var(GRAPHQL_MAX_DEPTH);
+        env::remove_var(GRAPHQL_MAX_COMPLEXITY);
+        env::remove_var(GRAPHQL_TIMEOUT_MS);
+        env::remove_var(GRAPHQL_ALLOW_LIST);
+        env::remove_var(GRAPHQL_MAX_PERSISTED_QUERIES);
+
+        env::remove_var(CLASSIFICATION_DICTIONARY);
+        env::remove_var(CLASSIFICATION_VEGGIE_CLAIM);
+
+        env::remove_var(PRICING_CURRENCY);
+        env::remove_var(PRICING_PRICE_LIST);
+
+        env::remove_var(INGREDIENTS_CATALOG);
+    }
+
+    #[test]
+    fn should_find_the_files_of_a_config_next_to_it() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/config.toml");
+
+        let mut config = parse_config_from_file(d);
+
+        assert!(Path::new(config.pricing.price_list_path.as_ref().unwrap()).is_file());
+        assert!(Path::new(config.ingredients.catalog_path.as_ref().unwrap()).is_file());
+
+        config.graphql.allow_list_path = Some(String::from("/etc/sandwiches/allowed.json"));
+        config.pricing.price_list_path = Some(String::from("prices.csv"));
+        let config = resolve_paths(config, Path::new("/srv/sandwiches"));
+
+        assert_eq!(Some(String::from("/etc/sandwiches/allowed.json")), config.graphql.allow_list_path);
+        assert_eq!(Some(String::from("/srv/sandwiches/prices.csv")), config.pricing.price_list_path);
     }
 }
@@ -0,0 +1,136 @@
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::phrases::PhraseDictionary;
+
+/// the table shipped with the service, so that it works without any external source
+const BUNDLED_TABLE: &str = include_str!("../../resources/allergens.toml");
+/// key of the phrases without allergens
+const NONE_KEY: &str = "none";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Allergen {
+    Gluten,
+    Dairy,
+    Nuts,
+    Peanuts,
+    Egg,
+    Fish,
+    Crustaceans,
+    Soy,
+    Sesame,
+    Mustard,
+    Celery,
+}
+
+impl Allergen {
+    pub const ALL: [Allergen; 11] = [Allergen::Gluten, Allergen::Dairy, Allergen::Nuts, Allergen::Peanuts, Allergen::Egg,
+        Allergen::Fish, Allergen::Crustaceans, Allergen::Soy, Allergen::Sesame, Allergen::Mustard, Allergen::Celery];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Allergen::Gluten => "gluten",
+            Allergen::Dairy => "dairy",
+            Allergen::Nuts => "nuts",
+            Allergen::Peanuts => "peanuts",
+            Allergen::Egg => "egg",
+            Allergen::Fish => "fish",
+            Allergen::Crustaceans => "crustaceans",
+            Allergen::Soy => "soy",
+            Allergen::Sesame => "sesame",
+            Allergen::Mustard => "mustard",
+            Allergen::Celery => "celery",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Allergen::ALL.into_iter().find(|a| a.name() == name.trim().to_lowercase())
+    }
+}
+
+/// Phrases of ingredient names mapped to the allergens they contain
+pub struct AllergenTable {
+    phrases: PhraseDictionary<Allergen>,
+}
+
+impl AllergenTable {
+    /// read a table listing, for each allergen, the phrases containing it
+    pub fn parse(table: &str) -> Result<Self, String> {
+        let phrases = PhraseDictionary::parse(table, |key| match key {
+            NONE_KEY => Ok(None),
+            _ => Allergen::parse(key).map(Some).ok_or_else(|| format!("Unknown allergen in the table: {}", key)),
+        })?;
+
+        Ok(AllergenTable { phrases })
+    }
+
+    /// the allergens of an ingredient, found from its name, none when the table does not know the ingredient
+    pub fn allergens_of(&self, ingredient: &str) -> Option<BTreeSet<Allergen>> {
+        self.phrases.lookup(ingredient)
+    }
+}
+
+/// The allergens of a sandwich, with the ingredients unknown to the table, which may contain any
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SandwichAllergens {
+    pub found: BTreeSet<Allergen>,
+    pub unknown: Vec<String>,
+}
+
+impl SandwichAllergens {
+    /// only when every ingredient is known, as an unknown one may contain the excluded allergens
+    pub fn free_of(&self, excluded: &[Allergen]) -> bool {
+        self.unknown.is_empty() && !self.found.iter().any(|a| excluded.contains(a))
+    }
+}
+
+/// the table shipped with the service
+pub fn bundled_table() -> &'static AllergenTable {
+    static TABLE: OnceLock<AllergenTable> = OnceLock::new();
+    TABLE.get_or_init(|| AllergenTable::parse(BUNDLED_TABLE).expect("the bundled allergen table is valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::{Sandwich, SandwichType};
+
+    use super::*;
+
+    fn allergens(ingredient: &str) -> Vec<Allergen> {
+        bundled_table().allergens_of(ingredient).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn should_find_the_allergens_of_an_ingredient() {
+        assert_eq!(allergens("Sourdough BREAD"), vec![Allergen::Gluten]);
+        assert_eq!(allergens("Gruyère"), vec![Allergen::Dairy]);
+        assert_eq!(allergens("basil pesto"), vec![Allergen::Dairy, Allergen::Nuts]);
+        assert_eq!(allergens("ham"), vec![]);
+    }
+
+    #[test]
+    fn should_prefer_the_longest_phrase() {
+        assert_eq!(allergens("peanut butter"), vec![Allergen::Peanuts]);
+        assert_eq!(allergens("coconut milk"), vec![]);
+        assert_eq!(allergens("butter"), vec![Allergen::Dairy]);
+    }
+
+    #[test]
+    fn should_not_take_an_unknown_ingredient_as_free_of_allergens() {
+        let ingredients = vec![String::from("bun"), String::from("2 slices biltong")];
+        let sandwich = Sandwich::new(String::new(), String::from("Biltong roll"), ingredients, vec![], SandwichType::Meat, 0).unwrap();
+
+        let allergens = sandwich.allergens();
+        assert_eq!(allergens.found.into_iter().collect::<Vec<Allergen>>(), vec![Allergen::Gluten]);
+        assert_eq!(allergens.unknown, vec![String::from("biltong")]);
+        assert!(!sandwich.allergens().free_of(&[Allergen::Dairy]));
+    }
+
+    #[test]
+    fn should_reject_an_unknown_allergen() {
+        assert!(AllergenTable::parse("lupin = [\"lupin\"]").is_err());
+    }
+}
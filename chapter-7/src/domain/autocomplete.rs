@@ -0,0 +1,75 @@
+use actix_web::web;
+
+use crate::domain::sandwich::Sandwich;
+use crate::driven::repository::RepoFindAllError;
+use crate::driven::suggestions::{Suggestion, SuggestionField, SuggestionIndex};
+use crate::helpers::empty_find_sandwich;
+use crate::Repository;
+
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 10;
+pub const MAX_SUGGESTION_LIMIT: usize = 50;
+
+#[derive(Debug)]
+pub enum AutocompleteError {
+    InvalidLimit(String),
+    Unknown(String),
+}
+
+// this is my port / use case
+/// The distinct names or ingredients starting with the prefix, the most frequent first
+pub async fn autocomplete<S: SuggestionIndex + ?Sized>(suggestions: &S, field: SuggestionField, prefix: &str, limit: Option<usize>) -> Result<Vec<Suggestion>, AutocompleteError> {
+
+    let limit = match limit {
+        None => DEFAULT_SUGGESTION_LIMIT,
+        Some(l) if (1..=MAX_SUGGESTION_LIMIT).contains(&l) => l,
+        Some(_) => return Err(AutocompleteError::InvalidLimit(format!("The limit must be between 1 and {}", MAX_SUGGESTION_LIMIT))),
+    };
+
+    Ok(suggestions.suggest(field, prefix, limit).await)
+}
+
+/// Fill the suggestion index with the stored sandwiches, the use cases keep it current afterwards
+pub async fn index_all_sandwiches<T: Repository<Sandwich>, S: SuggestionIndex + ?Sized>(repository: web::Data<T>, suggestions: &S) -> Result<usize, AutocompleteError> {
+
+    let sandwiches = repository.find_all(empty_find_sandwich()).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => AutocompleteError::Unknown(format!("Unknown error: {}", e)),
+        })?;
+
+    for sandwich in &sandwiches {
+        suggestions.index(sandwich).await;
+    }
+
+    Ok(sandwiches.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{get_testing_mongodb_config, SANDWICH_NAME};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_suggest_indexed_values() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        let suggestions = InMemorySuggestionIndex::new();
+
+        index_all_sandwiches(Data::new(repo), &suggestions).await.unwrap();
+        let found = autocomplete(&suggestions, SuggestionField::Name, "hot", None).await.unwrap();
+
+        assert_eq!(found, vec![Suggestion { value: String::from(SANDWICH_NAME), count: 1 }]);
+    }
+
+    #[actix_rt::test]
+    async fn should_reject_an_invalid_limit() {
+        let suggestions = InMemorySuggestionIndex::new();
+
+        let res = autocomplete(&suggestions, SuggestionField::Ingredient, "ha", Some(0)).await;
+
+        assert!(matches!(res, Err(AutocompleteError::InvalidLimit(_))));
+    }
+}
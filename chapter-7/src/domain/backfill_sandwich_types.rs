@@ -0,0 +1,80 @@
+use actix_web::web;
+
+use crate::domain::classification::classifier;
+use crate::domain::sandwich::{Sandwich, SandwichType};
+use crate::driven::repository::{RepoFindAllError, RepoUpdateError};
+use crate::helpers::empty_find_sandwich;
+use crate::Repository;
+
+#[derive(Debug)]
+pub enum BackfillError {
+    Unknown(String),
+}
+
+// this is my port / use case
+/// Type the sandwiches stored as undefined from their ingredients, returning how many were typed.
+/// Those with an ingredient unknown to the classifier stay undefined, they are not written.
+/// Each one is written only if unchanged since it was read, so the backfill can be run again to finish the job
+pub async fn backfill_sandwich_types<T: Repository<Sandwich>>(repository: web::Data<T>) -> Result<usize, BackfillError> {
+
+    let sandwiches = repository.find_all(empty_find_sandwich()).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => BackfillError::Unknown(e)
+        })?;
+
+    let classifier = classifier();
+    let typed: Vec<Sandwich> = sandwiches.into_iter()
+        .filter(|s| s.sandwich_type() == &SandwichType::Undefined)
+        .filter_map(|s| match classifier.infer(s.ingredients()) {
+            SandwichType::Undefined => None,
+            inferred => Some(s.with_sandwich_type(inferred)),
+        })
+        .collect();
+
+    let mut updated = 0;
+    let mut failures = vec![];
+    for result in repository.update_many(typed).await {
+        match result {
+            Ok(_) => updated += 1,
+            Err(RepoUpdateError::VersionMismatch) => failures.push(String::from("changed while typed")),
+            Err(RepoUpdateError::NotFound) => failures.push(String::from("deleted while typed")),
+            Err(RepoUpdateError::InvalidData(e)) | Err(RepoUpdateError::Unknown(e)) => failures.push(e),
+        }
+    }
+
+    match failures.is_empty() {
+        true => Ok(updated),
+        false => Err(BackfillError::Unknown(format!("{} sandwiches typed, {} failed: {}", updated, failures.len(), failures.join(", ")))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::get_testing_mongodb_config;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_leave_the_typed_sandwiches_alone() {
+
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let updated = backfill_sandwich_types(Data::new(repo)).await.unwrap();
+
+        assert_eq!(updated, 0);
+    }
+
+    #[actix_rt::test]
+    async fn should_not_backfill_while_the_repo_returns_error() {
+
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_error(true);
+
+        let result = backfill_sandwich_types(Data::new(repo)).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::web;
+
+use crate::domain::commands::SandwichPorts;
+use crate::domain::sandwich::{Sandwich, SandwichType};
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::repository::{RepoCreateError, RepoDeleteError, RepoFindAllError, RepoUpdateError};
+use crate::driven::suggestions::SuggestionIndex;
+use crate::Repository;
+
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug)]
+pub enum BatchError {
+    Empty,
+    TooLarge(usize),
+    Unknown(String),
+}
+
+#[derive(Debug)]
+pub enum OperationError {
+    InvalidData(String),
+    Unknown(String),
+    NotFound,
+    Conflict(String),
+    PreconditionFailed,
+}
+
+#[derive(Debug, Clone)]
+pub enum SandwichOperation {
+    Create { name: String, ingredients: Vec<String>, sandwich_type: SandwichType },
+    Update { id: String, name: String, ingredients: Vec<String>, sandwich_type: SandwichType, version: Option<i64> },
+    Delete { id: String, version: Option<i64> },
+}
+
+#[derive(Debug)]
+pub enum OperationOutcome {
+    Created(Sandwich),
+    Updated(Sandwich),
+    Deleted,
+}
+
+pub type OperationResult = Result<OperationOutcome, OperationError>;
+
+// this is my port / use case
+/// Apply many operations at once. Operations are grouped by kind, creations first, then updates and deletions,
+/// and a result is returned for each of them, in the received order
+pub async fn batch_sandwiches<T: Repository<Sandwich>, S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized>(repository: web::Data<T>, ports: &SandwichPorts<'_, S, C>, operations: Vec<SandwichOperation>) -> Result<Vec<OperationResult>, BatchError> {
+
+    if operations.is_empty() {
+        return Err(BatchError::Empty);
+    }
+
+    if operations.len() > MAX_BATCH_SIZE {
+        return Err(BatchError::TooLarge(MAX_BATCH_SIZE));
+    }
+
+    let preparation = ports.preparation().await
+        .map_err(BatchError::Unknown)?;
+
+    // the names already taken are read once for the whole batch, instead of once per creation
+    let names: Vec<String> = operations.iter()
+        .filter_map(|o| match o {
+            SandwichOperation::Create { name, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut taken_names: HashSet<String> = repository.find_many_by_names(names).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => BatchError::Unknown(format!("Unknown error: {}", e)),
+        })?
+        .iter()
+        .map(|s| s.name().value().to_string())
+        .collect();
+    // an update replaces the name, ingredients and type only, the rest is kept from the stored sandwiches
+    let ids: Vec<String> = operations.iter()
+        .filter_map(|o| match o {
+            SandwichOperation::Update { id, .. } if !id.is_empty() => Some(id.clone()),
+            _ => None,
+        })
+        .collect();
+    let stored: HashMap<String, Sandwich> = if ids.is_empty() {
+        HashMap::new()
+    } else {
+        repository.find_many_by_ids(ids).await
+            .map_err(|e| match e {
+                RepoFindAllError::Unknown(e) => BatchError::Unknown(format!("Unknown error: {}", e)),
+            })?
+            .into_iter()
+            .filter_map(|s| s.id().value().clone().map(|id| (id, s)))
+            .collect()
+    };
+    let mut touched_ids: HashSet<String> = HashSet::new();
+
+    let mut results: Vec<Option<OperationResult>> = operations.iter().map(|_| None).collect();
+    let mut creates = vec![];
+    let mut updates = vec![];
+    let mut deletes = vec![];
+
+    for (index, operation) in operations.into_iter().enumerate() {
+        match operation {
+            SandwichOperation::Create { name, ingredients, sandwich_type } => {
+                match Sandwich::new(String::from(""), name, ingredients, vec![], sandwich_type, 0).and_then(|s| preparation.prepare(s)) {
+                    Ok(s) if !taken_names.insert(s.name().value().to_string()) =>
+                        results[index] = Some(Err(OperationError::Conflict(String::from("A sandwich with this name is already present")))),
+                    Ok(s) => creates.push((index, s)),
+                    Err(e) => results[index] = Some(Err(OperationError::InvalidData(e))),
+                }
+            }
+            SandwichOperation::Update { id, name, ingredients, sandwich_type, version } => {
+                if id.is_empty() {
+                    results[index] = Some(Err(OperationError::InvalidData(String::from("Cannot update without a target id"))));
+                } else if !touched_ids.insert(id.clone()) {
+                    results[index] = Some(Err(OperationError::Conflict(String::from("Only one operation per sandwich is allowed in a batch"))));
+                } else if let Some(found) = stored.get(&id) {
+                    let updating = Sandwich::new(id, name, ingredients, found.steps().value().clone(), sandwich_type, found.stars().value())
+                        .map(|s| s.with_tags(found.tags().clone()).with_servings(*found.servings()))
+                        .and_then(|s| preparation.prepare(s));
+                    match updating {
+                        Ok(s) => updates.push((index, s.with_version(version))),
+                        Err(e) => results[index] = Some(Err(OperationError::InvalidData(e))),
+                    }
+                } else {
+                    results[index] = Some(Err(OperationError::NotFound));
+                }
+            }
+            SandwichOperation::Delete { id, version } => {
+                if id.is_empty() {
+                    results[index] = Some(Err(OperationError::InvalidData(String::from("Cannot delete without a target id"))));
+                } else if !touched_ids.insert(id.clone()) {
+                    results[index] = Some(Err(OperationError::Conflict(String::from("Only one operation per sandwich is allowed in a batch"))));
+                } else {
+                    deletes.push((index, (id, version)));
+                }
+            }
+        }
+    }
+
+    let (indexes, sandwiches): (Vec<usize>, Vec<Sandwich>) = creates.into_iter().unzip();
+    for (index, res) in indexes.into_iter().zip(repository.create_many(sandwiches).await) {
+        if let Ok(created) = &res {
+            ports.suggestions.index(created).await;
+        }
+        results[index] = Some(res
+            .map(OperationOutcome::Created)
+            .map_err(|e| match e {
+                RepoCreateError::InvalidData(e) => OperationError::InvalidData(format!("Invalid data: {}", e)),
+                RepoCreateError::Unknown(e) => OperationError::Unknown(format!("Unknown error: {}", e)),
+            }));
+    }
+
+    let (indexes, sandwiches): (Vec<usize>, Vec<Sandwich>) = updates.into_iter().unzip();
+    for (index, res) in indexes.into_iter().zip(repository.update_many(sandwiches).await) {
+        if let Ok(updated) = &res {
+            ports.suggestions.index(updated).await;
+        }
+        results[index] = Some(res
+            .map(OperationOutcome::Updated)
+            .map_err(|e| match e {
+                RepoUpdateError::InvalidData(e) => OperationError::InvalidData(format!("Invalid data: {}", e)),
+                RepoUpdateError::NotFound => OperationError::NotFound,
+                RepoUpdateError::VersionMismatch => OperationError::PreconditionFailed,
+                RepoUpdateError::Unknown(e) => OperationError::Unknown(format!("Unknown error: {}", e)),
+            }));
+    }
+
+    let (indexes, ids): (Vec<usize>, Vec<(String, Option<i64>)>) = deletes.into_iter().unzip();
+    let deleted_ids: Vec<String> = ids.iter().map(|(id, _)| id.clone()).collect();
+    for ((index, id), res) in indexes.into_iter().zip(deleted_ids).zip(repository.delete_many(ids).await) {
+        if res.is_ok() {
+            ports.suggestions.remove(&id).await;
+        }
+        results[index] = Some(res
+            .map(|_| OperationOutcome::Deleted)
+            .map_err(|e| match e {
+                RepoDeleteError::InvalidData(e) => OperationError::InvalidData(format!("Invalid data: {}", e)),
+                RepoDeleteError::NotFound => OperationError::NotFound,
+                RepoDeleteError::VersionMismatch => OperationError::PreconditionFailed,
+                RepoDeleteError::Unknown(e) => OperationError::Unknown(format!("Unknown error: {}", e)),
+            }));
+    }
+
+    // a repository returning fewer results than operations leaves some of them unanswered
+    Ok(results.into_iter()
+        .map(|r| r.unwrap_or_else(|| Err(OperationError::Unknown(String::from("No result for the operation")))))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::domain::ingredients::CatalogIngredient;
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{get_testing_mongodb_config, SANDWICH_ID, SANDWICH_NAME, SANDWICH_TYPE, stub_ingredients, stub_stored_sandwich};
+
+    use super::*;
+
+    fn create(name: &str, ingredients: Vec<String>) -> SandwichOperation {
+        SandwichOperation::Create { name: name.to_string(), ingredients, sandwich_type: SANDWICH_TYPE }
+    }
+
+    #[actix_rt::test]
+    async fn should_return_a_result_per_operation_in_order() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let operations = vec![
+            create("Club sandwich", stub_ingredients()),
+            create(SANDWICH_NAME, stub_ingredients()),
+            create("Empty", vec![]),
+            SandwichOperation::Update { id: SANDWICH_ID.to_string(), name: "Renamed".to_string(), ingredients: stub_ingredients(), sandwich_type: SANDWICH_TYPE, version: Some(1) },
+            SandwichOperation::Delete { id: SANDWICH_ID.to_string(), version: Some(2) },
+            create("Club sandwich", stub_ingredients()),
+        ];
+
+        let results = batch_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, operations).await.unwrap();
+
+        assert_eq!(results.len(), 6);
+        assert!(matches!(results[0], Ok(OperationOutcome::Created(_))));
+        assert!(matches!(results[1], Err(OperationError::Conflict(_))));
+        assert!(matches!(results[2], Err(OperationError::InvalidData(_))));
+        assert!(matches!(results[3], Ok(OperationOutcome::Updated(_))));
+        assert!(matches!(results[4], Err(OperationError::Conflict(_))));
+        assert!(matches!(results[5], Err(OperationError::Conflict(_))));
+    }
+
+    #[actix_rt::test]
+    async fn should_keep_the_stored_steps_tags_and_servings_on_update() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let operations = vec![
+            SandwichOperation::Update { id: SANDWICH_ID.to_string(), name: "Renamed".to_string(), ingredients: stub_ingredients(), sandwich_type: SANDWICH_TYPE, version: Some(1) },
+        ];
+
+        let results = batch_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, operations).await.unwrap();
+
+        let stored = stub_stored_sandwich(SANDWICH_ID);
+        match &results[0] {
+            Ok(OperationOutcome::Updated(s)) => {
+                assert_eq!(s.name().value(), "Renamed");
+                assert_eq!(s.steps().value(), stored.steps().value());
+                assert_eq!(s.tags(), stored.tags());
+                assert_eq!(s.servings().value(), stored.servings().value());
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn should_name_the_ingredients_after_the_catalog() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        let catalog = InMemoryIngredientCatalog::new();
+        catalog.upsert(CatalogIngredient::new("ketchup", vec![String::from("tomato ketchup")]).unwrap()).await.unwrap();
+
+        let operations = vec![create("Club sandwich", vec![String::from("bread"), String::from("Tomato Ketchup")])];
+
+        let results = batch_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &catalog }, operations).await.unwrap();
+
+        match &results[0] {
+            Ok(OperationOutcome::Created(s)) => assert_eq!(s.ingredients().names(), vec!["bread", "ketchup"]),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn should_reject_an_empty_batch() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let res = batch_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, vec![]).await;
+
+        assert!(matches!(res, Err(BatchError::Empty)));
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::domain::phrases::PhraseDictionary;
+use crate::domain::sandwich::{Sandwich, SandwichIngredients, SandwichType};
+
+/// the dictionary shipped with the service, used unless another one is configured
+pub const BUNDLED_DICTIONARY: &str = include_str!("../../resources/ingredient_categories.toml");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum IngredientCategory {
+    Meat,
+    Poultry,
+    Fish,
+    // an animal product that is neither meat nor fish, as cheese or eggs
+    Animal,
+    // free of meat and fish
+    Veggie,
+    // free of any animal product
+    Vegan,
+}
+
+const FLESH: [IngredientCategory; 3] = [IngredientCategory::Meat, IngredientCategory::Poultry, IngredientCategory::Fish];
+const ANIMAL: [IngredientCategory; 4] = [IngredientCategory::Meat, IngredientCategory::Poultry, IngredientCategory::Fish, IngredientCategory::Animal];
+
+/// What to do with a sandwich claimed veggie or vegan while its ingredients are not
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimPolicy {
+    /// keep the claim, the contradiction is reported along with the sandwich
+    #[default]
+    Warn,
+    /// refuse the sandwich
+    Reject,
+}
+
+impl ClaimPolicy {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_lowercase().as_str() {
+            "warn" => Ok(ClaimPolicy::Warn),
+            "reject" => Ok(ClaimPolicy::Reject),
+            _ => Err(format!("Unknown veggie claim policy: {}", name)),
+        }
+    }
+}
+
+/// Classifies sandwiches from their ingredients, after a dictionary of ingredient categories
+pub struct SandwichClassifier {
+    dictionary: PhraseDictionary<IngredientCategory>,
+    veggie_claim: ClaimPolicy,
+}
+
+impl SandwichClassifier {
+    /// read a dictionary listing the meat, poultry, fish and other animal phrases,
+    /// the veggie and vegan ones that would otherwise read as one of them, as "vegan ham", the plant ones,
+    /// and the qualifiers which name no ingredient, as "sliced"
+    pub fn new(dictionary: &str, veggie_claim: ClaimPolicy) -> Result<Self, String> {
+        let dictionary = PhraseDictionary::parse(dictionary, |key| match key {
+            "meat" => Ok(Some(IngredientCategory::Meat)),
+            "poultry" => Ok(Some(IngredientCategory::Poultry)),
+            "fish" => Ok(Some(IngredientCategory::Fish)),
+            "animal" => Ok(Some(IngredientCategory::Animal)),
+            "veggie" => Ok(Some(IngredientCategory::Veggie)),
+            "vegan" | "plant" => Ok(Some(IngredientCategory::Vegan)),
+            "qualifiers" => Ok(None),
+            _ => Err(format!("Unknown ingredient category in the dictionary: {}", key)),
+        })?;
+
+        Ok(SandwichClassifier { dictionary, veggie_claim })
+    }
+
+    /// undefined when an ingredient is unknown to the dictionary, else mixed with both meat or poultry and fish,
+    /// else the one of them found, else vegan when every ingredient is known vegan,
+    /// veggie when every one is known vegan, veggie or another animal product, and undefined otherwise
+    pub fn infer(&self, ingredients: &SandwichIngredients) -> SandwichType {
+        let found: Option<Vec<BTreeSet<IngredientCategory>>> = ingredients.value().iter()
+            .map(|i| self.dictionary.lookup(i.name()))
+            .collect();
+        let Some(found) = found else {
+            return SandwichType::Undefined;
+        };
+        let categories: BTreeSet<IngredientCategory> = found.iter().flatten().copied().collect();
+        let has = |c: IngredientCategory| categories.contains(&c);
+        let each_one_of = |allowed: &[IngredientCategory]| found.iter()
+            .all(|c| !c.is_empty() && c.iter().all(|c| allowed.contains(c)));
+
+        match (has(IngredientCategory::Meat), has(IngredientCategory::Poultry), has(IngredientCategory::Fish)) {
+            (true, _, true) | (_, true, true) => SandwichType::Mixed,
+            (true, _, _) => SandwichType::Meat,
+            (_, true, _) => SandwichType::Poultry,
+            (_, _, true) => SandwichType::Fish,
+            _ if each_one_of(&[IngredientCategory::Vegan]) => SandwichType::Vegan,
+            _ if each_one_of(&[IngredientCategory::Animal, IngredientCategory::Veggie, IngredientCategory::Vegan]) => SandwichType::Veggie,
+            _ => SandwichType::Undefined,
+        }
+    }
+
+    /// why a claimed type cannot be right, as a veggie sandwich listing ham or a vegan one listing cheese.
+    /// The dictionary cannot know every meat, so only the veggie and vegan claims are checked
+    pub fn contradiction(&self, claimed: &SandwichType, ingredients: &SandwichIngredients) -> Option<String> {
+        let forbidden: &[IngredientCategory] = match claimed {
+            SandwichType::Veggie => &FLESH,
+            SandwichType::Vegan => &ANIMAL,
+            _ => return None,
+        };
+
+        let offending: Vec<&str> = ingredients.value().iter()
+            .filter(|i| self.dictionary.find(i.name()).iter().any(|c| forbidden.contains(c)))
+            .map(|i| i.name())
+            .collect();
+
+        match offending.is_empty() {
+            true => None,
+            false => Some(format!("A {} sandwich cannot contain {}", claimed.name().to_lowercase(), offending.join(", "))),
+        }
+    }
+
+    /// the sandwich with its type inferred when undefined,
+    /// or an error when it claims to be veggie or vegan against its ingredients and such claims are rejected
+    pub fn classify(&self, sandwich: Sandwich) -> Result<Sandwich, String> {
+        if sandwich.sandwich_type() == &SandwichType::Undefined {
+            let inferred = self.infer(sandwich.ingredients());
+            return Ok(sandwich.with_sandwich_type(inferred));
+        }
+
+        match (self.contradiction(sandwich.sandwich_type(), sandwich.ingredients()), self.veggie_claim) {
+            (Some(reason), ClaimPolicy::Reject) => Err(reason),
+            _ => Ok(sandwich),
+        }
+    }
+}
+
+static CLASSIFIER: OnceLock<SandwichClassifier> = OnceLock::new();
+
+/// set the classifier used by the service, once at startup
+pub fn install(classifier: SandwichClassifier) -> Result<(), String> {
+    CLASSIFIER.set(classifier)
+        .map_err(|_| String::from("The sandwich classifier is already installed"))
+}
+
+/// the installed classifier, or the bundled dictionary warning about the false claims when none is
+pub fn classifier() -> &'static SandwichClassifier {
+    CLASSIFIER.get_or_init(|| SandwichClassifier::new(BUNDLED_DICTIONARY, ClaimPolicy::default())
+        .expect("the bundled ingredient dictionary is valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandwich(ingredients: Vec<&str>, sandwich_type: SandwichType) -> Sandwich {
+        let ingredients: Vec<String> = ingredients.iter().map(|i| i.to_string()).collect();
+        Sandwich::new(String::from(""), String::from("Test"), ingredients, vec![], sandwich_type, 0).unwrap()
+    }
+
+    #[test]
+    fn should_infer_the_type_from_the_ingredients() {
+        let classifier = SandwichClassifier::new(BUNDLED_DICTIONARY, ClaimPolicy::Warn).unwrap();
+
+        let type_of = |ingredients| classifier.classify(sandwich(ingredients, SandwichType::Undefined)).unwrap().sandwich_type().clone();
+
+        assert_eq!(type_of(vec!["bread", "200 g Smoked Salmon", "cream cheese"]), SandwichType::Fish);
+        assert_eq!(type_of(vec!["bread", "2 slices ham", "butter"]), SandwichType::Meat);
+        assert_eq!(type_of(vec!["bread", "chicken breast", "lettuce"]), SandwichType::Poultry);
+        assert_eq!(type_of(vec!["bread", "tuna", "2 slices ham"]), SandwichType::Mixed);
+        assert_eq!(type_of(vec!["bread", "2 eggs", "tomato"]), SandwichType::Veggie);
+        assert_eq!(type_of(vec!["bread", "vegan ham", "peanut butter"]), SandwichType::Vegan);
+    }
+
+    #[test]
+    fn should_leave_the_type_undefined_with_an_unknown_ingredient() {
+        let classifier = SandwichClassifier::new(BUNDLED_DICTIONARY, ClaimPolicy::Warn).unwrap();
+
+        let type_of = |ingredients| classifier.classify(sandwich(ingredients, SandwichType::Undefined)).unwrap().sandwich_type().clone();
+
+        assert_eq!(type_of(vec!["bread", "biltong"]), SandwichType::Undefined);
+        assert_eq!(type_of(vec!["bread", "tomato", "chorizzo"]), SandwichType::Undefined);
+    }
+
+    #[test]
+    fn should_never_take_a_cheese_or_meat_compound_for_vegan_or_veggie() {
+        let classifier = SandwichClassifier::new(BUNDLED_DICTIONARY, ClaimPolicy::Warn).unwrap();
+
+        let type_of = |ingredients| classifier.infer(sandwich(ingredients, SandwichType::Undefined).ingredients());
+
+        // a known word does not make the whole name known: the caesar dressing has anchovies, pepper jack is a cheese
+        for compound in ["caesar salad", "pepper jack", "breaded chicken", "chicken nuggets"] {
+            let inferred = type_of(vec!["bread", compound]);
+            assert_eq!(inferred, SandwichType::Undefined, "{} read as {:?}", compound, inferred);
+        }
+        assert_eq!(type_of(vec!["bread", "grilled chicken", "sliced tomato"]), SandwichType::Poultry);
+        assert!(classifier.contradiction(&SandwichType::Veggie, sandwich(vec!["bread", "breaded chicken"], SandwichType::Veggie).ingredients()).is_some());
+    }
+
+    #[test]
+    fn should_only_infer_vegan_or_veggie_from_known_ingredients() {
+        // a dictionary without plants knows no vegan ingredient but the substitutes
+        let classifier = SandwichClassifier::new(r#"animal = ["honey"]
+vegan = ["vegan ham"]"#, ClaimPolicy::Warn).unwrap();
+
+        let type_of = |ingredients| classifier.infer(sandwich(ingredients, SandwichType::Undefined).ingredients());
+
+        assert_eq!(type_of(vec!["vegan ham"]), SandwichType::Vegan);
+        assert_eq!(type_of(vec!["vegan ham", "honey"]), SandwichType::Veggie);
+        assert_eq!(type_of(vec!["bread", "honey"]), SandwichType::Undefined);
+        assert_eq!(type_of(vec!["bread"]), SandwichType::Undefined);
+    }
+
+    #[test]
+    fn should_warn_or_reject_a_false_claim() {
+        let ham = || sandwich(vec!["bread", "2 slices ham"], SandwichType::Veggie);
+
+        let warning = SandwichClassifier::new(BUNDLED_DICTIONARY, ClaimPolicy::Warn).unwrap();
+        assert_eq!(warning.contradiction(ham().sandwich_type(), ham().ingredients()), Some(String::from("A veggie sandwich cannot contain ham")));
+        assert_eq!(warning.classify(ham()).unwrap().sandwich_type(), &SandwichType::Veggie);
+
+        let rejecting = SandwichClassifier::new(BUNDLED_DICTIONARY, ClaimPolicy::Reject).unwrap();
+        assert!(rejecting.classify(ham()).is_err());
+        assert!(rejecting.classify(sandwich(vec!["bread", "cheddar"], SandwichType::Vegan)).is_err());
+        assert!(rejecting.classify(sandwich(vec!["bread", "cheddar"], SandwichType::Veggie)).is_ok());
+        // a claimed meat sandwich is trusted, its meat may be missing from the dictionary
+        assert!(rejecting.classify(sandwich(vec!["bread", "biltong"], SandwichType::Meat)).is_ok());
+    }
+}
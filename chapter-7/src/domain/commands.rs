@@ -0,0 +1,101 @@
+use crate::domain::classification::classifier;
+use crate::domain::ingredient_catalog::canonical_names;
+use crate::domain::ingredients::CanonicalNames;
+use crate::domain::sandwich::{PreparationStep, Sandwich, SandwichServings, SandwichTag, SandwichType};
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::suggestions::SuggestionIndex;
+
+/// The fields of a sandwich as sent to create or replace it
+#[derive(Debug, Clone)]
+pub struct SandwichCommand {
+    pub name: String,
+    pub ingredients: Vec<String>,
+    pub steps: Vec<PreparationStep>,
+    pub sandwich_type: SandwichType,
+    pub tags: Vec<SandwichTag>,
+    /// the default servings when not sent
+    pub servings: Option<u32>,
+    /// the version read by the client, checked when replacing
+    pub version: Option<i64>,
+}
+
+impl SandwichCommand {
+    /// the sandwich to write under the received id, empty for a new one
+    pub fn into_sandwich(self, id: &str) -> Result<Sandwich, String> {
+        let servings = self.servings.map(SandwichServings::try_from).transpose()?
+            .unwrap_or_default();
+
+        Ok(Sandwich::new(id.to_string(), self.name, self.ingredients, self.steps, self.sandwich_type, 0)?
+            .with_tags(self.tags)
+            .with_servings(servings)
+            .with_version(self.version))
+    }
+}
+
+/// The driven ports a sandwich write goes through besides the repository
+pub struct SandwichPorts<'a, S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized> {
+    /// kept current with the written sandwiches
+    pub suggestions: &'a S,
+    /// names the written ingredients canonically
+    pub catalog: &'a C,
+}
+
+impl<S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized> SandwichPorts<'_, S, C> {
+    /// what the sandwiches about to be written go through, after the current catalog
+    pub async fn preparation(&self) -> Result<SandwichPreparation, String> {
+        Ok(SandwichPreparation { names: canonical_names(self.catalog).await? })
+    }
+}
+
+/// What every write does to a sandwich just before the repository gets it,
+/// read once from the catalog for all the sandwiches of a write
+pub struct SandwichPreparation {
+    names: CanonicalNames,
+}
+
+impl SandwichPreparation {
+    /// the sandwich with its ingredients under their canonical names,
+    /// then its type inferred from them or its claim checked against them
+    pub fn prepare(&self, sandwich: Sandwich) -> Result<Sandwich, String> {
+        classifier().classify(self.names.normalize(sandwich))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::ingredients::CatalogIngredient;
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::test_utils::shared::stub_sandwich_command;
+
+    use super::*;
+
+    #[test]
+    fn should_build_the_sandwich_of_a_command() {
+        let sandwich = stub_sandwich_command(Some(3)).into_sandwich("").unwrap();
+
+        assert_eq!(sandwich.id().value(), &None);
+        assert_eq!(sandwich.servings().value(), SandwichServings::default().value());
+        assert_eq!(sandwich.version().value(), &Some(3));
+    }
+
+    #[test]
+    fn should_reject_invalid_servings() {
+        let command = SandwichCommand { servings: Some(0), ..stub_sandwich_command(None) };
+
+        assert!(command.into_sandwich("").is_err());
+    }
+
+    #[actix_rt::test]
+    async fn should_name_the_ingredients_canonically_before_typing_the_sandwich() {
+        let catalog = InMemoryIngredientCatalog::new();
+        catalog.upsert(CatalogIngredient::new("ham", vec![String::from("jambon")]).unwrap()).await.unwrap();
+        let ports = SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &catalog };
+
+        let command = SandwichCommand { ingredients: vec![String::from("bread"), String::from("2 slices Jambon")], sandwich_type: SandwichType::Undefined, ..stub_sandwich_command(None) };
+        let sandwich = ports.preparation().await.unwrap().prepare(command.into_sandwich("").unwrap()).unwrap();
+
+        assert_eq!(sandwich.ingredients().names(), vec!["bread", "ham"]);
+        assert_eq!(sandwich.sandwich_type(), &SandwichType::Meat);
+    }
+}
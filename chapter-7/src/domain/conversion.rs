@@ -0,0 +1,178 @@
+use crate::domain::sandwich::{Ingredient, Sandwich, SandwichIngredients, SandwichServings, Unit};
+
+const GRAMS_PER_OUNCE: f64 = 28.349523125;
+const GRAMS_PER_POUND: f64 = 453.59237;
+const MILLILITERS_PER_FLUID_OUNCE: f64 = 29.5735295625;
+const MILLILITERS_PER_CUP: f64 = 236.5882365;
+const FLUID_OUNCES_PER_CUP: f64 = 8.0;
+const OUNCES_PER_POUND: f64 = 16.0;
+const TEASPOONS_PER_TABLESPOON: f64 = 3.0;
+const MILLILITERS_PER_TEASPOON: f64 = 4.92892159375;
+const GRAMS_PER_PINCH: f64 = 0.36;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnitSystem {
+    Metric,
+    Imperial,
+}
+
+/// The recipe for another number of servings, with its units converted to a system if one is given
+pub fn scale_recipe(sandwich: Sandwich, servings: Option<SandwichServings>, system: Option<UnitSystem>) -> Sandwich {
+    let target = servings.unwrap_or(*sandwich.servings());
+    let factor = target.value() as f64 / sandwich.servings().value() as f64;
+
+    let ingredients: Vec<Ingredient> = sandwich.ingredients().value().iter()
+        .map(|i| scale_ingredient(i, factor, system))
+        .collect();
+
+    // as many ingredients as the sandwich has, so never empty
+    match SandwichIngredients::try_from(ingredients) {
+        Ok(ingredients) => sandwich.with_ingredients(ingredients).with_servings(target),
+        Err(_) => sandwich,
+    }
+}
+
+/// Multiply the quantity of an ingredient, convert it and express it in a readable unit and rounding.
+/// Ingredients without a quantity, as "salt", are left as they are
+pub fn scale_ingredient(ingredient: &Ingredient, factor: f64, system: Option<UnitSystem>) -> Ingredient {
+    let quantity = match ingredient.quantity() {
+        Some(q) => q * factor,
+        None => return ingredient.clone(),
+    };
+
+    match ingredient.unit() {
+        None => ingredient.with_quantity(round(quantity, None), None),
+        Some(unit) => {
+            let (quantity, unit) = match system {
+                Some(s) => convert(quantity, unit, s),
+                None => (quantity, unit),
+            };
+            let (quantity, unit) = readable(quantity, unit);
+            ingredient.with_quantity(round(quantity, Some(unit)), Some(unit))
+        }
+    }
+}
+
+/// Convert a quantity to the units of a system, g↔oz and lb, ml↔fl oz and cups.
+/// Spoons, pinches, slices and pieces are common to both systems and kept
+pub fn convert(quantity: f64, unit: Unit, system: UnitSystem) -> (f64, Unit) {
+    match (system, unit) {
+        (UnitSystem::Metric, Unit::Ounce) => (quantity * GRAMS_PER_OUNCE, Unit::Gram),
+        (UnitSystem::Metric, Unit::Pound) => (quantity * GRAMS_PER_POUND, Unit::Gram),
+        (UnitSystem::Metric, Unit::FluidOunce) => (quantity * MILLILITERS_PER_FLUID_OUNCE, Unit::Milliliter),
+        (UnitSystem::Metric, Unit::Cup) => (quantity * MILLILITERS_PER_CUP, Unit::Milliliter),
+        (UnitSystem::Imperial, Unit::Gram) => (quantity / GRAMS_PER_OUNCE, Unit::Ounce),
+        (UnitSystem::Imperial, Unit::Kilogram) => (quantity * 1000.0 / GRAMS_PER_OUNCE, Unit::Ounce),
+        (UnitSystem::Imperial, Unit::Milliliter) => (quantity / MILLILITERS_PER_FLUID_OUNCE, Unit::FluidOunce),
+        (UnitSystem::Imperial, Unit::Centiliter) => (quantity * 10.0 / MILLILITERS_PER_FLUID_OUNCE, Unit::FluidOunce),
+        (UnitSystem::Imperial, Unit::Liter) => (quantity * 1000.0 / MILLILITERS_PER_FLUID_OUNCE, Unit::FluidOunce),
+        _ => (quantity, unit),
+    }
+}
+
+/// The weight of a quantity, volumes being weighed as water.
+/// Slices and pieces weigh what the ingredient does, so they have none here
+pub fn to_grams(quantity: f64, unit: Unit) -> Option<f64> {
+    let grams = match unit {
+        Unit::Gram | Unit::Milliliter => quantity,
+        Unit::Kilogram | Unit::Liter => quantity * 1000.0,
+        Unit::Centiliter => quantity * 10.0,
+        Unit::Ounce => quantity * GRAMS_PER_OUNCE,
+        Unit::Pound => quantity * GRAMS_PER_POUND,
+        Unit::FluidOunce => quantity * MILLILITERS_PER_FLUID_OUNCE,
+        Unit::Cup => quantity * MILLILITERS_PER_CUP,
+        Unit::Teaspoon => quantity * MILLILITERS_PER_TEASPOON,
+        Unit::Tablespoon => quantity * MILLILITERS_PER_TEASPOON * TEASPOONS_PER_TABLESPOON,
+        Unit::Pinch => quantity * GRAMS_PER_PINCH,
+        Unit::Slice | Unit::Piece => return None,
+    };
+    Some(grams)
+}
+
+/// the larger or smaller unit of the same system when the quantity is out of the usual range, as 1.2 kg instead of 1200 g
+fn readable(quantity: f64, unit: Unit) -> (f64, Unit) {
+    match unit {
+        Unit::Gram if quantity >= 1000.0 => (quantity / 1000.0, Unit::Kilogram),
+        Unit::Kilogram if quantity < 1.0 => (quantity * 1000.0, Unit::Gram),
+        Unit::Milliliter if quantity >= 1000.0 => (quantity / 1000.0, Unit::Liter),
+        Unit::Centiliter if quantity >= 100.0 => (quantity / 100.0, Unit::Liter),
+        Unit::Liter if quantity < 1.0 => (quantity * 1000.0, Unit::Milliliter),
+        Unit::Teaspoon if quantity >= TEASPOONS_PER_TABLESPOON => (quantity / TEASPOONS_PER_TABLESPOON, Unit::Tablespoon),
+        Unit::Tablespoon if quantity < 1.0 => (quantity * TEASPOONS_PER_TABLESPOON, Unit::Teaspoon),
+        Unit::Ounce if quantity >= OUNCES_PER_POUND => (quantity / OUNCES_PER_POUND, Unit::Pound),
+        Unit::Pound if quantity < 1.0 => (quantity * OUNCES_PER_POUND, Unit::Ounce),
+        Unit::FluidOunce if quantity >= FLUID_OUNCES_PER_CUP => (quantity / FLUID_OUNCES_PER_CUP, Unit::Cup),
+        _ => (quantity, unit),
+    }
+}
+
+/// Round to what can be measured in a kitchen: grams and milliliters to 0.5, 1 or 5 depending on the amount,
+/// imperial units and spoons to a quarter, slices and pieces to a half, pinches to a whole.
+/// A quantity is never rounded down to nothing
+fn round(quantity: f64, unit: Option<Unit>) -> f64 {
+    let step = match unit {
+        Some(Unit::Gram) | Some(Unit::Milliliter) => match quantity {
+            q if q < 10.0 => 0.5,
+            q if q < 100.0 => 1.0,
+            _ => 5.0,
+        },
+        Some(Unit::Centiliter) => 0.5,
+        Some(Unit::Kilogram) | Some(Unit::Liter) => 0.05,
+        Some(Unit::Ounce) | Some(Unit::Pound) | Some(Unit::FluidOunce) | Some(Unit::Cup)
+        | Some(Unit::Teaspoon) | Some(Unit::Tablespoon) => 0.25,
+        Some(Unit::Slice) | Some(Unit::Piece) | None => 0.5,
+        Some(Unit::Pinch) => 1.0,
+    };
+
+    ((quantity / step).round() * step).max(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::SandwichType;
+
+    use super::*;
+
+    fn scaled(line: &str, factor: f64, system: Option<UnitSystem>) -> String {
+        scale_ingredient(&Ingredient::parse(line).unwrap(), factor, system).to_string()
+    }
+
+    #[test]
+    fn should_scale_and_express_in_a_readable_unit() {
+        assert_eq!(scaled("200 g ham", 3.0, None), "600 g ham");
+        assert_eq!(scaled("600 g ham", 2.0, None), "1.2 kg ham");
+        assert_eq!(scaled("2 tsp mustard", 3.0, None), "2 tbsp mustard");
+        assert_eq!(scaled("1 tbsp butter", 0.5, None), "1.5 tsp butter");
+        assert_eq!(scaled("2 eggs", 1.5, None), "3 eggs");
+        assert_eq!(scaled("salt", 4.0, None), "salt");
+    }
+
+    #[test]
+    fn should_convert_between_systems() {
+        assert_eq!(scaled("100 g cheddar", 1.0, Some(UnitSystem::Imperial)), "3.5 oz cheddar");
+        assert_eq!(scaled("500 g beef", 1.0, Some(UnitSystem::Imperial)), "1 lb beef");
+        assert_eq!(scaled("500 ml milk", 1.0, Some(UnitSystem::Imperial)), "2 cups milk");
+        assert_eq!(scaled("4 fl oz milk", 1.0, Some(UnitSystem::Metric)), "120 ml milk");
+        assert_eq!(scaled("1 cup milk", 1.0, Some(UnitSystem::Metric)), "235 ml milk");
+        assert_eq!(scaled("1 lb beef", 3.0, Some(UnitSystem::Metric)), "1.35 kg beef");
+        assert_eq!(scaled("1 tbsp oil", 1.0, Some(UnitSystem::Metric)), "1 tbsp oil");
+    }
+
+    #[test]
+    fn should_never_round_down_to_nothing() {
+        assert_eq!(scaled("1 pinch salt", 0.25, None), "1 pinch salt");
+        assert_eq!(scaled("1 g pepper", 0.1, None), "0.5 g pepper");
+    }
+
+    #[test]
+    fn should_scale_a_recipe_to_other_servings() {
+        let sandwich = Sandwich::new(String::from(""), String::from("Club"), vec![String::from("4 slices bacon"), String::from("lettuce")], vec![], SandwichType::Meat, 0)
+            .unwrap()
+            .with_servings(SandwichServings::try_from(2).unwrap());
+
+        let scaled = scale_recipe(sandwich, Some(SandwichServings::try_from(3).unwrap()), None);
+
+        assert_eq!(scaled.servings().value(), 3);
+        assert_eq!(scaled.ingredients().lines(), vec!["6 slices bacon", "lettuce"]);
+    }
+}
@@ -0,0 +1,96 @@
+use actix_web::web;
+
+use crate::domain::prices::{CostCeiling, PriceList, SandwichCost};
+use crate::domain::sandwich::Sandwich;
+use crate::driven::prices::{PriceCatalog, PriceCatalogError};
+use crate::driven::repository::{FindSandwich, IngredientFilter, RepoSelectError};
+use crate::Repository;
+
+#[derive(Debug)]
+pub enum CostError {
+    InvalidMaxCost(String),
+    NotFound,
+    Unknown(String),
+}
+
+impl From<PriceCatalogError> for CostError {
+    fn from(e: PriceCatalogError) -> Self {
+        match e {
+            PriceCatalogError::Unknown(m) => CostError::Unknown(m),
+        }
+    }
+}
+
+// this is my port / use case
+/// What the sandwich with the received id costs in the catalog currency, whole and per serving,
+/// with the ingredients that could not be priced
+pub async fn cost_sandwich<T: Repository<Sandwich>, P: PriceCatalog + ?Sized>(repository: web::Data<T>, prices: &P, id: &str) -> Result<SandwichCost, CostError> {
+
+    let s = FindSandwich {
+        id: Some(String::from(id)),
+        name: String::from(""),
+        ingredients: IngredientFilter::default()
+    };
+
+    let sandwich = repository.find_one(s).await
+        .map_err(|e| match e {
+            RepoSelectError::Unknown(e) => CostError::Unknown(format!("Unknown error: {}", e)),
+            RepoSelectError::NotFound => CostError::NotFound
+        })?;
+
+    Ok(price_list(prices).await?.cost(&sandwich))
+}
+
+/// The ceiling keeping the sandwiches fully priced at most at the received cost per serving
+pub async fn cost_ceiling<P: PriceCatalog + ?Sized>(prices: &P, max_per_serving: f64) -> Result<CostCeiling, CostError> {
+
+    if !max_per_serving.is_finite() || max_per_serving < 0.0 {
+        return Err(CostError::InvalidMaxCost(String::from("The maximum cost cannot be negative")));
+    }
+
+    Ok(CostCeiling { max_per_serving, prices: price_list(prices).await? })
+}
+
+async fn price_list<P: PriceCatalog + ?Sized>(prices: &P) -> Result<PriceList, PriceCatalogError> {
+    PriceList::new(prices.currency(), prices.find_all().await?)
+        .map_err(PriceCatalogError::Unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::domain::prices::IngredientPrice;
+    use crate::driven::prices::in_memory_catalog::InMemoryPriceCatalog;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{get_testing_mongodb_config, SANDWICH_ID};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_cost_a_sandwich_in_the_catalog_currency() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        let prices = InMemoryPriceCatalog::new("CHF");
+        prices.upsert(IngredientPrice::new("wurst", 1.2, 1.0, None).unwrap()).await.unwrap();
+
+        let cost = cost_sandwich(Data::new(repo), &prices, SANDWICH_ID).await.unwrap();
+
+        assert_eq!(cost.currency, "CHF");
+        // the stub lists its ingredients without quantities
+        assert_eq!(cost.unquantified_ingredients, vec!["Wurst"]);
+        assert_eq!(cost.unknown_ingredients, vec!["Ketchup"]);
+    }
+
+    #[actix_rt::test]
+    async fn should_not_cost_while_the_repo_returns_error() {
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_error(true);
+        let prices = InMemoryPriceCatalog::new("EUR");
+
+        let result = cost_sandwich(Data::new(repo), &prices, SANDWICH_ID).await;
+
+        assert!(result.is_err());
+        assert!(matches!(cost_ceiling(&prices, -1.0).await, Err(CostError::InvalidMaxCost(_))));
+        assert!(cost_ceiling(&prices, 5.0).await.is_ok());
+    }
+}
@@ -1,8 +1,11 @@
 use actix_web::web;
 
+use crate::domain::commands::{SandwichCommand, SandwichPorts};
 use crate::domain::does_sandwich_exist_by_name;
-use crate::domain::sandwich::{Sandwich, SandwichType};
+use crate::domain::sandwich::Sandwich;
+use crate::driven::ingredients::IngredientCatalog;
 use crate::driven::repository::RepoCreateError;
+use crate::driven::suggestions::SuggestionIndex;
 use crate::Repository;
 
 #[derive(Debug)]
@@ -13,21 +16,27 @@ pub enum CreateError {
 }
 
 // this is my port / use case
-pub async fn create_sandwich<'a, T: Repository<Sandwich>>(repository: web::Data<T>, name: &'a str, ingredients: &'a Vec<&str>, sandwich_type: &SandwichType) -> Result<Sandwich, CreateError> {
+pub async fn create_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized>(repository: web::Data<T>, ports: &SandwichPorts<'_, S, C>, command: SandwichCommand) -> Result<Sandwich, CreateError> {
 
-    let ingredients = ingredients.iter().map(|item| item.to_string()).collect::<Vec<String>>();
-    let sandwich = Sandwich::new(String::from(""), name.to_string(), ingredients, sandwich_type.clone(), 0)
-        .map_err(|e| CreateError::InvalidData(e))?;
+    let sandwich = command.into_sandwich("")
+        .map_err(CreateError::InvalidData)?;
+    let sandwich = ports.preparation().await
+        .map_err(CreateError::Unknown)?
+        .prepare(sandwich)
+        .map_err(CreateError::InvalidData)?;
 
-    if does_sandwich_exist_by_name(&repository, name).await {
+    if does_sandwich_exist_by_name(&repository, sandwich.name().value()).await {
         return Err(CreateError::Conflict(String::from("A sandwich with this name is already present")))
     }
 
-    repository.create(sandwich).await
+    let created = repository.create(sandwich).await
         .map_err(|e| return match e {
             RepoCreateError::InvalidData(e) => CreateError::InvalidData(format!("Invalid data: {}", e)),
             RepoCreateError::Unknown(e) => CreateError::Unknown(format!("Unknown error: {}", e)),
-        })
+        })?;
+
+    ports.suggestions.index(&created).await;
+    Ok(created)
 }
 
 
@@ -35,8 +44,9 @@ pub async fn create_sandwich<'a, T: Repository<Sandwich>>(repository: web::Data<
 mod tests {
 
     use actix_web::web::Data;
-    use crate::helpers::string_vec_to_vec_str;
-    use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, stub_ingredients, assert_on_sandwich, SANDWICH_ID};
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, assert_on_sandwich, SANDWICH_ID, stub_sandwich_command};
     use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
 
     use super::*;
@@ -44,12 +54,9 @@ mod tests {
     #[actix_rt::test]
     async fn should_create_a_sandwich() {
 
-        let ingredients = stub_ingredients();
-        let ingredients = string_vec_to_vec_str(&ingredients);
-
         let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
         repo.set_error(true);
-        let s = create_sandwich(Data::new(repo), SANDWICH_NAME, &ingredients, &SANDWICH_TYPE).await.unwrap();
+        let s = create_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, stub_sandwich_command(None)).await.unwrap();
 
         assert_eq!(s.id().value().is_some(), true);
         assert_on_sandwich(stub_sandwich(false),&s, false);
@@ -58,12 +65,9 @@ mod tests {
     #[actix_rt::test]
     async fn should_not_create_a_sandwich_if_conflicting() {
 
-        let ingredients = stub_ingredients();
-        let ingredients = string_vec_to_vec_str(&ingredients);
-
         let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
         repo.set_error(true);
-        let s = create_sandwich(Data::new(repo), SANDWICH_NAME, &ingredients, &SANDWICH_TYPE).await.unwrap();
+        let s = create_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, stub_sandwich_command(None)).await.unwrap();
 
         assert_eq!(s.id().value().is_some(), true);
         assert_on_sandwich(stub_sandwich(false),&s, false);
@@ -2,31 +2,37 @@ use actix_web::web;
 
 use crate::domain::sandwich::Sandwich;
 use crate::driven::repository::RepoDeleteError;
+use crate::driven::suggestions::SuggestionIndex;
 use crate::Repository;
 
 #[derive(Debug)]
 pub enum DeleteOneError {
     InvalidData(String),
     Unknown(String),
-    NotFound
+    NotFound,
+    PreconditionFailed
 }
 
 // this is my port / use case
-pub async fn delete_one_sandwich<'a, T: Repository<Sandwich>>(repository: web::Data<T>, id: &str) -> Result<(), DeleteOneError> {
+pub async fn delete_one_sandwich<'a, T: Repository<Sandwich>, S: SuggestionIndex + ?Sized>(repository: web::Data<T>, suggestions: &S, id: &str, version: Option<i64>) -> Result<(), DeleteOneError> {
 
-    repository.delete(id).await
+    repository.delete(id, version).await
         .map_err(|e| return match e {
             RepoDeleteError::InvalidData(e) => DeleteOneError::InvalidData(format!("Invalid data: {}", e)),
             RepoDeleteError::Unknown(e) => DeleteOneError::Unknown(format!("Unknown error: {}", e)),
-            RepoDeleteError::NotFound => DeleteOneError::NotFound
-        })
+            RepoDeleteError::NotFound => DeleteOneError::NotFound,
+            RepoDeleteError::VersionMismatch => DeleteOneError::PreconditionFailed
+        })?;
+
+    suggestions.remove(id).await;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
 
     use actix_web::web::Data;
-    use crate::helpers::string_vec_to_vec_str;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
     use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, stub_ingredients, assert_on_sandwich, SANDWICH_ID};
     use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
 
@@ -39,7 +45,7 @@ mod tests {
         let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
 
         // WHEN I delete the hot dog
-        let deleted = delete_one_sandwich(Data::new(repo), SANDWICH_ID).await;
+        let deleted = delete_one_sandwich(Data::new(repo), &InMemorySuggestionIndex::new(), SANDWICH_ID, Some(1)).await;
 
         // THEN Ok is returned
         assert_eq!(true, deleted.is_ok());
@@ -54,7 +60,7 @@ mod tests {
         repo.set_error(true);
 
         // WHEN I delete the hot dog
-        let deleted = delete_one_sandwich(Data::new(repo), SANDWICH_ID).await;
+        let deleted = delete_one_sandwich(Data::new(repo), &InMemorySuggestionIndex::new(), SANDWICH_ID, Some(1)).await;
 
         // THEN Err is returned
         assert_eq!(true, deleted.is_err());
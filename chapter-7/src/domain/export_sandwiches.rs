@@ -0,0 +1,58 @@
+use actix_web::web;
+use futures::stream::{BoxStream, StreamExt};
+
+use crate::domain::sandwich::Sandwich;
+use crate::driven::repository::RepoFindAllError;
+use crate::Repository;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Unknown(String)
+}
+
+// this is my port / use case
+/// Every stored sandwich, read from the repository as the stream is consumed
+pub async fn export_sandwiches<T: Repository<Sandwich>>(repository: web::Data<T>) -> Result<BoxStream<'static, Result<Sandwich, ExportError>>, ExportError> {
+
+    let sandwiches = repository.stream_all().await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(s) => ExportError::Unknown(s)
+        })?;
+
+    Ok(sandwiches
+        .map(|res| res.map_err(|e| match e {
+            RepoFindAllError::Unknown(s) => ExportError::Unknown(s)
+        }))
+        .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+    use futures::TryStreamExt;
+
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::get_testing_mongodb_config;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_stream_every_sandwich() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let sandwiches: Vec<Sandwich> = export_sandwiches(Data::new(repo)).await.unwrap()
+            .try_collect().await.unwrap();
+
+        assert_eq!(sandwiches.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn should_fail_to_open_the_stream() {
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_error(true);
+
+        let res = export_sandwiches(Data::new(repo)).await;
+
+        assert!(matches!(res, Err(ExportError::Unknown(_))));
+    }
+}
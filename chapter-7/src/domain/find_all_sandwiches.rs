@@ -1,7 +1,12 @@
 use actix_web::web;
 
+use crate::domain::allergens::Allergen;
+use crate::domain::ingredient_catalog::canonical_names;
+use crate::domain::ingredients::CanonicalNames;
+use crate::domain::prices::CostCeiling;
 use crate::domain::sandwich::Sandwich;
-use crate::driven::repository::{FindSandwich, RepoFindAllError};
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::repository::{FindSandwich, IngredientFilter, RepoFindAllError};
 use crate::Repository;
 
 #[derive(Debug)]
@@ -10,30 +15,65 @@ pub enum FindAllError {
 }
 
 // this is my port / use case
-pub async fn find_all_sandwiches<'a, T: Repository<Sandwich>>(repository: web::Data<T>, name: &'a str, ingredients: &'a Vec<&str>) -> Result<Vec<Sandwich>, FindAllError> {
+/// Sandwiches with the received name, if not empty, whose ingredients satisfy the filter
+/// and contain none of the excluded allergens, staying under the cost ceiling if any.
+/// The filtered ingredients are named after the catalog, as the stored ones are
+pub async fn find_all_sandwiches<T: Repository<Sandwich>, C: IngredientCatalog + ?Sized>(repository: web::Data<T>, catalog: &C, name: &str, ingredients: IngredientFilter, exclude_allergens: &[Allergen], max_cost: Option<&CostCeiling>) -> Result<Vec<Sandwich>, FindAllError> {
 
-    let ingredients = ingredients.iter().map(|item| item.to_string()).collect::<Vec<String>>();
+    // the catalog is only read when there are ingredients to name
+    let names = match ingredients.is_empty() {
+        true => CanonicalNames::new(&[]),
+        false => canonical_names(catalog).await,
+    }.map_err(FindAllError::Unknown)?;
 
     let s = FindSandwich {
         id: None,
         name: String::from(name),
-        ingredients
+        ingredients: IngredientFilter {
+            all_of: canonical(&names, ingredients.all_of),
+            any_of: canonical(&names, ingredients.any_of),
+            none_of: canonical(&names, ingredients.none_of),
+        }
     };
 
-    repository.find_all(s).await
+    let mut sandwiches = repository.find_all(s).await
         .map_err(|e| return match e {
             RepoFindAllError::Unknown(s) => FindAllError::Unknown(s)
-        })
+        })?;
+
+    // allergens are derived from the ingredients, so they are filtered here rather than by the repository.
+    // A sandwich with an ingredient unknown to the allergen table cannot be told free of them, it is left out
+    if !exclude_allergens.is_empty() {
+        sandwiches.retain(|s| s.allergens().free_of(exclude_allergens));
+    }
+
+    if let Some(ceiling) = max_cost {
+        sandwiches.retain(|s| ceiling.allows(s));
+    }
+
+    Ok(sandwiches)
+}
+
+fn canonical(names: &CanonicalNames, ingredients: Vec<String>) -> Vec<String> {
+    ingredients.iter()
+        .map(|i| i.trim())
+        .filter(|i| !i.is_empty())
+        .map(|i| names.canonical(i).unwrap_or(i).to_string())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
 
     use actix_web::web::Data;
-    use crate::helpers::string_vec_to_vec_str;
     use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, stub_ingredients, assert_on_sandwich, SANDWICH_ID};
     use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
 
+    use crate::domain::ingredients::CatalogIngredient;
+    use crate::domain::prices::{IngredientPrice, PriceList};
+    use crate::domain::sandwich::Unit;
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+
     use super::*;
 
     #[actix_rt::test]
@@ -41,13 +81,56 @@ mod tests {
 
         let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
 
-        let sand_list = find_all_sandwiches(Data::new(repo), "", &vec![]).await.unwrap();
+        let sand_list = find_all_sandwiches(Data::new(repo), &InMemoryIngredientCatalog::new(), "", IngredientFilter::default(), &[], None).await.unwrap();
 
         assert_eq!(sand_list.len(), 2);
         assert_on_sandwich(stub_sandwich(false),&sand_list[0], false);
         assert_on_sandwich(stub_cheeseburger(),&sand_list[1], false);
     }
 
+    #[actix_rt::test]
+    async fn should_filter_on_the_catalog_names_of_the_ingredients() {
+
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_filters_ingredients(true);
+        let catalog = InMemoryIngredientCatalog::new();
+        catalog.upsert(CatalogIngredient::new("Ketchup", vec![String::from("tomato ketchup")]).unwrap()).await.unwrap();
+        let repo = Data::new(repo);
+
+        let with_ketchup = find_all_sandwiches(repo.clone(), &catalog, "", IngredientFilter::all_of(vec![String::from(" Tomato Ketchup ")]), &[], None).await.unwrap();
+        let without_ketchup = find_all_sandwiches(repo, &catalog, "", IngredientFilter { none_of: vec![String::from("tomato ketchup")], ..IngredientFilter::default() }, &[], None).await.unwrap();
+
+        assert_eq!(with_ketchup.len(), 2);
+        assert!(without_ketchup.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn should_leave_out_the_sandwiches_with_excluded_allergens() {
+
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let sand_list = find_all_sandwiches(Data::new(repo), &InMemoryIngredientCatalog::new(), "", IngredientFilter::default(), &[Allergen::Dairy], None).await.unwrap();
+
+        assert_eq!(sand_list.len(), 1);
+        assert_eq!(sand_list[0].name().value(), SANDWICH_NAME);
+    }
+
+    #[actix_rt::test]
+    async fn should_leave_out_the_sandwiches_not_priced_under_the_max_cost() {
+
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        let prices = PriceList::new("EUR", vec![
+            IngredientPrice::new("Wurst", 1.2, 1.0, None).unwrap(),
+            IngredientPrice::new("Ketchup", 3.2, 500.0, Some(Unit::Milliliter)).unwrap(),
+        ]).unwrap();
+        let ceiling = CostCeiling { max_per_serving: 100.0, prices };
+
+        // the stubs list their ingredients without quantities, so none of them can be proven to stay under
+        let sand_list = find_all_sandwiches(Data::new(repo), &InMemoryIngredientCatalog::new(), "", IngredientFilter::default(), &[], Some(&ceiling)).await.unwrap();
+
+        assert!(sand_list.is_empty());
+    }
+
     #[actix_rt::test]
     async fn should_not_find_all_sandwiches_while_the_repo_returns_error() {
 
@@ -57,7 +140,7 @@ mod tests {
         repo.set_error(true);
 
         // WHEN I fetch the sandwiches
-        match find_all_sandwiches(Data::new(repo), "", &vec![]).await {
+        match find_all_sandwiches(Data::new(repo), &InMemoryIngredientCatalog::new(), "", IngredientFilter::default(), &[], None).await {
             // THEN Err is returned
             Err(_) => {},
             Ok(_) => unreachable!()
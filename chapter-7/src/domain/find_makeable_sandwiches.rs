@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use actix_web::web;
+
+use crate::domain::ingredients::ingredient_key;
+use crate::domain::sandwich::Sandwich;
+use crate::driven::repository::RepoFindAllError;
+use crate::helpers::empty_find_sandwich;
+use crate::Repository;
+
+pub const DEFAULT_MAX_MISSING: usize = 2;
+
+#[derive(Debug)]
+pub enum MakeableError {
+    EmptyPantry,
+    Unknown(String),
+}
+
+/// A sandwich that cannot be made with the pantry, and what it lacks
+#[derive(Debug, Clone)]
+pub struct NearMiss {
+    pub sandwich: Sandwich,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct MakeableSandwiches {
+    pub makeable: Vec<Sandwich>,
+    pub near_misses: Vec<NearMiss>,
+}
+
+// this is my port / use case
+/// Sandwiches whose ingredients are all in the pantry, and those lacking at most `max_missing` of them,
+/// the fewest missing first. Ingredients are compared case and accent insensitively
+pub async fn find_makeable_sandwiches<T: Repository<Sandwich>>(repository: web::Data<T>, pantry: &[String], max_missing: Option<usize>) -> Result<MakeableSandwiches, MakeableError> {
+
+    let available: HashSet<String> = pantry.iter()
+        .map(|i| ingredient_key(i))
+        .filter(|i| !i.is_empty())
+        .collect();
+
+    if available.is_empty() {
+        return Err(MakeableError::EmptyPantry);
+    }
+
+    // the repositories match whole names only, which would leave out "Crème fraîche" for "creme fraiche",
+    // so every sandwich is compared with the pantry here
+    let candidates = repository.find_all(empty_find_sandwich()).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => MakeableError::Unknown(format!("Unknown error: {}", e)),
+        })?;
+
+    let max_missing = max_missing.unwrap_or(DEFAULT_MAX_MISSING);
+    let mut result = MakeableSandwiches::default();
+
+    for sandwich in candidates {
+        // quantities do not matter, only the names are looked for in the pantry
+        let missing: Vec<String> = sandwich.ingredients().value().iter()
+            .filter(|i| !available.contains(&ingredient_key(i.name())))
+            .map(|i| i.to_string())
+            .collect();
+
+        if missing.is_empty() {
+            result.makeable.push(sandwich);
+        } else if missing.len() <= max_missing && missing.len() < sandwich.ingredients().value().len() {
+            result.near_misses.push(NearMiss { sandwich, missing });
+        }
+    }
+
+    result.makeable.sort_by(|a, b| a.name().value().cmp(b.name().value()));
+    result.near_misses.sort_by(|a, b| a.missing.len().cmp(&b.missing.len())
+        .then_with(|| a.sandwich.name().value().cmp(b.sandwich.name().value())));
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{CHEESEBURGER_NAME, get_testing_mongodb_config, SANDWICH_NAME};
+
+    use super::*;
+
+    fn pantry(items: &[&str]) -> Vec<String> {
+        items.iter().map(|i| i.to_string()).collect()
+    }
+
+    #[actix_rt::test]
+    async fn should_find_makeable_sandwiches_and_near_misses() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let found = find_makeable_sandwiches(Data::new(repo), &pantry(&["WURST", "ketchup", "Cheese", "Mayo"]), None).await.unwrap();
+
+        assert_eq!(found.makeable.len(), 1);
+        assert_eq!(found.makeable[0].name().value(), SANDWICH_NAME);
+        assert_eq!(found.near_misses.len(), 1);
+        assert_eq!(found.near_misses[0].sandwich.name().value(), CHEESEBURGER_NAME);
+        assert_eq!(found.near_misses[0].missing, vec![String::from("ground meat")]);
+    }
+
+    #[actix_rt::test]
+    async fn should_leave_out_sandwiches_missing_too_much() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let found = find_makeable_sandwiches(Data::new(repo), &pantry(&["ketchup"]), Some(1)).await.unwrap();
+
+        assert_eq!(found.makeable.len(), 0);
+        assert_eq!(found.near_misses.len(), 1);
+        assert_eq!(found.near_misses[0].sandwich.name().value(), SANDWICH_NAME);
+    }
+
+    #[actix_rt::test]
+    async fn should_match_the_pantry_regardless_of_accents_and_punctuation() {
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_filters_ingredients(true);
+
+        let found = find_makeable_sandwiches(Data::new(repo), &pantry(&["wurst!", "KETCHÜP"]), None).await.unwrap();
+
+        assert_eq!(found.makeable.len(), 1);
+        assert_eq!(found.makeable[0].name().value(), SANDWICH_NAME);
+    }
+
+    #[actix_rt::test]
+    async fn should_reject_an_empty_pantry() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let res = find_makeable_sandwiches(Data::new(repo), &pantry(&[" "]), None).await;
+
+        assert!(matches!(res, Err(MakeableError::EmptyPantry)));
+    }
+}
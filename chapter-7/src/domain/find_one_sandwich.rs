@@ -1,7 +1,7 @@
 use actix_web::web;
 
 use crate::domain::sandwich::Sandwich;
-use crate::driven::repository::{FindSandwich, RepoSelectError};
+use crate::driven::repository::{FindSandwich, IngredientFilter, RepoSelectError};
 use crate::Repository;
 
 #[derive(Debug)]
@@ -18,7 +18,7 @@ pub async fn find_one_sandwich<'a, T: Repository<Sandwich>>(repository: web::Dat
     let s = FindSandwich {
         id: Some(String::from(id)),
         name: String::from(name),
-        ingredients
+        ingredients: IngredientFilter::all_of(ingredients)
     };
 
     repository.find_one(s).await
@@ -32,7 +32,6 @@ pub async fn find_one_sandwich<'a, T: Repository<Sandwich>>(repository: web::Dat
 mod tests {
 
     use actix_web::web::Data;
-    use crate::helpers::string_vec_to_vec_str;
     use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, stub_ingredients, assert_on_sandwich, SANDWICH_ID};
     use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
 
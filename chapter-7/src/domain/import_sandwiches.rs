@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+
+use actix_web::web;
+
+use crate::domain::commands::SandwichPorts;
+use crate::domain::sandwich::{Sandwich, SandwichServings, SandwichType};
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::repository::{RepoCreateError, RepoFindAllError, RepoUpdateError};
+use crate::driven::suggestions::SuggestionIndex;
+use crate::Repository;
+
+#[derive(Debug)]
+pub enum ImportError {
+    Empty,
+    Conflict(String),
+    Unknown(String),
+}
+
+/// What to do with an imported sandwich whose name is already present
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    Fail,
+}
+
+/// A sandwich read from an import, not yet validated
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    pub name: String,
+    pub ingredients: Vec<String>,
+    pub sandwich_type: SandwichType,
+    /// when missing, the default servings for a new sandwich, the stored ones for an overwritten one
+    pub servings: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportOutcome {
+    Created,
+    Overwritten,
+}
+
+#[derive(Debug, Clone)]
+pub struct AcceptedRow {
+    pub row: usize,
+    pub id: String,
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct RejectedRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub accepted: Vec<AcceptedRow>,
+    pub rejected: Vec<RejectedRow>,
+}
+
+// this is my port / use case
+/// Import the received rows, numbered from 1. Unreadable and invalid rows are rejected,
+/// rows conflicting by name are handled according to the policy; with `Fail` nothing is written
+pub async fn import_sandwiches<T: Repository<Sandwich>, S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized>(repository: web::Data<T>, ports: &SandwichPorts<'_, S, C>, rows: Vec<Result<ImportRow, String>>, policy: ConflictPolicy) -> Result<ImportReport, ImportError> {
+
+    if rows.is_empty() {
+        return Err(ImportError::Empty);
+    }
+
+    let preparation = ports.preparation().await
+        .map_err(ImportError::Unknown)?;
+
+    // only the sandwiches named like a row are read, instead of the whole catalog
+    let names: Vec<String> = rows.iter()
+        .filter_map(|r| r.as_ref().ok().map(|r| r.name.clone()))
+        .collect();
+    let existing: HashMap<String, Sandwich> = repository.find_many_by_names(names).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => ImportError::Unknown(format!("Unknown error: {}", e)),
+        })?
+        .into_iter()
+        .map(|s| (s.name().value().to_string(), s))
+        .collect();
+
+    let mut report = ImportReport::default();
+    let mut seen_names = HashSet::new();
+    let mut conflicts = vec![];
+    let mut creates = vec![];
+    let mut overwrites = vec![];
+
+    for (index, row) in rows.into_iter().enumerate() {
+        let row_number = index + 1;
+
+        let sandwich = match row.and_then(|r| {
+            let servings = r.servings.map(SandwichServings::try_from).transpose()?;
+            Sandwich::new(String::from(""), r.name, r.ingredients, vec![], r.sandwich_type, 0)
+                .and_then(|s| preparation.prepare(s))
+                .map(|s| (s, servings))
+        }) {
+            Ok(s) => s,
+            Err(reason) => {
+                report.rejected.push(RejectedRow { row: row_number, reason });
+                continue;
+            }
+        };
+
+        let (sandwich, servings) = sandwich;
+        let name = sandwich.name().value().to_string();
+        if !seen_names.insert(name.clone()) {
+            report.rejected.push(RejectedRow { row: row_number, reason: String::from("The name is duplicated in the import") });
+            continue;
+        }
+
+        match (existing.get(&name), &policy) {
+            (None, _) => creates.push((row_number, sandwich.with_servings(servings.unwrap_or_default()))),
+            (Some(_), ConflictPolicy::Skip) =>
+                report.rejected.push(RejectedRow { row: row_number, reason: String::from("A sandwich with this name is already present") }),
+            (Some(found), ConflictPolicy::Overwrite) => {
+                // the imported content replaces the stored one whatever its version,
+                // the catalog has no preparation steps nor tags so the stored ones are kept
+                let overwriting = Sandwich::new(found.id().value().clone().unwrap_or_default(),
+                                                name,
+                                                sandwich.ingredients().value().clone(),
+                                                found.steps().value().clone(),
+                                                sandwich.sandwich_type().clone(),
+                                                found.stars().value())
+                    .map_err(ImportError::Unknown)?
+                    .with_tags(found.tags().clone())
+                    .with_servings(servings.unwrap_or(*found.servings()));
+                overwrites.push((row_number, overwriting));
+            }
+            (Some(_), ConflictPolicy::Fail) => conflicts.push(name),
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(ImportError::Conflict(format!("Sandwiches already present: {}", conflicts.join(", "))));
+    }
+
+    let (row_numbers, sandwiches): (Vec<usize>, Vec<Sandwich>) = creates.into_iter().unzip();
+    for (row, res) in row_numbers.into_iter().zip(repository.create_many(sandwiches).await) {
+        match res {
+            Ok(s) => {
+                ports.suggestions.index(&s).await;
+                report.accepted.push(AcceptedRow { row, id: s.id().value().clone().unwrap_or_default(), outcome: ImportOutcome::Created })
+            }
+            Err(RepoCreateError::InvalidData(reason)) | Err(RepoCreateError::Unknown(reason)) =>
+                report.rejected.push(RejectedRow { row, reason }),
+        }
+    }
+
+    let (row_numbers, sandwiches): (Vec<usize>, Vec<Sandwich>) = overwrites.into_iter().unzip();
+    for (row, res) in row_numbers.into_iter().zip(repository.update_many(sandwiches).await) {
+        match res {
+            Ok(s) => {
+                ports.suggestions.index(&s).await;
+                report.accepted.push(AcceptedRow { row, id: s.id().value().clone().unwrap_or_default(), outcome: ImportOutcome::Overwritten })
+            }
+            Err(RepoUpdateError::NotFound) =>
+                report.rejected.push(RejectedRow { row, reason: String::from("The sandwich to overwrite has been deleted") }),
+            Err(RepoUpdateError::VersionMismatch) =>
+                report.rejected.push(RejectedRow { row, reason: String::from("The sandwich to overwrite has been modified") }),
+            Err(RepoUpdateError::InvalidData(reason)) | Err(RepoUpdateError::Unknown(reason)) =>
+                report.rejected.push(RejectedRow { row, reason }),
+        }
+    }
+
+    report.accepted.sort_by_key(|r| r.row);
+    report.rejected.sort_by_key(|r| r.row);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{get_testing_mongodb_config, SANDWICH_NAME, SANDWICH_TYPE, stub_ingredients};
+
+    use super::*;
+
+    fn rows() -> Vec<Result<ImportRow, String>> {
+        vec![
+            Ok(ImportRow { name: String::from("Club sandwich"), ingredients: stub_ingredients(), sandwich_type: SANDWICH_TYPE, servings: None }),
+            Ok(ImportRow { name: String::from(SANDWICH_NAME), ingredients: stub_ingredients(), sandwich_type: SANDWICH_TYPE, servings: None }),
+            Ok(ImportRow { name: String::from("No ingredients"), ingredients: vec![], sandwich_type: SANDWICH_TYPE, servings: None }),
+            Err(String::from("unreadable row")),
+        ]
+    }
+
+    #[actix_rt::test]
+    async fn should_skip_conflicting_rows() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let report = import_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, rows(), ConflictPolicy::Skip).await.unwrap();
+
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.accepted[0].row, 1);
+        assert_eq!(report.accepted[0].outcome, ImportOutcome::Created);
+        assert_eq!(report.rejected.iter().map(|r| r.row).collect::<Vec<usize>>(), vec![2, 3, 4]);
+    }
+
+    #[actix_rt::test]
+    async fn should_overwrite_conflicting_rows() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let report = import_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, rows(), ConflictPolicy::Overwrite).await.unwrap();
+
+        assert_eq!(report.accepted.len(), 2);
+        assert_eq!(report.accepted[1].row, 2);
+        assert_eq!(report.accepted[1].outcome, ImportOutcome::Overwritten);
+    }
+
+    #[actix_rt::test]
+    async fn should_fail_on_conflicting_rows() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let res = import_sandwiches(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, rows(), ConflictPolicy::Fail).await;
+
+        assert!(matches!(res, Err(ImportError::Conflict(_))));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::domain::ingredients::{CanonicalNames, CatalogIngredient};
+use crate::driven::ingredients::{IngredientCatalog, IngredientCatalogError};
+
+#[derive(Debug)]
+pub enum IngredientError {
+    InvalidData(String),
+    Conflict(String),
+    NotFound,
+    Unknown(String),
+}
+
+impl From<IngredientCatalogError> for IngredientError {
+    fn from(e: IngredientCatalogError) -> Self {
+        match e {
+            IngredientCatalogError::Unknown(m) => IngredientError::Unknown(m),
+        }
+    }
+}
+
+// this is my port / use case
+/// All the catalog ingredients, sorted by name
+pub async fn find_ingredients<C: IngredientCatalog + ?Sized>(catalog: &C) -> Result<Vec<CatalogIngredient>, IngredientError> {
+    Ok(catalog.find_all().await?)
+}
+
+// this is my port / use case
+/// The catalog ingredient known under the received name, canonical or synonym
+pub async fn find_ingredient<C: IngredientCatalog + ?Sized>(catalog: &C, name: &str) -> Result<CatalogIngredient, IngredientError> {
+    catalog.find_one(name).await?
+        .ok_or(IngredientError::NotFound)
+}
+
+// this is my port / use case
+/// Set the synonyms of a canonical ingredient name, returning it with whether it was not in the catalog before.
+/// The stored sandwiches keep their names until they are saved again or normalized
+pub async fn set_ingredient<C: IngredientCatalog + ?Sized>(catalog: &C, name: &str, synonyms: Vec<String>) -> Result<(CatalogIngredient, bool), IngredientError> {
+
+    let ingredient = CatalogIngredient::new(name, synonyms)
+        .map_err(IngredientError::InvalidData)?;
+
+    // the names of the other ingredients must stay theirs
+    let mut ingredients = catalog.find_all().await?;
+    ingredients.retain(|i| i.key() != ingredient.key());
+    ingredients.push(ingredient.clone());
+    CanonicalNames::new(&ingredients)
+        .map_err(IngredientError::Conflict)?;
+
+    let created = catalog.upsert(ingredient.clone()).await?;
+    Ok((ingredient, created))
+}
+
+// this is my port / use case
+/// Remove an ingredient from the catalog by its canonical name
+pub async fn delete_ingredient<C: IngredientCatalog + ?Sized>(catalog: &C, name: &str) -> Result<(), IngredientError> {
+    match catalog.delete(name).await? {
+        true => Ok(()),
+        false => Err(IngredientError::NotFound),
+    }
+}
+
+/// The canonical names of the catalog, used to normalize the ingredients of the sandwiches
+pub async fn canonical_names<C: IngredientCatalog + ?Sized>(catalog: &C) -> Result<CanonicalNames, String> {
+    let ingredients = catalog.find_all().await
+        .map_err(|e| match e {
+            IngredientCatalogError::Unknown(m) => m,
+        })?;
+    CanonicalNames::new(&ingredients)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_manage_the_catalog() {
+        let catalog = InMemoryIngredientCatalog::new();
+
+        let (ketchup, created) = set_ingredient(&catalog, "ketchup", vec![String::from("tomato ketchup")]).await.unwrap();
+        assert!(created);
+        assert_eq!(find_ingredient(&catalog, "Tomato Ketchup").await.unwrap(), ketchup);
+
+        // a synonym of ketchup cannot become another ingredient, nor one of its synonyms
+        assert!(matches!(set_ingredient(&catalog, "tomato ketchup", vec![]).await, Err(IngredientError::Conflict(_))));
+        assert!(matches!(set_ingredient(&catalog, "tomato", vec![String::from("ketchup")]).await, Err(IngredientError::Conflict(_))));
+        assert!(matches!(set_ingredient(&catalog, "", vec![]).await, Err(IngredientError::InvalidData(_))));
+
+        // while ketchup can take its synonyms back
+        let (_, created) = set_ingredient(&catalog, "Ketchup", vec![]).await.unwrap();
+        assert!(!created);
+        assert!(set_ingredient(&catalog, "tomato", vec![String::from("tomato ketchup")]).await.is_ok());
+
+        delete_ingredient(&catalog, "ketchup").await.unwrap();
+        assert!(matches!(delete_ingredient(&catalog, "ketchup").await, Err(IngredientError::NotFound)));
+        assert_eq!(find_ingredients(&catalog).await.unwrap().len(), 1);
+    }
+}
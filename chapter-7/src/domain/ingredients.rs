@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::domain::sandwich::{Sandwich, SandwichIngredients};
+use crate::domain::search::tokenize;
+
+/// An ingredient of the catalog, with its canonical name and the other names it is known under,
+/// as "ketchup" for "tomato ketchup"
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogIngredient {
+    name: String,
+    synonyms: Vec<String>,
+}
+
+impl CatalogIngredient {
+    pub fn new(name: &str, synonyms: Vec<String>) -> Result<Self, String> {
+        let name = name.trim();
+        if tokenize(name).is_empty() {
+            return Err(String::from("Any catalog ingredient must have a name"));
+        }
+
+        // the spellings of the canonical name or of another synonym are redundant
+        let mut keys = vec![ingredient_key(name)];
+        let mut distinct = vec![];
+        for synonym in synonyms.iter().map(|s| s.trim()) {
+            let key = ingredient_key(synonym);
+            if key.is_empty() {
+                return Err(format!("The synonyms of {} cannot be empty", name));
+            }
+            if !keys.contains(&key) {
+                keys.push(key);
+                distinct.push(synonym.to_string());
+            }
+        }
+
+        Ok(CatalogIngredient { name: name.to_string(), synonyms: distinct })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn synonyms(&self) -> &Vec<String> {
+        &self.synonyms
+    }
+
+    /// the key of the ingredient in a catalog, so that "Ketchup" and "ketchup" share one
+    pub fn key(&self) -> String {
+        ingredient_key(&self.name)
+    }
+
+    /// the keys of its canonical name and synonyms
+    fn keys(&self) -> Vec<String> {
+        std::iter::once(&self.name)
+            .chain(self.synonyms.iter())
+            .map(|n| ingredient_key(n))
+            .collect()
+    }
+}
+
+/// the key of an ingredient name, case, accent and punctuation insensitive
+pub fn ingredient_key(name: &str) -> String {
+    tokenize(name).join(" ")
+}
+
+/// The canonical name of each name of the catalog ingredients
+pub struct CanonicalNames {
+    names: HashMap<String, String>,
+}
+
+impl CanonicalNames {
+    /// the names of the catalog, an error when one of them belongs to two ingredients
+    pub fn new(ingredients: &[CatalogIngredient]) -> Result<Self, String> {
+        let mut names: HashMap<String, String> = HashMap::new();
+
+        for ingredient in ingredients {
+            for key in ingredient.keys() {
+                match names.get(&key) {
+                    Some(canonical) if canonical != ingredient.name() =>
+                        return Err(format!("{} cannot be a name of both {} and {}", key, canonical, ingredient.name())),
+                    _ => names.insert(key, ingredient.name().to_string()),
+                };
+            }
+        }
+
+        Ok(CanonicalNames { names })
+    }
+
+    /// the canonical name of an ingredient name, none when the catalog does not know it
+    pub fn canonical(&self, name: &str) -> Option<&str> {
+        self.names.get(&ingredient_key(name)).map(String::as_str)
+    }
+
+    /// the sandwich with its ingredients under their canonical names, keeping their quantities and notes
+    pub fn normalize(&self, sandwich: Sandwich) -> Sandwich {
+        let ingredients = sandwich.ingredients().value().iter()
+            .map(|i| match self.canonical(i.name()) {
+                Some(canonical) if canonical != i.name() => i.with_name(canonical),
+                _ => i.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        match SandwichIngredients::try_from(ingredients) {
+            Ok(ingredients) => sandwich.with_ingredients(ingredients),
+            Err(_) => sandwich,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::SandwichType;
+
+    use super::*;
+
+    fn ketchup() -> CatalogIngredient {
+        CatalogIngredient::new("ketchup", vec![String::from("Tomato Ketchup"), String::from("catsup"), String::from("KETCHUP")]).unwrap()
+    }
+
+    #[test]
+    fn should_normalize_the_synonyms_to_the_canonical_name() {
+        let names = CanonicalNames::new(&[ketchup()]).unwrap();
+        let ingredients = vec![String::from("Wurst"), String::from("2 tbsp Tomato Ketchup (spicy)"), String::from("Ketchup")];
+        let sandwich = Sandwich::new(String::from(""), String::from("Hot dog"), ingredients, vec![], SandwichType::Meat, 0).unwrap();
+
+        let normalized = names.normalize(sandwich);
+
+        assert_eq!(normalized.ingredients().lines(), vec!["Wurst", "2 tbsp ketchup (spicy)", "ketchup"]);
+        assert_eq!(ketchup().synonyms(), &vec![String::from("Tomato Ketchup"), String::from("catsup")]);
+    }
+
+    #[test]
+    fn should_not_give_a_name_to_two_ingredients() {
+        let tomato = CatalogIngredient::new("tomato", vec![String::from("tomato ketchup")]).unwrap();
+
+        assert!(CanonicalNames::new(&[ketchup(), tomato]).is_err());
+        assert!(CatalogIngredient::new(" ", vec![]).is_err());
+        assert!(CatalogIngredient::new("ketchup", vec![String::from("")]).is_err());
+    }
+}
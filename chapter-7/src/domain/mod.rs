@@ -1,14 +1,35 @@
 use actix_web::web;
 
 use crate::{Repository, Sandwich};
-use crate::driven::repository::FindSandwich;
+use crate::driven::repository::{FindSandwich, IngredientFilter};
 
 pub mod sandwich;
+pub mod commands;
 pub mod create_sandwich;
 pub mod delete_one_sandwich;
 pub mod find_all_sandwiches;
 pub mod find_one_sandwich;
 pub mod update_sandwich;
+pub mod patch_sandwich;
+pub mod batch_sandwiches;
+pub mod import_sandwiches;
+pub mod export_sandwiches;
+pub mod search;
+pub mod search_sandwiches;
+pub mod autocomplete;
+pub mod find_makeable_sandwiches;
+pub mod conversion;
+pub mod allergens;
+pub mod phrases;
+pub mod classification;
+pub mod backfill_sandwich_types;
+pub mod nutrition;
+pub mod prices;
+pub mod price_catalog;
+pub mod cost_sandwich;
+pub mod ingredients;
+pub mod ingredient_catalog;
+pub mod normalize_ingredients;
 
 pub trait Entity {}
 
@@ -17,7 +38,7 @@ async fn does_sandwich_exist_by_name<'a, T: Repository<Sandwich>>(repository: &w
     let s = FindSandwich {
         id: None,
         name: String::from(name),
-        ingredients: vec![]
+        ingredients: IngredientFilter::default()
     };
 
     repository.find_one(s).await.is_ok()
@@ -28,7 +49,7 @@ async fn does_sandwich_exist<'a, T: Repository<Sandwich>>(repository: &web::Data
     let s = FindSandwich {
         id: Some(String::from(id)),
         name: String::from(""),
-        ingredients: vec![]
+        ingredients: IngredientFilter::default()
     };
 
     repository.find_one(s).await.is_ok()
@@ -0,0 +1,88 @@
+use actix_web::web;
+
+use crate::domain::ingredient_catalog::canonical_names;
+use crate::domain::sandwich::Sandwich;
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::repository::{RepoFindAllError, RepoUpdateError};
+use crate::helpers::empty_find_sandwich;
+use crate::Repository;
+
+#[derive(Debug)]
+pub enum NormalizeError {
+    Unknown(String),
+}
+
+// this is my port / use case
+/// Rename the ingredients of the stored sandwiches after the catalog, returning how many sandwiches were changed.
+/// Each one is written only if unchanged since it was read, so the normalization can be run again to finish the job
+pub async fn normalize_ingredients<T: Repository<Sandwich>, C: IngredientCatalog + ?Sized>(repository: web::Data<T>, catalog: &C) -> Result<usize, NormalizeError> {
+
+    let names = canonical_names(catalog).await
+        .map_err(NormalizeError::Unknown)?;
+
+    let sandwiches = repository.find_all(empty_find_sandwich()).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => NormalizeError::Unknown(e)
+        })?;
+
+    let renamed: Vec<Sandwich> = sandwiches.into_iter()
+        .filter_map(|s| {
+            let before = s.ingredients().names();
+            let normalized = names.normalize(s);
+            (normalized.ingredients().names() != before).then_some(normalized)
+        })
+        .collect();
+
+    let mut updated = 0;
+    let mut failures = vec![];
+    for result in repository.update_many(renamed).await {
+        match result {
+            Ok(_) => updated += 1,
+            Err(RepoUpdateError::VersionMismatch) => failures.push(String::from("changed while normalized")),
+            Err(RepoUpdateError::NotFound) => failures.push(String::from("deleted while normalized")),
+            Err(RepoUpdateError::InvalidData(e)) | Err(RepoUpdateError::Unknown(e)) => failures.push(e),
+        }
+    }
+
+    match failures.is_empty() {
+        true => Ok(updated),
+        false => Err(NormalizeError::Unknown(format!("{} sandwiches normalized, {} failed: {}", updated, failures.len(), failures.join(", ")))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::domain::ingredients::CatalogIngredient;
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::get_testing_mongodb_config;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_rename_only_the_sandwiches_with_another_name() {
+
+        let repo = Data::new(SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap());
+        let catalog = InMemoryIngredientCatalog::new();
+
+        assert_eq!(normalize_ingredients(repo.clone(), &catalog).await.unwrap(), 0);
+
+        // the hot dog lists Ketchup and the cheeseburger ketchup
+        catalog.upsert(CatalogIngredient::new("ketchup", vec![]).unwrap()).await.unwrap();
+
+        assert_eq!(normalize_ingredients(repo, &catalog).await.unwrap(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn should_not_normalize_while_the_repo_returns_error() {
+
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_error(true);
+
+        let result = normalize_ingredients(Data::new(repo), &InMemoryIngredientCatalog::new()).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::ops::{Add, Div};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::domain::conversion::to_grams;
+use crate::domain::sandwich::{Ingredient, Sandwich, Unit};
+use crate::domain::search::{lookup_keys, tokenize};
+
+/// the table shipped with the service, values per 100 g
+const BUNDLED_TABLE: &str = include_str!("../../resources/nutrition.csv");
+
+/// Energy and macronutrients, in kcal and grams
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Nutrients {
+    pub calories_kcal: f64,
+    pub protein_g: f64,
+    pub fat_g: f64,
+    pub carbs_g: f64,
+}
+
+impl Nutrients {
+    fn scale(&self, factor: f64) -> Self {
+        Nutrients {
+            calories_kcal: self.calories_kcal * factor,
+            protein_g: self.protein_g * factor,
+            fat_g: self.fat_g * factor,
+            carbs_g: self.carbs_g * factor,
+        }
+    }
+
+    /// to the tenth, an estimate is not more precise than that
+    fn rounded(&self) -> Self {
+        let round = |v: f64| (v * 10.0).round() / 10.0;
+        Nutrients {
+            calories_kcal: round(self.calories_kcal),
+            protein_g: round(self.protein_g),
+            fat_g: round(self.fat_g),
+            carbs_g: round(self.carbs_g),
+        }
+    }
+}
+
+impl Add for Nutrients {
+    type Output = Nutrients;
+
+    fn add(self, other: Nutrients) -> Nutrients {
+        Nutrients {
+            calories_kcal: self.calories_kcal + other.calories_kcal,
+            protein_g: self.protein_g + other.protein_g,
+            fat_g: self.fat_g + other.fat_g,
+            carbs_g: self.carbs_g + other.carbs_g,
+        }
+    }
+}
+
+impl Div<f64> for Nutrients {
+    type Output = Nutrients;
+
+    fn div(self, divisor: f64) -> Nutrients {
+        self.scale(1.0 / divisor)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct NutritionRow {
+    ingredient: String,
+    kcal: f64,
+    protein: f64,
+    fat: f64,
+    carbs: f64,
+    // weight of a slice and of a piece, or of one when counted as "2 eggs"
+    slice_grams: Option<f64>,
+    piece_grams: Option<f64>,
+}
+
+impl NutritionRow {
+    fn per_100_grams(&self) -> Nutrients {
+        Nutrients { calories_kcal: self.kcal, protein_g: self.protein, fat_g: self.fat, carbs_g: self.carbs }
+    }
+
+    fn grams_of(&self, ingredient: &Ingredient) -> Option<f64> {
+        match (ingredient.quantity(), ingredient.unit()) {
+            (Some(q), Some(Unit::Slice)) => self.slice_grams.map(|g| q * g),
+            (Some(q), Some(Unit::Piece)) | (Some(q), None) => self.piece_grams.map(|g| q * g),
+            (Some(q), Some(unit)) => to_grams(q, unit),
+            (None, _) => None,
+        }
+    }
+}
+
+/// The nutrients of the ingredients, per 100 g
+pub struct NutritionTable {
+    rows: HashMap<String, NutritionRow>,
+}
+
+impl NutritionTable {
+    /// read a CSV table with an ingredient, kcal, protein, fat, carbs, slice_grams and piece_grams column
+    pub fn parse(table: &str) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(table.as_bytes());
+
+        let mut rows = HashMap::new();
+        for (index, row) in reader.deserialize::<NutritionRow>().enumerate() {
+            let row = row.map_err(|e| format!("Invalid nutrition table row {}: {}", index + 1, e))?;
+            rows.insert(tokenize(&row.ingredient).join(" "), row);
+        }
+
+        Ok(NutritionTable { rows })
+    }
+
+    fn row_of(&self, name: &str) -> Option<&NutritionRow> {
+        lookup_keys(name).iter().find_map(|k| self.rows.get(k))
+    }
+
+    /// the nutrients of a whole sandwich and of one of its servings, estimated from the ingredients
+    /// the table knows with a quantity that can be weighed
+    pub fn estimate(&self, sandwich: &Sandwich) -> NutritionEstimate {
+        let mut total = Nutrients::default();
+        let mut unknown_ingredients = vec![];
+        let mut unquantified_ingredients = vec![];
+
+        for ingredient in sandwich.ingredients().value() {
+            let row = match self.row_of(ingredient.name()) {
+                Some(r) => r,
+                None => {
+                    unknown_ingredients.push(ingredient.name().to_string());
+                    continue;
+                }
+            };
+
+            match row.grams_of(ingredient) {
+                Some(grams) => total = total + row.per_100_grams().scale(grams / 100.0),
+                None => unquantified_ingredients.push(ingredient.name().to_string()),
+            }
+        }
+
+        let servings = sandwich.servings().value();
+        NutritionEstimate {
+            servings,
+            total: total.rounded(),
+            per_serving: (total / servings as f64).rounded(),
+            unknown_ingredients,
+            unquantified_ingredients,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NutritionEstimate {
+    pub servings: u32,
+    pub total: Nutrients,
+    pub per_serving: Nutrients,
+    /// ingredients missing from the table, left out of the estimate
+    pub unknown_ingredients: Vec<String>,
+    /// ingredients without a quantity, or counted in slices or pieces the table cannot weigh, left out too
+    pub unquantified_ingredients: Vec<String>,
+}
+
+/// the table shipped with the service
+pub fn bundled_table() -> &'static NutritionTable {
+    static TABLE: OnceLock<NutritionTable> = OnceLock::new();
+    TABLE.get_or_init(|| NutritionTable::parse(BUNDLED_TABLE).expect("the bundled nutrition table is valid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::{SandwichServings, SandwichType};
+
+    use super::*;
+
+    fn sandwich(ingredients: Vec<&str>, servings: u32) -> Sandwich {
+        let ingredients: Vec<String> = ingredients.iter().map(|i| i.to_string()).collect();
+        Sandwich::new(String::from(""), String::from("Test"), ingredients, vec![], SandwichType::Undefined, 0)
+            .unwrap()
+            .with_servings(SandwichServings::try_from(servings).unwrap())
+    }
+
+    #[test]
+    fn should_estimate_the_nutrients_from_the_quantities() {
+        let estimate = bundled_table().estimate(&sandwich(vec!["2 slices bread", "100 g Smoked Salmon", "2 tbsp cream cheese"], 2));
+
+        // 60 g of bread, 100 g of salmon and about 30 g of cream cheese
+        assert_eq!(estimate.total, Nutrients { calories_kcal: 377.1, protein_g: 25.2, fat_g: 16.3, carbs_g: 30.6 });
+        assert_eq!(estimate.per_serving.calories_kcal, 188.6);
+        assert!(estimate.unknown_ingredients.is_empty());
+    }
+
+    #[test]
+    fn should_list_the_ingredients_left_out() {
+        let estimate = bundled_table().estimate(&sandwich(vec!["3 eggs", "lettuce", "50 g unobtainium"], 1));
+
+        assert_eq!(estimate.total.calories_kcal, 232.5);
+        assert_eq!(estimate.unknown_ingredients, vec!["unobtainium"]);
+        assert_eq!(estimate.unquantified_ingredients, vec!["lettuce"]);
+    }
+
+    #[test]
+    fn should_not_take_an_ingredient_for_its_last_word() {
+        let estimate = bundled_table().estimate(&sandwich(vec!["100 g wild salmon", "20 g almond butter"], 1));
+
+        assert_eq!(estimate.total.calories_kcal, 208.0);
+        assert_eq!(estimate.unknown_ingredients, vec!["almond butter"]);
+    }
+}
@@ -0,0 +1,161 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::commands::SandwichPorts;
+use crate::domain::does_sandwich_exist_by_name;
+use crate::domain::sandwich::{PreparationStep, Sandwich, SandwichServings, SandwichTag, SandwichType};
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::repository::{FindSandwich, IngredientFilter, RepoSelectError, RepoUpdateError};
+use crate::driven::suggestions::SuggestionIndex;
+use crate::Repository;
+
+#[derive(Debug)]
+pub enum PatchError {
+    InvalidPatch(String),
+    InvalidData(String),
+    Unknown(String),
+    NotFound,
+    Conflict(String),
+    PreconditionFailed,
+}
+
+/// The editable fields of a sandwich, on which a patch is applied
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SandwichDraft {
+    pub name: String,
+    pub ingredients: Vec<String>,
+    pub steps: Vec<PreparationStep>,
+    pub sandwich_type: SandwichType,
+    pub tags: Vec<SandwichTag>,
+    pub servings: u32,
+}
+
+impl From<&Sandwich> for SandwichDraft {
+    fn from(s: &Sandwich) -> Self {
+        SandwichDraft {
+            name: s.name().value().to_string(),
+            ingredients: s.ingredients().lines(),
+            steps: s.steps().value().clone(),
+            sandwich_type: s.sandwich_type().clone(),
+            tags: s.tags().clone(),
+            servings: s.servings().value(),
+        }
+    }
+}
+
+// this is my port / use case
+pub async fn patch_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized, F>(repository: web::Data<T>, ports: &SandwichPorts<'_, S, C>, id: &str, version: Option<i64>, patch: F) -> Result<Sandwich, PatchError>
+    where F: FnOnce(SandwichDraft) -> Result<SandwichDraft, String> {
+
+    if id.is_empty() {
+        return Err(PatchError::InvalidData(String::from("Cannot patch without a target id")));
+    }
+
+    let find = FindSandwich {
+        id: Some(String::from(id)),
+        name: String::from(""),
+        ingredients: IngredientFilter::default()
+    };
+
+    let current = repository.find_one(find).await
+        .map_err(|e| match e {
+            RepoSelectError::NotFound => PatchError::NotFound,
+            RepoSelectError::Unknown(e) => PatchError::Unknown(format!("Unknown error: {}", e)),
+        })?;
+
+    if version.is_some() && current.version().value().is_some() && &version != current.version().value() {
+        return Err(PatchError::PreconditionFailed);
+    }
+
+    let draft = patch(SandwichDraft::from(&current))
+        .map_err(PatchError::InvalidPatch)?;
+
+    // the patched sandwich must satisfy the same rules of a new one, and it is written
+    // only if nobody changed it since it was read
+    let servings = SandwichServings::try_from(draft.servings)
+        .map_err(PatchError::InvalidData)?;
+    let sandwich = Sandwich::new(String::from(id), draft.name, draft.ingredients, draft.steps, draft.sandwich_type, current.stars().value())
+        .map_err(PatchError::InvalidData)?
+        .with_tags(draft.tags)
+        .with_servings(servings)
+        .with_version(*current.version().value());
+    let sandwich = ports.preparation().await
+        .map_err(PatchError::Unknown)?
+        .prepare(sandwich)
+        .map_err(PatchError::InvalidData)?;
+
+    if sandwich.name() != current.name() && does_sandwich_exist_by_name(&repository, sandwich.name().value()).await {
+        return Err(PatchError::Conflict(String::from("A sandwich with this name is already present")));
+    }
+
+    let patched = repository.update(sandwich).await
+        .map_err(|e| match e {
+            RepoUpdateError::InvalidData(e) => PatchError::InvalidData(format!("Invalid data: {}", e)),
+            RepoUpdateError::NotFound => PatchError::NotFound,
+            RepoUpdateError::VersionMismatch => PatchError::PreconditionFailed,
+            RepoUpdateError::Unknown(e) => PatchError::Unknown(format!("Unknown error: {}", e)),
+        })?;
+
+    ports.suggestions.index(&patched).await;
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{get_testing_mongodb_config, SANDWICH_ID, SANDWICH_NAME};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_patch_an_existing_sandwich() {
+
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let s = patch_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, SANDWICH_ID, None, |mut draft| {
+            draft.ingredients.push(String::from("Mustard"));
+            draft.sandwich_type = SandwichType::Veggie;
+            Ok(draft)
+        }).await.unwrap();
+
+        assert_eq!(s.id().value().as_ref().unwrap(), SANDWICH_ID);
+        assert_eq!(s.name().value(), SANDWICH_NAME);
+        assert_eq!(&s.ingredients().lines(), &vec![String::from("Wurst"), String::from("Ketchup"), String::from("Mustard")]);
+        assert_eq!(s.sandwich_type(), &SandwichType::Veggie);
+    }
+
+    #[actix_rt::test]
+    async fn should_not_patch_into_an_invalid_sandwich() {
+
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let res = patch_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, SANDWICH_ID, None, |mut draft| {
+            draft.ingredients.clear();
+            Ok(draft)
+        }).await;
+
+        match res {
+            Err(PatchError::InvalidData(_)) => {},
+            _ => unreachable!()
+        }
+    }
+
+    #[actix_rt::test]
+    async fn should_not_patch_a_non_existing_sandwich() {
+
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_error(true);
+
+        let res = patch_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, SANDWICH_ID, None, Ok).await;
+
+        match res {
+            Err(_) => {},
+            Ok(_) => unreachable!()
+        }
+    }
+}
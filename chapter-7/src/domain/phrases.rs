@@ -0,0 +1,115 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::domain::search::tokenize;
+
+/// Phrases of ingredient names mapped to values, as the allergens or the category of an ingredient.
+/// A name is read word by word, at each word the longest known phrase is taken,
+/// so that "peanut butter" is not read as "butter"
+pub struct PhraseDictionary<V> {
+    phrases: HashMap<Vec<String>, BTreeSet<V>>,
+    longest: usize,
+}
+
+impl<V: Ord + Copy> PhraseDictionary<V> {
+    /// read a TOML table listing the phrases under each key, the key being turned into a value,
+    /// or into none for the phrases which have to be known but carry no value
+    pub fn parse<F>(table: &str, value_of: F) -> Result<Self, String>
+        where F: Fn(&str) -> Result<Option<V>, String>
+    {
+        let entries: HashMap<String, Vec<String>> = toml::from_str(table)
+            .map_err(|e| format!("Invalid phrase table: {}", e))?;
+
+        let mut phrases: HashMap<Vec<String>, BTreeSet<V>> = HashMap::new();
+        for (key, listed) in entries {
+            let value = value_of(&key)?;
+
+            for phrase in listed {
+                let values = phrases.entry(tokenize(&phrase)).or_default();
+                values.extend(value);
+            }
+        }
+        phrases.remove(&vec![]);
+
+        let longest = phrases.keys().map(Vec::len).max().unwrap_or(0);
+        Ok(PhraseDictionary { phrases, longest })
+    }
+
+    /// the values of the phrases found in a name, even when some of its words are unknown
+    pub fn find(&self, name: &str) -> BTreeSet<V> {
+        self.scan(name).0
+    }
+
+    /// the values of the phrases a name is made of, none when one of its words is not part of a phrase of the dictionary:
+    /// the name is unknown, which is not the same as known to carry no value.
+    /// "caesar salad" is unknown, as knowing the salad tells nothing of the caesar
+    pub fn lookup(&self, name: &str) -> Option<BTreeSet<V>> {
+        let (found, covered) = self.scan(name);
+        covered.then_some(found)
+    }
+
+    /// the values of the phrases found in a name, and whether every word of the name is part of one of them
+    fn scan(&self, name: &str) -> (BTreeSet<V>, bool) {
+        let words = tokenize(name);
+        let mut found = BTreeSet::new();
+        let mut covered = !words.is_empty();
+
+        let mut start = 0;
+        while start < words.len() {
+            let longest = self.longest.min(words.len() - start);
+            let matched = (1..=longest).rev()
+                .find_map(|length| self.phrases.get(&words[start..start + length]).map(|v| (length, v)));
+
+            match matched {
+                Some((length, values)) => {
+                    found.extend(values.iter().copied());
+                    start += length;
+                }
+                None => {
+                    covered = false;
+                    start += 1;
+                }
+            }
+        }
+
+        (found, covered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary() -> PhraseDictionary<&'static str> {
+        PhraseDictionary::parse(r#"meat = ["chicken", "ham"]
+animal = ["cheese"]
+plant = ["bread", "salad", "pepper", "peanut butter"]
+qualifiers = ["sliced"]"#, |key| Ok(match key {
+            "qualifiers" => None,
+            "meat" => Some("meat"),
+            "animal" => Some("animal"),
+            _ => Some("plant"),
+        })).unwrap()
+    }
+
+    #[test]
+    fn should_know_a_name_made_of_known_phrases() {
+        let dictionary = dictionary();
+
+        assert_eq!(dictionary.lookup("Sliced Ham"), Some(BTreeSet::from(["meat"])));
+        assert_eq!(dictionary.lookup("peanut butter"), Some(BTreeSet::from(["plant"])));
+        assert_eq!(dictionary.lookup("sliced"), Some(BTreeSet::new()));
+    }
+
+    #[test]
+    fn should_not_know_a_name_with_an_unknown_word() {
+        let dictionary = dictionary();
+
+        assert_eq!(dictionary.lookup("breaded chicken"), None);
+        assert_eq!(dictionary.lookup("chicken nuggets"), None);
+        assert_eq!(dictionary.lookup("caesar salad"), None);
+        assert_eq!(dictionary.lookup("pepper jack"), None);
+        assert_eq!(dictionary.lookup(""), None);
+        // what is found is still told, as a claim is checked against any meat named
+        assert_eq!(dictionary.find("breaded chicken"), BTreeSet::from(["meat"]));
+    }
+}
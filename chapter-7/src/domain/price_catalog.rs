@@ -0,0 +1,73 @@
+use crate::domain::prices::IngredientPrice;
+use crate::domain::sandwich::Unit;
+use crate::driven::prices::{PriceCatalog, PriceCatalogError};
+
+#[derive(Debug)]
+pub enum PriceError {
+    InvalidData(String),
+    NotFound,
+    Unknown(String),
+}
+
+impl From<PriceCatalogError> for PriceError {
+    fn from(e: PriceCatalogError) -> Self {
+        match e {
+            PriceCatalogError::Unknown(m) => PriceError::Unknown(m),
+        }
+    }
+}
+
+// this is my port / use case
+/// All the ingredient prices, sorted by ingredient
+pub async fn find_prices<P: PriceCatalog + ?Sized>(prices: &P) -> Result<Vec<IngredientPrice>, PriceError> {
+    Ok(prices.find_all().await?)
+}
+
+// this is my port / use case
+/// The price of an ingredient, case and accent insensitive
+pub async fn find_price<P: PriceCatalog + ?Sized>(prices: &P, ingredient: &str) -> Result<IngredientPrice, PriceError> {
+    prices.find_one(ingredient).await?
+        .ok_or(PriceError::NotFound)
+}
+
+// this is my port / use case
+/// Set the price paid for a quantity of an ingredient, returning it with whether the ingredient had no price before
+pub async fn set_price<P: PriceCatalog + ?Sized>(prices: &P, ingredient: &str, price: f64, quantity: f64, unit: Option<Unit>) -> Result<(IngredientPrice, bool), PriceError> {
+
+    let price = IngredientPrice::new(ingredient, price, quantity, unit)
+        .map_err(PriceError::InvalidData)?;
+
+    let created = prices.upsert(price.clone()).await?;
+    Ok((price, created))
+}
+
+// this is my port / use case
+/// Forget the price of an ingredient
+pub async fn delete_price<P: PriceCatalog + ?Sized>(prices: &P, ingredient: &str) -> Result<(), PriceError> {
+    match prices.delete(ingredient).await? {
+        true => Ok(()),
+        false => Err(PriceError::NotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driven::prices::in_memory_catalog::InMemoryPriceCatalog;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_manage_the_prices() {
+        let prices = InMemoryPriceCatalog::new("EUR");
+
+        let (ham, created) = set_price(&prices, "Ham", 14.9, 1.0, Some(Unit::Kilogram)).await.unwrap();
+        assert!(created);
+        assert_eq!(find_price(&prices, "ham").await.unwrap(), ham);
+
+        assert!(matches!(set_price(&prices, "ham", -1.0, 1.0, None).await, Err(PriceError::InvalidData(_))));
+
+        delete_price(&prices, "HAM").await.unwrap();
+        assert!(matches!(delete_price(&prices, "ham").await, Err(PriceError::NotFound)));
+        assert!(find_prices(&prices).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::domain::conversion::to_grams;
+use crate::domain::sandwich::{Ingredient, Sandwich, Unit};
+use crate::domain::search::{lookup_keys, tokenize};
+
+/// The price of an ingredient, paid for a quantity of it, as 12.50 for 1 kg of ham or 0.30 for 1 egg
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngredientPrice {
+    ingredient: String,
+    price: f64,
+    quantity: f64,
+    // none when counted, as eggs
+    unit: Option<Unit>,
+}
+
+impl IngredientPrice {
+    pub fn new(ingredient: &str, price: f64, quantity: f64, unit: Option<Unit>) -> Result<Self, String> {
+        let ingredient = ingredient.trim();
+        if tokenize(ingredient).is_empty() {
+            return Err(String::from("Any price must have an ingredient"));
+        }
+
+        if !price.is_finite() || price < 0.0 {
+            return Err(format!("The price of {} cannot be negative", ingredient));
+        }
+
+        if !quantity.is_finite() || quantity <= 0.0 {
+            return Err(format!("The price of {} must be for a positive quantity", ingredient));
+        }
+
+        Ok(IngredientPrice { ingredient: ingredient.to_string(), price, quantity, unit })
+    }
+
+    pub fn ingredient(&self) -> &str {
+        &self.ingredient
+    }
+
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    pub fn quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    pub fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+
+    /// the key of the price in a catalog, so that "Smoked Salmon" and "smoked salmon" share one
+    pub fn key(&self) -> String {
+        price_key(&self.ingredient)
+    }
+
+    /// the cost of the quantity of an ingredient, none when it cannot be compared with the priced one,
+    /// as slices of an ingredient priced by the kilo
+    fn cost_of(&self, ingredient: &Ingredient) -> Option<f64> {
+        let quantity = ingredient.quantity()?;
+
+        let ratio = match (self.unit, ingredient.unit()) {
+            (None, None) | (None, Some(Unit::Piece)) | (Some(Unit::Piece), None) => quantity / self.quantity,
+            (Some(priced), Some(used)) if priced == used => quantity / self.quantity,
+            (Some(priced), Some(used)) => to_grams(quantity, used)? / to_grams(self.quantity, priced)?,
+            _ => return None,
+        };
+
+        Some(self.price * ratio)
+    }
+}
+
+/// the key of the price of an ingredient
+pub fn price_key(ingredient: &str) -> String {
+    tokenize(ingredient).join(" ")
+}
+
+/// What a sandwich costs, from the ingredients with a known price and a quantity it applies to
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandwichCost {
+    pub currency: String,
+    pub servings: u32,
+    pub total: f64,
+    pub per_serving: f64,
+    /// ingredients without a price, left out of the cost
+    pub unknown_ingredients: Vec<String>,
+    /// ingredients without a quantity, or with one the price does not apply to, left out too
+    pub unquantified_ingredients: Vec<String>,
+}
+
+impl SandwichCost {
+    /// whether every ingredient was priced, so that the cost is not underestimated
+    pub fn is_complete(&self) -> bool {
+        self.unknown_ingredients.is_empty() && self.unquantified_ingredients.is_empty()
+    }
+}
+
+/// The prices of a catalog, in its currency
+pub struct PriceList {
+    currency: String,
+    prices: HashMap<String, IngredientPrice>,
+}
+
+impl PriceList {
+    /// a price list, refused when an ingredient is priced twice as it is not known which price is right
+    pub fn new(currency: &str, prices: Vec<IngredientPrice>) -> Result<Self, String> {
+        let mut by_key = HashMap::new();
+        for price in prices {
+            if let Some(previous) = by_key.insert(price.key(), price) {
+                return Err(format!("The price of {} is given twice", previous.ingredient()));
+            }
+        }
+
+        Ok(PriceList { currency: currency.to_string(), prices: by_key })
+    }
+
+    fn price_of(&self, name: &str) -> Option<&IngredientPrice> {
+        lookup_keys(name).iter().find_map(|k| self.prices.get(k))
+    }
+
+    /// the cost of a whole sandwich and of one of its servings, rounded to the cent
+    pub fn cost(&self, sandwich: &Sandwich) -> SandwichCost {
+        let mut total = 0.0;
+        let mut unknown_ingredients = vec![];
+        let mut unquantified_ingredients = vec![];
+
+        for ingredient in sandwich.ingredients().value() {
+            let price = match self.price_of(ingredient.name()) {
+                Some(p) => p,
+                None => {
+                    unknown_ingredients.push(ingredient.name().to_string());
+                    continue;
+                }
+            };
+
+            match price.cost_of(ingredient) {
+                Some(cost) => total += cost,
+                None => unquantified_ingredients.push(ingredient.name().to_string()),
+            }
+        }
+
+        let servings = sandwich.servings().value();
+        SandwichCost {
+            currency: self.currency.clone(),
+            servings,
+            total: to_cents(total),
+            per_serving: to_cents(total / servings as f64),
+            unknown_ingredients,
+            unquantified_ingredients,
+        }
+    }
+}
+
+/// A maximum cost per serving, that the sandwiches not fully priced cannot be proven to stay under
+pub struct CostCeiling {
+    pub max_per_serving: f64,
+    pub prices: PriceList,
+}
+
+impl CostCeiling {
+    pub fn allows(&self, sandwich: &Sandwich) -> bool {
+        let cost = self.prices.cost(sandwich);
+        cost.is_complete() && cost.per_serving <= self.max_per_serving
+    }
+}
+
+fn to_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::{SandwichServings, SandwichType};
+
+    use super::*;
+
+    fn sandwich(ingredients: Vec<&str>, servings: u32) -> Sandwich {
+        let ingredients: Vec<String> = ingredients.iter().map(|i| i.to_string()).collect();
+        Sandwich::new(String::from(""), String::from("Test"), ingredients, vec![], SandwichType::Undefined, 0)
+            .unwrap()
+            .with_servings(SandwichServings::try_from(servings).unwrap())
+    }
+
+    fn prices() -> PriceList {
+        PriceList::new("EUR", vec![
+            IngredientPrice::new("bread", 0.15, 1.0, Some(Unit::Slice)).unwrap(),
+            IngredientPrice::new("smoked salmon", 4.5, 100.0, Some(Unit::Gram)).unwrap(),
+            IngredientPrice::new("egg", 1.8, 6.0, None).unwrap(),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn should_price_a_sandwich_per_serving() {
+        let cost = prices().cost(&sandwich(vec!["2 slices bread", "0.2 lb Smoked Salmon", "3 eggs"], 2));
+
+        // 0.30 for the bread, 4.08 for the salmon and 0.90 for the eggs
+        assert_eq!(cost.total, 5.28);
+        assert_eq!(cost.per_serving, 2.64);
+        assert!(cost.is_complete());
+    }
+
+    #[test]
+    fn should_list_the_ingredients_left_out() {
+        let cost = prices().cost(&sandwich(vec!["1 slice smoked salmon", "butter", "bread"], 1));
+
+        assert_eq!(cost.total, 0.0);
+        assert_eq!(cost.unknown_ingredients, vec!["butter"]);
+        assert_eq!(cost.unquantified_ingredients, vec!["smoked salmon", "bread"]);
+
+        let ceiling = CostCeiling { max_per_serving: 10.0, prices: prices() };
+        assert!(!ceiling.allows(&sandwich(vec!["1 slice smoked salmon", "butter", "bread"], 1)));
+        assert!(ceiling.allows(&sandwich(vec!["2 slices bread", "2 eggs"], 1)));
+    }
+
+    #[test]
+    fn should_not_price_an_ingredient_for_nothing() {
+        assert!(IngredientPrice::new(" ", 1.0, 1.0, None).is_err());
+        assert!(IngredientPrice::new("ham", -1.0, 1.0, None).is_err());
+        assert!(IngredientPrice::new("ham", 1.0, 0.0, Some(Unit::Kilogram)).is_err());
+    }
+
+    #[test]
+    fn should_not_price_an_ingredient_twice() {
+        let prices = PriceList::new("EUR", vec![
+            IngredientPrice::new("Bread", 3.2, 1.0, Some(Unit::Kilogram)).unwrap(),
+            IngredientPrice::new("bread", 0.15, 1.0, Some(Unit::Slice)).unwrap(),
+        ]);
+
+        assert_eq!(prices.err(), Some(String::from("The price of Bread is given twice")));
+    }
+}
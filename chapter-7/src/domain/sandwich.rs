@@ -2,6 +2,7 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+use crate::domain::allergens::{bundled_table, SandwichAllergens};
 use crate::domain::Entity;
 
 // Sandwich Id
@@ -48,36 +49,455 @@ impl TryFrom<String> for SandwichName {
     }
 }
 
+// Ingredient Unit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Unit {
+    #[serde(rename = "g")]
+    Gram,
+    #[serde(rename = "kg")]
+    Kilogram,
+    #[serde(rename = "ml")]
+    Milliliter,
+    #[serde(rename = "cl")]
+    Centiliter,
+    #[serde(rename = "l")]
+    Liter,
+    #[serde(rename = "tsp")]
+    Teaspoon,
+    #[serde(rename = "tbsp")]
+    Tablespoon,
+    #[serde(rename = "cup")]
+    Cup,
+    #[serde(rename = "oz")]
+    Ounce,
+    #[serde(rename = "fl oz")]
+    FluidOunce,
+    #[serde(rename = "lb")]
+    Pound,
+    #[serde(rename = "slice")]
+    Slice,
+    #[serde(rename = "piece")]
+    Piece,
+    #[serde(rename = "pinch")]
+    Pinch,
+}
+
+impl Unit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Milliliter => "ml",
+            Unit::Centiliter => "cl",
+            Unit::Liter => "l",
+            Unit::Teaspoon => "tsp",
+            Unit::Tablespoon => "tbsp",
+            Unit::Cup => "cup",
+            Unit::Ounce => "oz",
+            Unit::FluidOunce => "fl oz",
+            Unit::Pound => "lb",
+            Unit::Slice => "slice",
+            Unit::Piece => "piece",
+            Unit::Pinch => "pinch",
+        }
+    }
+
+    /// recognize a unit by its symbol, name or plural, case insensitive
+    pub fn parse(word: &str) -> Option<Self> {
+        match word.to_lowercase().trim_end_matches('.') {
+            "g" | "gr" | "gram" | "grams" | "gramme" | "grammes" => Some(Unit::Gram),
+            "kg" | "kilo" | "kilos" | "kilogram" | "kilograms" => Some(Unit::Kilogram),
+            "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => Some(Unit::Milliliter),
+            "cl" | "centiliter" | "centiliters" | "centilitre" | "centilitres" => Some(Unit::Centiliter),
+            "l" | "liter" | "liters" | "litre" | "litres" => Some(Unit::Liter),
+            "tsp" | "teaspoon" | "teaspoons" => Some(Unit::Teaspoon),
+            "tbsp" | "tablespoon" | "tablespoons" => Some(Unit::Tablespoon),
+            "cup" | "cups" => Some(Unit::Cup),
+            "oz" | "ounce" | "ounces" => Some(Unit::Ounce),
+            "fl oz" | "fl. oz" | "floz" | "fluid ounce" | "fluid ounces" => Some(Unit::FluidOunce),
+            "lb" | "lbs" | "pound" | "pounds" => Some(Unit::Pound),
+            "slice" | "slices" => Some(Unit::Slice),
+            "piece" | "pieces" | "pc" | "pcs" => Some(Unit::Piece),
+            "pinch" | "pinches" => Some(Unit::Pinch),
+            _ => None,
+        }
+    }
+
+    /// the unit as written after a quantity, plural for the countable ones
+    fn label(&self, quantity: f64) -> String {
+        match self {
+            Unit::Cup | Unit::Slice | Unit::Piece if quantity != 1.0 => format!("{}s", self.symbol()),
+            Unit::Pinch if quantity != 1.0 => String::from("pinches"),
+            _ => self.symbol().to_string(),
+        }
+    }
+}
+
+// Ingredient, a name with an optional quantity, unit and note, as in "200 g ham (thinly sliced)"
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Ingredient {
+    name: String,
+    quantity: Option<f64>,
+    unit: Option<Unit>,
+    note: Option<String>,
+}
+
+impl Ingredient {
+    pub fn new(name: String, quantity: Option<f64>, unit: Option<Unit>, note: Option<String>) -> Result<Self, String> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(String::from("Any ingredient must have a name"));
+        }
+        if let Some(q) = quantity {
+            if !q.is_finite() || q <= 0.0 {
+                return Err(format!("The quantity of {} must be positive", name));
+            }
+        }
+        if unit.is_some() && quantity.is_none() {
+            return Err(format!("The unit of {} needs a quantity", name));
+        }
+
+        let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+        Ok(Self { name, quantity, unit, note })
+    }
+
+    /// Parse a free-text line: an optional quantity, as an integer, a decimal or a fraction,
+    /// an optional unit, the name, and an optional note between parentheses or, after a quantity, after a comma
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+
+        let (rest, note) = match (line.find('('), line.ends_with(')')) {
+            (Some(open), true) => (&line[..open], Some(line[open + 1..line.len() - 1].to_string())),
+            // without a quantity, as in "Salt, pepper", the comma is part of the name
+            _ => match line.split_once(',') {
+                Some((rest, note)) if rest.split_whitespace().next().and_then(leading_quantity).is_some() =>
+                    (rest, Some(note.to_string())),
+                _ => (line, None),
+            },
+        };
+
+        let mut words: Vec<&str> = rest.split_whitespace().collect();
+        let mut quantity = None;
+        let mut unit = None;
+
+        let leading = words.first().and_then(|w| leading_quantity(w));
+
+        if let Some((q, attached_unit)) = leading {
+            words.remove(0);
+            quantity = Some(q);
+
+            // a mixed number, as in "1 1/2"
+            if attached_unit.is_empty() && q.fract() == 0.0 {
+                if let Some(fraction) = words.first().and_then(|w| parse_fraction(w)) {
+                    if fraction < 1.0 {
+                        words.remove(0);
+                        quantity = Some(q + fraction);
+                    }
+                }
+            }
+
+            if !attached_unit.is_empty() {
+                unit = Unit::parse(attached_unit);
+            } else if words.len() > 2 && Unit::parse(&words[..2].join(" ")).is_some() {
+                // a unit of two words, as in "fl oz"
+                unit = Unit::parse(&words[..2].join(" "));
+                words.drain(..2);
+            } else if words.len() > 1 {
+                if let Some(u) = words.first().and_then(|w| Unit::parse(w)) {
+                    words.remove(0);
+                    unit = Some(u);
+                }
+            }
+
+            if unit.is_some() && words.len() > 1 && words[0].eq_ignore_ascii_case("of") {
+                words.remove(0);
+            }
+        }
+
+        Ingredient::new(words.join(" "), quantity, unit, note)
+            .map_err(|e| format!("{} in \"{}\"", e, line))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn quantity(&self) -> Option<f64> {
+        self.quantity
+    }
+
+    pub fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+
+    /// the same ingredient in another quantity, the current one is kept if the new one is not positive
+    pub fn with_quantity(&self, quantity: f64, unit: Option<Unit>) -> Self {
+        Ingredient::new(self.name.clone(), Some(quantity), unit, self.note.clone())
+            .unwrap_or_else(|_| self.clone())
+    }
+
+    /// the same ingredient under another name, the current one is kept if the new one is empty
+    pub fn with_name(&self, name: &str) -> Self {
+        Ingredient::new(name.to_string(), self.quantity, self.unit, self.note.clone())
+            .unwrap_or_else(|_| self.clone())
+    }
+}
+
+impl fmt::Display for Ingredient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(q) = self.quantity {
+            write!(f, "{} ", format_quantity(q))?;
+            if let Some(u) = self.unit {
+                write!(f, "{} ", u.label(q))?;
+            }
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(note) = &self.note {
+            write!(f, " ({})", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// the quantity a line starts with, and the unit glued to it if any.
+/// A number glued to an unknown word, as in "7up", is part of the name
+fn leading_quantity(word: &str) -> Option<(f64, &str)> {
+    split_quantity(word)
+        .filter(|(_, attached_unit)| attached_unit.is_empty() || Unit::parse(attached_unit).is_some())
+}
+
+fn split_quantity(word: &str) -> Option<(f64, &str)> {
+    let end = word.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '/')).unwrap_or(word.len());
+    if end == 0 {
+        return None;
+    }
+    parse_fraction(&word[..end]).map(|q| (q, &word[end..]))
+}
+
+fn parse_fraction(word: &str) -> Option<f64> {
+    match word.split_once('/') {
+        Some((num, den)) => match (num.parse::<f64>(), den.parse::<f64>()) {
+            (Ok(n), Ok(d)) if d != 0.0 => Some(n / d),
+            _ => None,
+        },
+        None => word.parse::<f64>().ok(),
+    }
+}
+
+/// at most two decimals, without trailing zeros
+pub fn format_quantity(quantity: f64) -> String {
+    let rounded = format!("{:.2}", quantity);
+    rounded.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
 // Sandwich Ingredients
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SandwichIngredients(Vec<String>);
+pub struct SandwichIngredients(Vec<Ingredient>);
 
 impl SandwichIngredients {
-    pub fn value(&self) -> &Vec<String> {
+    pub fn value(&self) -> &Vec<Ingredient> {
         &self.0
     }
+
+    /// the names of the ingredients, without quantities
+    pub fn names(&self) -> Vec<String> {
+        self.0.iter().map(|i| i.name().to_string()).collect()
+    }
+
+    /// the ingredients written as free-text lines
+    pub fn lines(&self) -> Vec<String> {
+        self.0.iter().map(|i| i.to_string()).collect()
+    }
 }
 
-impl TryFrom<Vec<String>> for SandwichIngredients {
-    type Error = &'static str;
+impl TryFrom<Vec<Ingredient>> for SandwichIngredients {
+    type Error = String;
 
-    fn try_from(ingredients: Vec<String>) -> Result<Self, Self::Error> {
+    fn try_from(ingredients: Vec<Ingredient>) -> Result<Self, Self::Error> {
         if ingredients.is_empty() {
-            Err("Any sandwich must have at least one ingredient")
+            Err(String::from("Any sandwich must have at least one ingredient"))
         } else {
             Ok(Self(ingredients))
         }
     }
 }
 
+impl TryFrom<Vec<String>> for SandwichIngredients {
+    type Error = String;
+
+    fn try_from(lines: Vec<String>) -> Result<Self, Self::Error> {
+        let ingredients = lines.iter()
+            .map(|l| Ingredient::parse(l))
+            .collect::<Result<Vec<Ingredient>, String>>()?;
+
+        Self::try_from(ingredients)
+    }
+}
+
+pub const MAX_STEPS: usize = 50;
+pub const MAX_STEP_DURATION_MINUTES: u32 = 24 * 60;
+
+// Preparation Step, an instruction with the minutes it takes if known.
+// Passive steps, as toasting or resting, count in the total time but not in the preparation time
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum SandwichType {
-    Meat,
-    Fish,
-    Veggie,
-    Undefined,
+pub struct PreparationStep {
+    instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_minutes: Option<u32>,
+    #[serde(default)]
+    passive: bool,
+}
+
+impl PreparationStep {
+    /// new constructor function, the steps are validated by the sandwich holding them
+    pub fn new(instruction: String, duration_minutes: Option<u32>, passive: bool) -> Self {
+        Self { instruction: instruction.trim().to_string(), duration_minutes, passive }
+    }
+
+    pub fn instruction(&self) -> &str {
+        &self.instruction
+    }
+
+    pub fn duration_minutes(&self) -> Option<u32> {
+        self.duration_minutes
+    }
+
+    pub fn passive(&self) -> bool {
+        self.passive
+    }
+}
+
+// Sandwich Steps, in the order they are performed
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SandwichSteps(Vec<PreparationStep>);
+
+impl SandwichSteps {
+    pub fn value(&self) -> &Vec<PreparationStep> {
+        &self.0
+    }
+
+    /// minutes of the active steps, none if no step is timed
+    pub fn prep_time_minutes(&self) -> Option<u32> {
+        self.sum_minutes(|s| !s.passive)
+    }
+
+    /// minutes of all the steps, none if no step is timed
+    pub fn total_time_minutes(&self) -> Option<u32> {
+        self.sum_minutes(|_| true)
+    }
+
+    fn sum_minutes<F: Fn(&PreparationStep) -> bool>(&self, counted: F) -> Option<u32> {
+        if self.0.iter().all(|s| s.duration_minutes.is_none()) {
+            return None;
+        }
+        Some(self.0.iter()
+            .filter(|s| counted(s))
+            .filter_map(|s| s.duration_minutes)
+            .sum())
+    }
+}
+
+impl TryFrom<Vec<PreparationStep>> for SandwichSteps {
+    type Error = String;
+
+    fn try_from(steps: Vec<PreparationStep>) -> Result<Self, Self::Error> {
+        if steps.len() > MAX_STEPS {
+            return Err(format!("A sandwich has at most {} preparation steps", MAX_STEPS));
+        }
+        for (index, step) in steps.iter().enumerate() {
+            if step.instruction.is_empty() {
+                return Err(format!("The preparation step {} has no instruction", index + 1));
+            }
+            if let Some(d) = step.duration_minutes {
+                if d == 0 || d > MAX_STEP_DURATION_MINUTES {
+                    return Err(format!("The duration of the preparation step {} must be between 1 and {} minutes", index + 1, MAX_STEP_DURATION_MINUTES));
+                }
+            }
+        }
+        Ok(Self(steps))
+    }
+}
+
+/// The sandwich types, listed once with the names they are stored and exchanged with.
+/// The domain enum and the enums of the adapters are generated from this list by `$apply`,
+/// so that a type cannot be added to one and missed by another.
+/// Renaming one would leave the stored sandwiches unreadable, add an alias to `parse` instead
+macro_rules! sandwich_types {
+    ($apply:ident $(, $arg:tt)*) => {
+        $apply! { $($arg,)* SandwichType {
+            Meat => "Meat",
+            Poultry => "Poultry",
+            Fish => "Fish",
+            Mixed => "Mixed",
+            Veggie => "Veggie",
+            Vegan => "Vegan",
+            Undefined => "Undefined",
+        } }
+    };
+}
+pub(crate) use sandwich_types;
+
+/// The tags a sandwich can be given, listed as the types are
+macro_rules! sandwich_tags {
+    ($apply:ident $(, $arg:tt)*) => {
+        $apply! { $($arg,)* SandwichTag {
+            Spicy => "spicy",
+            Breakfast => "breakfast",
+            Kids => "kids",
+            Hot => "hot",
+            Cold => "cold",
+            Sweet => "sweet",
+            Party => "party",
+        } }
+    };
+}
+pub(crate) use sandwich_tags;
+
+/// A closed enum exchanged by name, read case insensitively
+macro_rules! named_enum {
+    ($domain:ident { $($variant:ident => $name:tt),+ $(,)? }) => {
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub enum $domain {
+            $($variant),+
+        }
+
+        impl $domain {
+            pub const ALL: &'static [$domain] = &[$($domain::$variant),+];
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($domain::$variant => $name),+
+                }
+            }
+
+            pub fn parse(name: &str) -> Option<Self> {
+                $domain::ALL.iter().find(|v| v.name().eq_ignore_ascii_case(name.trim())).cloned()
+            }
+        }
+
+        impl Serialize for $domain {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $domain {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                $domain::parse(&name).ok_or_else(|| serde::de::Error::unknown_variant(&name, &[$($name),+]))
+            }
+        }
+    };
 }
 
+sandwich_types!(named_enum);
+sandwich_tags!(named_enum);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SandwichStars(i32);
 
@@ -93,33 +513,129 @@ impl From<i32> for SandwichStars {
     }
 }
 
+pub const DEFAULT_SERVINGS: u32 = 1;
+pub const MAX_SERVINGS: u32 = 100;
+
+// Sandwich Servings, the number of people the quantities of the ingredients are meant for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SandwichServings(u32);
+
+impl SandwichServings {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Default for SandwichServings {
+    fn default() -> Self {
+        Self(DEFAULT_SERVINGS)
+    }
+}
+
+impl TryFrom<u32> for SandwichServings {
+    type Error = String;
+
+    fn try_from(servings: u32) -> Result<Self, Self::Error> {
+        if (1..=MAX_SERVINGS).contains(&servings) {
+            Ok(Self(servings))
+        } else {
+            Err(format!("The servings must be between 1 and {}", MAX_SERVINGS))
+        }
+    }
+}
+
+// Sandwich Version, incremented by the persistence system on every update
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SandwichVersion(Option<i64>);
+
+impl SandwichVersion {
+    pub fn value(&self) -> &Option<i64> {
+        &self.0
+    }
+}
+
+impl From<Option<i64>> for SandwichVersion {
+    fn from(version: Option<i64>) -> Self {
+        Self(version)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sandwich {
     id: SandwichId,
     name: SandwichName,
     ingredients: SandwichIngredients,
+    steps: SandwichSteps,
     sandwich_type: SandwichType,
+    tags: Vec<SandwichTag>,
     stars: SandwichStars,
+    servings: SandwichServings,
+    version: SandwichVersion,
 }
 
 impl Entity for Sandwich {}
 
 impl Sandwich {
-    pub fn new(id: String, name: String, ingredients: Vec<String>, sandwich_type: SandwichType, stars: i32) -> Result<Self, String> {
+    /// the ingredients are either structured or free-text lines to be parsed
+    pub fn new<I>(id: String, name: String, ingredients: I, steps: Vec<PreparationStep>, sandwich_type: SandwichType, stars: i32) -> Result<Self, String>
+        where I: TryInto<SandwichIngredients, Error = String> {
         let sandwich_id = SandwichId::try_from(id)?;
         let sandwich_name = SandwichName::try_from(name)?;
-        let sandwich_ingrs = SandwichIngredients::try_from(ingredients)?;
+        let sandwich_ingrs = ingredients.try_into()?;
+        let sandwich_steps = SandwichSteps::try_from(steps)?;
         let sandwich_stars = SandwichStars::from(stars);
 
         Ok(Self {
             id: sandwich_id,
             name: sandwich_name,
             ingredients: sandwich_ingrs,
+            steps: sandwich_steps,
             sandwich_type,
+            tags: vec![],
             stars: sandwich_stars,
+            servings: SandwichServings::default(),
+            version: SandwichVersion::from(None),
         })
     }
 
+    /// set the id generated by the persistence system
+    pub fn with_id(mut self, id: String) -> Self {
+        self.id = SandwichId(Some(id).filter(|i| !i.is_empty()));
+        self
+    }
+
+    /// set the version read from the persistence system or expected by an update
+    pub fn with_version(mut self, version: Option<i64>) -> Self {
+        self.version = SandwichVersion::from(version);
+        self
+    }
+
+    /// set the tags, each one kept once
+    pub fn with_tags(mut self, tags: Vec<SandwichTag>) -> Self {
+        self.tags = tags;
+        self.tags.sort();
+        self.tags.dedup();
+        self
+    }
+
+    /// set the servings the quantities are meant for, one by default
+    pub fn with_servings(mut self, servings: SandwichServings) -> Self {
+        self.servings = servings;
+        self
+    }
+
+    /// set the type, as when it is inferred from the ingredients
+    pub fn with_sandwich_type(mut self, sandwich_type: SandwichType) -> Self {
+        self.sandwich_type = sandwich_type;
+        self
+    }
+
+    /// replace the ingredients, as when the recipe is scaled
+    pub fn with_ingredients(mut self, ingredients: SandwichIngredients) -> Self {
+        self.ingredients = ingredients;
+        self
+    }
+
     pub fn id(&self) -> &SandwichId {
         &self.id
     }
@@ -132,13 +648,42 @@ impl Sandwich {
         &self.ingredients
     }
 
+    pub fn steps(&self) -> &SandwichSteps {
+        &self.steps
+    }
+
+    /// the allergens of the ingredients, after the bundled allergen table, and the ingredients it does not know
+    pub fn allergens(&self) -> SandwichAllergens {
+        let table = bundled_table();
+        let mut allergens = SandwichAllergens::default();
+        for ingredient in self.ingredients.value() {
+            match table.allergens_of(ingredient.name()) {
+                Some(found) => allergens.found.extend(found),
+                None => allergens.unknown.push(ingredient.name().to_string()),
+            }
+        }
+        allergens
+    }
+
     pub fn sandwich_type(&self) -> &SandwichType {
         &self.sandwich_type
     }
 
+    pub fn tags(&self) -> &Vec<SandwichTag> {
+        &self.tags
+    }
+
     pub fn stars(&self) -> &SandwichStars {
         &self.stars
     }
+
+    pub fn servings(&self) -> &SandwichServings {
+        &self.servings
+    }
+
+    pub fn version(&self) -> &SandwichVersion {
+        &self.version
+    }
 }
 
 impl fmt::Display for Sandwich {
@@ -163,13 +708,14 @@ mod tests {
         let hot_dog = Sandwich::new(SANDWICH_ID.to_string(),
                                     SANDWICH_NAME.to_string(),
                                     stub_ingredients(),
+                                    vec![],
                                     SANDWICH_TYPE,
                                     SANDWICH_STARS)
             .unwrap();
 
         assert_eq!(hot_dog.id().value().as_ref().unwrap(), SANDWICH_ID);
         assert_eq!(hot_dog.name.value(), SANDWICH_NAME);
-        assert_on_ingredients(&stub_ingredients(), hot_dog.ingredients().value());
+        assert_on_ingredients(&stub_ingredients(), &hot_dog.ingredients().lines());
     }
 
     #[test]
@@ -177,6 +723,7 @@ mod tests {
         let err_sandwich = Sandwich::new("".to_string(),
                                          "".to_string(),
                                          vec!["Wurst".to_string(), "Ketchup".to_string()],
+                                         vec![],
                                          SANDWICH_TYPE,
                                          SANDWICH_STARS);
 
@@ -185,6 +732,7 @@ mod tests {
 
         let err_sandwich = Sandwich::new(SANDWICH_ID.to_string(),
                                          SANDWICH_NAME.to_string(),
+                                         Vec::<String>::new(),
                                          vec![],
                                          SANDWICH_TYPE,
                                          SANDWICH_STARS);
@@ -192,4 +740,94 @@ mod tests {
         assert_eq!(err_sandwich.is_err(), true);
         assert_eq!(err_sandwich.unwrap_err(), "Any sandwich must have at least one ingredient");
     }
+
+    #[test]
+    fn should_parse_ingredient_lines() {
+        let ham = Ingredient::parse("200g ham (thinly sliced)").unwrap();
+        assert_eq!(ham.name(), "ham");
+        assert_eq!(ham.quantity(), Some(200.0));
+        assert_eq!(ham.unit(), Some(Unit::Gram));
+        assert_eq!(ham.note(), Some("thinly sliced"));
+
+        let bread = Ingredient::parse("2 slices of bread, toasted").unwrap();
+        assert_eq!((bread.name(), bread.quantity(), bread.unit(), bread.note()), ("bread", Some(2.0), Some(Unit::Slice), Some("toasted")));
+
+        let butter = Ingredient::parse("1 1/2 tbsp butter").unwrap();
+        assert_eq!((butter.name(), butter.quantity(), butter.unit()), ("butter", Some(1.5), Some(Unit::Tablespoon)));
+
+        let eggs = Ingredient::parse("2 eggs").unwrap();
+        assert_eq!((eggs.name(), eggs.quantity(), eggs.unit()), ("eggs", Some(2.0), None));
+
+        let milk = Ingredient::parse("4 fl oz milk").unwrap();
+        assert_eq!((milk.name(), milk.quantity(), milk.unit()), ("milk", Some(4.0), Some(Unit::FluidOunce)));
+
+        let meat = Ingredient::parse("ground meat").unwrap();
+        assert_eq!((meat.name(), meat.quantity(), meat.unit()), ("ground meat", None, None));
+    }
+
+    #[test]
+    fn should_print_ingredients_as_lines() {
+        for line in ["200 g ham (thinly sliced)", "2 slices bread", "1.5 tbsp butter", "Wurst"] {
+            assert_eq!(Ingredient::parse(line).unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn should_reject_invalid_ingredients() {
+        assert!(Ingredient::parse("200").is_err());
+        assert!(Ingredient::parse("0 g ham").is_err());
+    }
+
+    #[test]
+    fn should_keep_numbers_glued_to_a_name() {
+        let soda = Ingredient::parse("7up").unwrap();
+        assert_eq!((soda.name(), soda.quantity()), ("7up", None));
+    }
+
+    #[test]
+    fn should_keep_a_comma_without_quantity_in_the_name() {
+        let seasoning = Ingredient::parse("Salt, pepper").unwrap();
+        assert_eq!((seasoning.name(), seasoning.note()), ("Salt, pepper", None));
+    }
+
+    fn step(instruction: &str, duration_minutes: Option<u32>, passive: bool) -> PreparationStep {
+        PreparationStep::new(instruction.to_string(), duration_minutes, passive)
+    }
+
+    #[test]
+    fn should_derive_the_preparation_and_total_times() {
+        let steps = vec![
+            step("Slice the bread", Some(2), false),
+            step("Spread the butter", None, false),
+            step("Toast", Some(5), true),
+            step("Add the ham and cheese", Some(3), false),
+        ];
+        let sandwich = Sandwich::new(SANDWICH_ID.to_string(), SANDWICH_NAME.to_string(), stub_ingredients(), steps, SANDWICH_TYPE, SANDWICH_STARS).unwrap();
+
+        assert_eq!(sandwich.steps().value().len(), 4);
+        assert_eq!(sandwich.steps().prep_time_minutes(), Some(5));
+        assert_eq!(sandwich.steps().total_time_minutes(), Some(10));
+        assert_eq!(SandwichSteps::default().total_time_minutes(), None);
+    }
+
+    #[test]
+    fn should_reject_invalid_steps() {
+        for steps in [vec![step(" ", None, false)], vec![step("Toast", Some(0), true)], vec![step("Rest", Some(MAX_STEP_DURATION_MINUTES + 1), true)]] {
+            let res = Sandwich::new(SANDWICH_ID.to_string(), SANDWICH_NAME.to_string(), stub_ingredients(), steps, SANDWICH_TYPE, SANDWICH_STARS);
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn should_read_the_stored_types_and_tags_by_name() {
+        let types: Vec<SandwichType> = serde_json::from_str(r#"["Meat", "Fish", "Veggie", "Undefined", "vegan"]"#).unwrap();
+        assert_eq!(types, vec![SandwichType::Meat, SandwichType::Fish, SandwichType::Veggie, SandwichType::Undefined, SandwichType::Vegan]);
+        assert!(serde_json::from_str::<SandwichType>(r#""Vegetal""#).is_err());
+
+        let sandwich = Sandwich::new(SANDWICH_ID.to_string(), SANDWICH_NAME.to_string(), stub_ingredients(), vec![], SandwichType::Poultry, SANDWICH_STARS)
+            .unwrap()
+            .with_tags(vec![SandwichTag::Spicy, SandwichTag::Kids, SandwichTag::Spicy]);
+        assert_eq!(serde_json::to_string(sandwich.tags()).unwrap(), r#"["spicy","kids"]"#);
+        assert_eq!(serde_json::to_string(sandwich.sandwich_type()).unwrap(), r#""Poultry""#);
+    }
 }
@@ -0,0 +1,238 @@
+use std::cmp::{min, Ordering};
+use std::collections::HashMap;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::domain::sandwich::Sandwich;
+
+/// a match on the name counts more than a match on an ingredient
+const NAME_WEIGHT: f64 = 2.0;
+const INGREDIENT_WEIGHT: f64 = 1.0;
+
+const EXACT_MATCH: f64 = 1.0;
+const PREFIX_MATCH: f64 = 0.8;
+const TYPO_MATCH: f64 = 0.6;
+
+/// words telling how an ingredient is prepared or chosen, not what it is: "wild salmon" is still salmon,
+/// while "almond butter" is not butter
+const QUALIFIERS: [&str; 24] = [
+    "fresh", "wild", "smoked", "sliced", "grated", "chopped", "diced", "shredded", "minced", "toasted",
+    "grilled", "roasted", "fried", "cooked", "raw", "ripe", "organic", "red", "green", "yellow", "white",
+    "large", "small", "extra",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Ingredient,
+}
+
+impl Field {
+    fn weight(&self) -> f64 {
+        match self {
+            Field::Name => NAME_WEIGHT,
+            Field::Ingredient => INGREDIENT_WEIGHT,
+        }
+    }
+}
+
+/// In-process full-text index of sandwiches, on their names and ingredients.
+/// Matching is case and accent insensitive and tolerates typos, every term of the query must match
+pub struct SearchIndex {
+    sandwiches: Vec<Sandwich>,
+    postings: HashMap<String, Vec<(usize, Field)>>,
+}
+
+impl SearchIndex {
+    pub fn new(sandwiches: Vec<Sandwich>) -> Self {
+        let mut postings: HashMap<String, Vec<(usize, Field)>> = HashMap::new();
+
+        for (index, sandwich) in sandwiches.iter().enumerate() {
+            for term in tokenize(sandwich.name().value()) {
+                postings.entry(term).or_default().push((index, Field::Name));
+            }
+            for ingredient in sandwich.ingredients().names() {
+                for term in tokenize(&ingredient) {
+                    postings.entry(term).or_default().push((index, Field::Ingredient));
+                }
+            }
+        }
+
+        SearchIndex { sandwiches, postings }
+    }
+
+    /// the sandwiches matching the query, the most relevant first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Sandwich> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        // best score of each query term on each sandwich
+        let mut scores: HashMap<usize, Vec<f64>> = HashMap::new();
+
+        for (position, query_term) in terms.iter().enumerate() {
+            for (indexed_term, postings) in self.postings.iter() {
+                let quality = match_quality(query_term, indexed_term);
+                if quality == 0.0 {
+                    continue;
+                }
+
+                for (index, field) in postings {
+                    let term_scores = scores.entry(*index).or_insert_with(|| vec![0.0; terms.len()]);
+                    term_scores[position] = term_scores[position].max(quality * field.weight());
+                }
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter()
+            .filter(|(_, term_scores)| term_scores.iter().all(|s| *s > 0.0))
+            .map(|(index, term_scores)| (index, term_scores.iter().sum()))
+            .collect();
+
+        ranked.sort_by(|(a, score_a), (b, score_b)| score_b.partial_cmp(score_a)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.sandwiches[*a].name().value().cmp(self.sandwiches[*b].name().value())));
+
+        ranked.into_iter()
+            .take(limit)
+            .map(|(index, _)| self.sandwiches[index].clone())
+            .collect()
+    }
+}
+
+/// lowercase, without accents and punctuation
+pub fn normalize(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect()
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    normalize(text)
+        .split_whitespace()
+        .map(String::from)
+        .collect()
+}
+
+/// the keys an ingredient may be listed under in a table, its words then its endings without the leading qualifiers,
+/// as "salmon" for "wild salmon", each followed by its singular forms
+pub fn lookup_keys(name: &str) -> Vec<String> {
+    let words = tokenize(name);
+    let qualifiers = words.iter().take_while(|w| QUALIFIERS.contains(&w.as_str())).count();
+
+    (0..words.len()).take(qualifiers + 1)
+        .map(|start| words[start..].join(" "))
+        .flat_map(|phrase| {
+            let singulars = [phrase.strip_suffix("es"), phrase.strip_suffix('s')]
+                .into_iter()
+                .flatten()
+                .map(String::from)
+                .collect::<Vec<String>>();
+            std::iter::once(phrase).chain(singulars)
+        })
+        .collect()
+}
+
+/// how well an indexed term matches a query term, 0 when it does not
+fn match_quality(query_term: &str, indexed_term: &str) -> f64 {
+    if query_term == indexed_term {
+        return EXACT_MATCH;
+    }
+
+    if query_term.chars().count() >= 2 && indexed_term.starts_with(query_term) {
+        return PREFIX_MATCH;
+    }
+
+    let allowed = max_typos(query_term.chars().count());
+    if allowed == 0 {
+        return 0.0;
+    }
+
+    match edit_distance(query_term, indexed_term) {
+        d if d <= allowed => TYPO_MATCH / d as f64,
+        _ => 0.0,
+    }
+}
+
+/// short words must be typed right, longer ones may have one or two typos
+fn max_typos(length: usize) -> usize {
+    match length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Levenshtein distance between two words
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = min(substitution, min(previous[j] + 1, current[j - 1] + 1));
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::SandwichType;
+
+    use super::*;
+
+    fn sandwich(name: &str, ingredients: &[&str]) -> Sandwich {
+        Sandwich::new(String::from(""), name.to_string(), ingredients.iter().map(|i| i.to_string()).collect::<Vec<String>>(), vec![], SandwichType::Undefined, 0).unwrap()
+    }
+
+    fn index() -> SearchIndex {
+        SearchIndex::new(vec![
+            sandwich("Croque-monsieur", &["Ham", "Gruyère", "Bread"]),
+            sandwich("Cheeseburger", &["Beef", "Cheese", "Bun"]),
+            sandwich("Ham and cheese", &["Ham", "Cheese", "Bread"]),
+        ])
+    }
+
+    fn names(results: Vec<Sandwich>) -> Vec<String> {
+        results.iter().map(|s| s.name().value().to_string()).collect()
+    }
+
+    #[test]
+    fn should_ignore_case_and_accents() {
+        assert_eq!(names(index().search("GRUYERE", 10)), vec!["Croque-monsieur"]);
+        assert_eq!(normalize("Crôque-Monsieur"), "croque monsieur");
+    }
+
+    #[test]
+    fn should_tolerate_typos() {
+        assert_eq!(names(index().search("chesseburger", 10)), vec!["Cheeseburger"]);
+    }
+
+    #[test]
+    fn should_rank_name_matches_first() {
+        assert_eq!(names(index().search("cheese", 10)), vec!["Ham and cheese", "Cheeseburger"]);
+    }
+
+    #[test]
+    fn should_require_every_term() {
+        assert_eq!(names(index().search("ham bun", 10)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn should_compute_the_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "ham"), 3);
+    }
+}
@@ -0,0 +1,64 @@
+use actix_web::web;
+
+use crate::domain::sandwich::Sandwich;
+use crate::domain::search::tokenize;
+use crate::driven::repository::RepoFindAllError;
+use crate::Repository;
+
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+pub const MAX_SEARCH_LIMIT: usize = 100;
+
+#[derive(Debug)]
+pub enum SearchError {
+    InvalidQuery(String),
+    Unknown(String),
+}
+
+// this is my port / use case
+/// Sandwiches whose name or ingredients match the query, the most relevant first
+pub async fn search_sandwiches<T: Repository<Sandwich>>(repository: web::Data<T>, query: &str, limit: Option<usize>) -> Result<Vec<Sandwich>, SearchError> {
+
+    if tokenize(query).is_empty() {
+        return Err(SearchError::InvalidQuery(String::from("The query must contain at least one word")));
+    }
+
+    let limit = match limit {
+        None => DEFAULT_SEARCH_LIMIT,
+        Some(l) if (1..=MAX_SEARCH_LIMIT).contains(&l) => l,
+        Some(_) => return Err(SearchError::InvalidQuery(format!("The limit must be between 1 and {}", MAX_SEARCH_LIMIT))),
+    };
+
+    repository.search(query.trim(), limit).await
+        .map_err(|e| match e {
+            RepoFindAllError::Unknown(e) => SearchError::Unknown(format!("Unknown error: {}", e)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Data;
+
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{CHEESEBURGER_NAME, get_testing_mongodb_config};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_search_sandwiches() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let found = search_sandwiches(Data::new(repo), "CHESEBURGER", None).await.unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name().value(), CHEESEBURGER_NAME);
+    }
+
+    #[actix_rt::test]
+    async fn should_reject_an_empty_query() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let res = search_sandwiches(Data::new(repo), " - ", None).await;
+
+        assert!(matches!(res, Err(SearchError::InvalidQuery(_))));
+    }
+}
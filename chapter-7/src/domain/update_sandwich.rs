@@ -1,8 +1,11 @@
 use actix_web::web;
 
+use crate::domain::commands::{SandwichCommand, SandwichPorts};
 use crate::domain::does_sandwich_exist;
-use crate::domain::sandwich::{Sandwich, SandwichType};
+use crate::domain::sandwich::Sandwich;
+use crate::driven::ingredients::IngredientCatalog;
 use crate::driven::repository::RepoUpdateError;
+use crate::driven::suggestions::SuggestionIndex;
 use crate::Repository;
 
 #[derive(Debug)]
@@ -11,36 +14,45 @@ pub enum UpdateError {
     Unknown(String),
     NotFound,
     Conflict(String),
+    PreconditionFailed,
 }
 
 // this is my port / use case
-pub async fn update_sandwich<'a, T: Repository<Sandwich>>(repository: web::Data<T>, id: &'a str, name: &'a str, ingredients: &'a Vec<&str>, sandwich_type: &SandwichType) -> Result<Sandwich, UpdateError> {
+pub async fn update_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + ?Sized, C: IngredientCatalog + ?Sized>(repository: web::Data<T>, ports: &SandwichPorts<'_, S, C>, id: &str, command: SandwichCommand) -> Result<Sandwich, UpdateError> {
     if id.is_empty() {
         return Err(UpdateError::InvalidData(String::from("Cannot update without a target id")));
     }
 
-    let ingredients = ingredients.iter().map(|item| item.to_string()).collect::<Vec<String>>();
-    let sandwich = Sandwich::new(String::from(id), name.to_string(), ingredients, sandwich_type.clone(), 0)
-        .map_err(|e| UpdateError::InvalidData(e))?;
+    let sandwich = command.into_sandwich(id)
+        .map_err(UpdateError::InvalidData)?;
+    let sandwich = ports.preparation().await
+        .map_err(UpdateError::Unknown)?
+        .prepare(sandwich)
+        .map_err(UpdateError::InvalidData)?;
 
     if ! does_sandwich_exist(&repository, id).await {
         return Err(UpdateError::Conflict(String::from("Cannot find the sandwich to update")));
     }
 
-    repository.update(sandwich).await
+    let updated = repository.update(sandwich).await
         .map_err(|e| return match e {
             RepoUpdateError::InvalidData(e) => UpdateError::InvalidData(format!("Invalid data: {}", e)),
             RepoUpdateError::NotFound => UpdateError::NotFound,
+            RepoUpdateError::VersionMismatch => UpdateError::PreconditionFailed,
             RepoUpdateError::Unknown(e) => UpdateError::Unknown(format!("Unknown error: {}", e)),
-        })
+        })?;
+
+    ports.suggestions.index(&updated).await;
+    Ok(updated)
 }
 
 #[cfg(test)]
 mod tests {
 
     use actix_web::web::Data;
-    use crate::helpers::string_vec_to_vec_str;
-    use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, stub_ingredients, assert_on_sandwich, SANDWICH_ID};
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::test_utils::shared::{stub_cheeseburger, get_testing_mongodb_config, match_and_assert_on_sandwich, SANDWICH_NAME, SANDWICH_STARS, SANDWICH_TYPE, stub_sandwich, assert_on_sandwich, SANDWICH_ID, stub_sandwich_command};
     use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
 
     use super::*;
@@ -48,12 +60,9 @@ mod tests {
     #[actix_rt::test]
     async fn should_update_an_existing_sandwich() {
 
-        let ingrs = stub_ingredients();
-        let ingrs = string_vec_to_vec_str(&ingrs);
-
         let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
 
-        let s = update_sandwich(Data::new(repo), SANDWICH_ID, SANDWICH_NAME, &ingrs, &SANDWICH_TYPE).await.unwrap();
+        let s = update_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, SANDWICH_ID, stub_sandwich_command(Some(1))).await.unwrap();
 
         assert_on_sandwich(stub_sandwich(false), &s, false);
     }
@@ -61,13 +70,10 @@ mod tests {
     #[actix_rt::test]
     async fn should_not_update_a_non_existing_sandwich() {
 
-        let ingrs = stub_ingredients();
-        let ingrs = string_vec_to_vec_str(&ingrs);
-
         let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
         repo.set_error(true);
 
-        let res = update_sandwich(Data::new(repo), SANDWICH_ID, SANDWICH_NAME, &ingrs, &SANDWICH_TYPE).await;
+        let res = update_sandwich(Data::new(repo), &SandwichPorts { suggestions: &InMemorySuggestionIndex::new(), catalog: &InMemoryIngredientCatalog::new() }, SANDWICH_ID, stub_sandwich_command(Some(1))).await;
 
         match res {
             Err(_) => {},
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+
+use crate::domain::ingredients::{CatalogIngredient, ingredient_key};
+use crate::driven::ingredients::{IngredientCatalog, IngredientCatalogError, read_catalog};
+
+/// Ingredients kept in memory, for the tests not to need a database
+#[derive(Default)]
+pub struct InMemoryIngredientCatalog {
+    ingredients: RwLock<BTreeMap<String, CatalogIngredient>>,
+}
+
+impl InMemoryIngredientCatalog {
+    /// new constructor function
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// create a catalog preloaded with a TOML file having an `[[ingredients]]` table for each of them,
+    /// with a name and the synonyms it replaces
+    pub fn from_catalog<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let ingredients = read_catalog(path)?;
+
+        Ok(InMemoryIngredientCatalog {
+            ingredients: RwLock::new(ingredients.into_iter().map(|i| (i.key(), i)).collect()),
+        })
+    }
+}
+
+#[async_trait]
+impl IngredientCatalog for InMemoryIngredientCatalog {
+
+    async fn find_all(&self) -> Result<Vec<CatalogIngredient>, IngredientCatalogError> {
+        let ingredients = self.ingredients.read()
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+        Ok(ingredients.values().cloned().collect())
+    }
+
+    async fn find_one(&self, name: &str) -> Result<Option<CatalogIngredient>, IngredientCatalogError> {
+        let ingredients = self.ingredients.read()
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+
+        let key = ingredient_key(name);
+        Ok(ingredients.get(&key).cloned()
+            .or_else(|| ingredients.values().find(|i| i.synonyms().iter().any(|s| ingredient_key(s) == key)).cloned()))
+    }
+
+    async fn upsert(&self, ingredient: CatalogIngredient) -> Result<bool, IngredientCatalogError> {
+        let mut ingredients = self.ingredients.write()
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+        Ok(ingredients.insert(ingredient.key(), ingredient).is_none())
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool, IngredientCatalogError> {
+        let mut ingredients = self.ingredients.write()
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+        Ok(ingredients.remove(&ingredient_key(name)).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_load_the_catalog_and_find_an_ingredient_by_synonym() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/ingredients.toml");
+
+        let catalog = InMemoryIngredientCatalog::from_catalog(d).unwrap();
+
+        let ketchup = catalog.find_one("Tomato ketchup").await.unwrap().unwrap();
+        assert_eq!(ketchup.name(), "ketchup");
+        assert_eq!(catalog.find_one("KETCHUP").await.unwrap(), Some(ketchup));
+        assert_eq!(catalog.find_one("unobtainium").await.unwrap(), None);
+    }
+
+    #[actix_rt::test]
+    async fn should_set_and_delete_an_ingredient() {
+        let catalog = InMemoryIngredientCatalog::new();
+        let mayo = |synonyms: Vec<&str>| CatalogIngredient::new("mayo", synonyms.into_iter().map(String::from).collect()).unwrap();
+
+        assert!(catalog.upsert(mayo(vec![])).await.unwrap());
+        assert!(!catalog.upsert(mayo(vec!["mayonnaise"])).await.unwrap());
+        assert_eq!(catalog.find_all().await.unwrap(), vec![mayo(vec!["mayonnaise"])]);
+
+        // removed by its canonical name only
+        assert!(!catalog.delete("mayonnaise").await.unwrap());
+        assert!(catalog.delete("Mayo").await.unwrap());
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::domain::ingredients::{CanonicalNames, CatalogIngredient};
+
+#[cfg(test)]
+pub mod in_memory_catalog;
+pub mod mongo_catalog;
+
+#[derive(Debug)]
+pub enum IngredientCatalogError {
+    Unknown(String)
+}
+
+#[async_trait]
+pub trait IngredientCatalog {
+
+    /// All the catalog ingredients, sorted by name
+    async fn find_all(&self) -> Result<Vec<CatalogIngredient>, IngredientCatalogError>;
+
+    /// The ingredient with the received canonical name or synonym, case and accent insensitive
+    async fn find_one(&self, name: &str) -> Result<Option<CatalogIngredient>, IngredientCatalogError>;
+
+    /// Set an ingredient under its canonical name, returning whether it was not in the catalog before
+    async fn upsert(&self, ingredient: CatalogIngredient) -> Result<bool, IngredientCatalogError>;
+
+    /// Remove the ingredient with the received canonical name, returning whether it was in the catalog
+    async fn delete(&self, name: &str) -> Result<bool, IngredientCatalogError>;
+}
+
+#[derive(Deserialize)]
+struct IngredientRow {
+    name: String,
+    #[serde(default)]
+    synonyms: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CatalogFile {
+    #[serde(default)]
+    ingredients: Vec<IngredientRow>,
+}
+
+/// read a TOML file having an `[[ingredients]]` table for each of them, with a name and the synonyms it replaces
+pub fn read_catalog<P: AsRef<Path>>(path: P) -> Result<Vec<CatalogIngredient>, String> {
+    let content = read_to_string(path)
+        .map_err(|e| format!("Cannot read the ingredient catalog: {}", e))?;
+    let file: CatalogFile = toml::from_str(&content)
+        .map_err(|e| format!("Cannot parse the ingredient catalog: {}", e))?;
+
+    let ingredients = file.ingredients.into_iter()
+        .map(|r| CatalogIngredient::new(&r.name, r.synonyms))
+        .collect::<Result<Vec<_>, String>>()?;
+    CanonicalNames::new(&ingredients)?;
+
+    Ok(ingredients)
+}
@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use mongodb::{Client, Collection};
+use mongodb::bson::doc;
+use mongodb::options::{FindOptions, ReplaceOptions};
+use serde::{Deserialize, Serialize};
+
+use crate::config::MongoDBConfig;
+use crate::domain::ingredients::{CatalogIngredient, ingredient_key};
+use crate::driven::ingredients::{IngredientCatalog, IngredientCatalogError};
+use crate::driven::repository::mongo_repository::create_connection_uri;
+
+const INGREDIENTS_COLLECTION: &str = "ingredients";
+
+// an ingredient stored under its key, with the keys of its synonyms to find it by any of them
+#[derive(Debug, Serialize, Deserialize)]
+struct IngredientMongo {
+    _id: String,
+    name: String,
+    synonyms: Vec<String>,
+    synonym_keys: Vec<String>,
+}
+
+impl From<CatalogIngredient> for IngredientMongo {
+
+    fn from(ingredient: CatalogIngredient) -> Self {
+
+        IngredientMongo {
+            _id: ingredient.key(),
+            name: ingredient.name().to_string(),
+            synonym_keys: ingredient.synonyms().iter().map(|s| ingredient_key(s)).collect(),
+            synonyms: ingredient.synonyms().clone(),
+        }
+    }
+}
+
+impl TryInto<CatalogIngredient> for IngredientMongo {
+
+    type Error = String;
+
+    fn try_into(self) -> Result<CatalogIngredient, Self::Error> {
+        CatalogIngredient::new(&self.name, self.synonyms)
+    }
+}
+
+/// Ingredients stored in MongoDB, next to the sandwiches, so that the changes outlive a restart
+/// and every process reads the same catalog
+#[derive(Clone)]
+pub struct MongoIngredientCatalog {
+    database: String,
+    conn_uri: String,
+}
+
+impl MongoIngredientCatalog {
+    /// new constructor function
+    pub fn new(config: &MongoDBConfig) -> Result<Self, String> {
+        config.validate()?;
+
+        Ok(MongoIngredientCatalog {
+            database: config.database.clone(),
+            conn_uri: create_connection_uri(config),
+        })
+    }
+
+    async fn get_collection(&self) -> Result<Collection<IngredientMongo>, IngredientCatalogError> {
+        let client = Client::with_uri_str(&self.conn_uri).await
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+        Ok(client.database(&self.database).collection(INGREDIENTS_COLLECTION))
+    }
+
+    /// Store the ingredients of a catalog file when the catalog is empty, returning how many were stored.
+    /// A catalog already stored is left as it is, so that the changes made since are kept
+    pub async fn seed(&self, ingredients: Vec<CatalogIngredient>) -> Result<usize, IngredientCatalogError> {
+        let coll = self.get_collection().await?;
+
+        let stored = coll.count_documents(doc! {}, None).await
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+        if stored > 0 || ingredients.is_empty() {
+            return Ok(0);
+        }
+
+        let documents: Vec<IngredientMongo> = ingredients.into_iter().map(IngredientMongo::from).collect();
+        coll.insert_many(documents, None).await
+            .map(|r| r.inserted_ids.len())
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))
+    }
+}
+
+fn to_ingredient(document: IngredientMongo) -> Result<CatalogIngredient, IngredientCatalogError> {
+    document.try_into().map_err(IngredientCatalogError::Unknown)
+}
+
+#[async_trait]
+impl IngredientCatalog for MongoIngredientCatalog {
+
+    async fn find_all(&self) -> Result<Vec<CatalogIngredient>, IngredientCatalogError> {
+        let coll = self.get_collection().await?;
+
+        let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        let documents: Vec<IngredientMongo> = coll.find(doc! {}, options).await
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?
+            .try_collect().await
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+
+        documents.into_iter().map(to_ingredient).collect()
+    }
+
+    async fn find_one(&self, name: &str) -> Result<Option<CatalogIngredient>, IngredientCatalogError> {
+        let coll = self.get_collection().await?;
+        let key = ingredient_key(name);
+
+        // the canonical name wins over a synonym
+        for filter in [doc! { "_id": &key }, doc! { "synonym_keys": &key }] {
+            let found = coll.find_one(filter, None).await
+                .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))?;
+            if let Some(document) = found {
+                return to_ingredient(document).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn upsert(&self, ingredient: CatalogIngredient) -> Result<bool, IngredientCatalogError> {
+        let coll = self.get_collection().await?;
+        let document = IngredientMongo::from(ingredient);
+
+        let options = ReplaceOptions::builder().upsert(true).build();
+        coll.replace_one(doc! { "_id": &document._id }, &document, options).await
+            .map(|r| r.matched_count == 0)
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))
+    }
+
+    async fn delete(&self, name: &str) -> Result<bool, IngredientCatalogError> {
+        let coll = self.get_collection().await?;
+
+        coll.delete_one(doc! { "_id": ingredient_key(name) }, None).await
+            .map(|r| r.deleted_count > 0)
+            .map_err(|e| IngredientCatalogError::Unknown(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_store_an_ingredient_under_the_keys_of_its_names() {
+        let ketchup = CatalogIngredient::new("Ketchup", vec![String::from("Tomato Ketchup")]).unwrap();
+
+        let document = IngredientMongo::from(ketchup.clone());
+
+        assert_eq!(document._id, "ketchup");
+        assert_eq!(document.synonym_keys, vec!["tomato ketchup"]);
+        assert_eq!(to_ingredient(document).unwrap(), ketchup);
+    }
+}
@@ -1 +1,5 @@
 pub(crate) mod repository;
+pub(crate) mod persisted_queries;
+pub(crate) mod suggestions;
+pub(crate) mod prices;
+pub(crate) mod ingredients;
\ No newline at end of file
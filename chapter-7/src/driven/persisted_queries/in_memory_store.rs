@@ -0,0 +1,131 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::read_to_string;
+use std::path::Path;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+
+use crate::driven::persisted_queries::{PersistedQueryStore, sha256_hash, StoreRegisterError};
+
+/// queries registered by the clients kept by default, the allow-listed ones not counting
+pub const DEFAULT_CAPACITY: usize = 1000;
+
+#[derive(Default)]
+struct Queries {
+    by_hash: HashMap<String, String>,
+    // hashes registered by the clients, the oldest first, evicted when over capacity
+    registered: VecDeque<String>,
+}
+
+pub struct InMemoryPersistedQueryStore {
+    capacity: usize,
+    queries: RwLock<Queries>,
+}
+
+impl InMemoryPersistedQueryStore {
+    /// new constructor function, keeping up to `capacity` registered queries
+    pub fn new(capacity: usize) -> Self {
+        InMemoryPersistedQueryStore {
+            capacity,
+            queries: RwLock::new(Queries::default()),
+        }
+    }
+
+    /// create a store preloaded with the queries listed in a JSON array file, which are never evicted
+    pub fn from_allow_list<P: AsRef<Path>>(path: P, capacity: usize) -> Result<Self, String> {
+        let content = read_to_string(path)
+            .map_err(|e| format!("Cannot read the persisted queries allow list: {}", e))?;
+        let queries: Vec<String> = serde_json::from_str(&content)
+            .map_err(|e| format!("Cannot parse the persisted queries allow list: {}", e))?;
+
+        let by_hash = queries.into_iter()
+            .map(|q| (sha256_hash(&q), q))
+            .collect();
+
+        Ok(InMemoryPersistedQueryStore {
+            capacity,
+            queries: RwLock::new(Queries { by_hash, registered: VecDeque::new() }),
+        })
+    }
+}
+
+impl Default for InMemoryPersistedQueryStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl PersistedQueryStore for InMemoryPersistedQueryStore {
+
+    async fn find(&self, hash: &str) -> Option<String> {
+        self.queries.read().ok()?.by_hash.get(hash).cloned()
+    }
+
+    async fn register(&self, hash: &str, query: &str) -> Result<(), StoreRegisterError> {
+        let mut queries = self.queries.write()
+            .map_err(|e| StoreRegisterError::Unknown(e.to_string()))?;
+
+        if queries.by_hash.contains_key(hash) {
+            return Ok(());
+        }
+
+        if queries.registered.len() >= self.capacity {
+            match queries.registered.pop_front() {
+                Some(oldest) => queries.by_hash.remove(&oldest),
+                None => return Err(StoreRegisterError::Unknown(String::from("The persisted query store accepts no registration"))),
+            };
+        }
+
+        queries.by_hash.insert(hash.to_string(), query.to_string());
+        queries.registered.push_back(hash.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const QUERY: &str = "{ sandwiches { name } }";
+
+    #[actix_rt::test]
+    async fn should_register_and_find_a_query() {
+        let store = InMemoryPersistedQueryStore::default();
+        let hash = sha256_hash(QUERY);
+
+        assert_eq!(store.find(&hash).await, None);
+
+        store.register(&hash, QUERY).await.unwrap();
+
+        assert_eq!(store.find(&hash).await, Some(QUERY.to_string()));
+    }
+
+    #[actix_rt::test]
+    async fn should_evict_the_oldest_registered_query_when_full() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("src/tests/persisted_queries.json");
+        let store = InMemoryPersistedQueryStore::from_allow_list(d, 1).unwrap();
+        let (first, second) = ("{ sandwiches { id } }", "{ sandwiches { stars } }");
+
+        store.register(&sha256_hash(first), first).await.unwrap();
+        store.register(&sha256_hash(second), second).await.unwrap();
+
+        assert_eq!(store.find(&sha256_hash(first)).await, None);
+        assert_eq!(store.find(&sha256_hash(second)).await, Some(second.to_string()));
+        // the allow-listed query stays
+        assert_eq!(store.find(&sha256_hash(QUERY)).await, Some(QUERY.to_string()));
+    }
+
+    #[actix_rt::test]
+    async fn should_load_the_allow_list() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("src/tests/persisted_queries.json");
+
+        let store = InMemoryPersistedQueryStore::from_allow_list(d, DEFAULT_CAPACITY).unwrap();
+
+        assert_eq!(store.find(&sha256_hash(QUERY)).await, Some(QUERY.to_string()));
+    }
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+pub mod in_memory_store;
+
+#[derive(Debug)]
+pub enum StoreRegisterError {
+    Unknown(String)
+}
+
+#[async_trait]
+pub trait PersistedQueryStore {
+
+    /// Find the query registered with the received SHA-256 hash
+    async fn find(&self, hash: &str) -> Option<String>;
+
+    /// Register a query under its SHA-256 hash
+    async fn register(&self, hash: &str, query: &str) -> Result<(), StoreRegisterError>;
+}
+
+/// compute the lowercase hex SHA-256 hash a query is registered under
+pub fn sha256_hash(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_compute_the_sha256_hash() {
+        assert_eq!(sha256_hash(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+}
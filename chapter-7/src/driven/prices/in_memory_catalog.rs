@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::domain::prices::{IngredientPrice, price_key};
+use crate::domain::sandwich::Unit;
+use crate::driven::prices::{PriceCatalog, PriceCatalogError};
+
+// a price as written in a price list file, a missing quantity meaning one
+#[derive(Deserialize)]
+struct PriceRow {
+    ingredient: String,
+    price: f64,
+    quantity: Option<f64>,
+    unit: Option<String>,
+}
+
+impl PriceRow {
+    fn into_price(self) -> Result<IngredientPrice, String> {
+        let unit = match self.unit.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(u) => Some(Unit::parse(u).ok_or_else(|| format!("Unknown unit for the price of {}: {}", self.ingredient, u))?),
+        };
+        IngredientPrice::new(&self.ingredient, self.price, self.quantity.unwrap_or(1.0), unit)
+    }
+}
+
+#[derive(Deserialize)]
+struct PriceListFile {
+    #[serde(default)]
+    prices: Vec<PriceRow>,
+}
+
+/// Prices kept in memory, loaded from a price list file at startup. The changes made afterwards last until a restart
+pub struct InMemoryPriceCatalog {
+    currency: String,
+    prices: RwLock<BTreeMap<String, IngredientPrice>>,
+}
+
+impl InMemoryPriceCatalog {
+    /// new constructor function
+    pub fn new(currency: &str) -> Self {
+        InMemoryPriceCatalog {
+            currency: currency.to_string(),
+            prices: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// create a catalog preloaded with a price list, a CSV file with an ingredient, price, quantity and unit column
+    /// or a TOML file with a `[[prices]]` table for each of them, refused when an ingredient is priced twice
+    pub fn from_price_list<P: AsRef<Path>>(path: P, currency: &str) -> Result<Self, String> {
+        let content = read_to_string(&path)
+            .map_err(|e| format!("Cannot read the price list: {}", e))?;
+
+        let is_csv = path.as_ref().extension().is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+        let rows = match is_csv {
+            true => parse_csv(&content)?,
+            false => toml::from_str::<PriceListFile>(&content)
+                .map_err(|e| format!("Cannot parse the price list: {}", e))?
+                .prices,
+        };
+
+        let mut prices = BTreeMap::new();
+        let mut rows_by_key = BTreeMap::new();
+        for (index, row) in rows.into_iter().enumerate() {
+            let price = row.into_price()?;
+            if let Some(first) = rows_by_key.insert(price.key(), index + 1) {
+                return Err(format!("Invalid price list row {}: {} is already priced in row {}", index + 1, price.ingredient(), first));
+            }
+            prices.insert(price.key(), price);
+        }
+
+        Ok(InMemoryPriceCatalog {
+            currency: currency.to_string(),
+            prices: RwLock::new(prices),
+        })
+    }
+}
+
+fn parse_csv(content: &str) -> Result<Vec<PriceRow>, String> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes())
+        .deserialize::<PriceRow>()
+        .enumerate()
+        .map(|(index, row)| row.map_err(|e| format!("Invalid price list row {}: {}", index + 1, e)))
+        .collect()
+}
+
+#[async_trait]
+impl PriceCatalog for InMemoryPriceCatalog {
+
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    async fn find_all(&self) -> Result<Vec<IngredientPrice>, PriceCatalogError> {
+        let prices = self.prices.read()
+            .map_err(|e| PriceCatalogError::Unknown(e.to_string()))?;
+        Ok(prices.values().cloned().collect())
+    }
+
+    async fn find_one(&self, ingredient: &str) -> Result<Option<IngredientPrice>, PriceCatalogError> {
+        let prices = self.prices.read()
+            .map_err(|e| PriceCatalogError::Unknown(e.to_string()))?;
+        Ok(prices.get(&price_key(ingredient)).cloned())
+    }
+
+    async fn upsert(&self, price: IngredientPrice) -> Result<bool, PriceCatalogError> {
+        let mut prices = self.prices.write()
+            .map_err(|e| PriceCatalogError::Unknown(e.to_string()))?;
+        Ok(prices.insert(price.key(), price).is_none())
+    }
+
+    async fn delete(&self, ingredient: &str) -> Result<bool, PriceCatalogError> {
+        let mut prices = self.prices.write()
+            .map_err(|e| PriceCatalogError::Unknown(e.to_string()))?;
+        Ok(prices.remove(&price_key(ingredient)).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_load_a_toml_or_csv_price_list() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/prices.toml");
+        let from_toml = InMemoryPriceCatalog::from_price_list(d, "EUR").unwrap();
+
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("src/tests/prices.csv");
+        let from_csv = InMemoryPriceCatalog::from_price_list(d, "USD").unwrap();
+
+        let egg = IngredientPrice::new("egg", 0.3, 1.0, None).unwrap();
+        assert_eq!(from_toml.find_one("EGG").await.unwrap(), Some(egg.clone()));
+        assert_eq!(from_csv.find_one("egg").await.unwrap(), Some(egg));
+        assert_eq!(from_csv.currency(), "USD");
+    }
+
+    #[test]
+    fn should_not_load_a_price_list_pricing_an_ingredient_twice() {
+        let mut path = std::env::temp_dir();
+        path.push("duplicate_prices.csv");
+        std::fs::write(&path, "ingredient,price,quantity,unit\nham,12.5,1,kg\negg,0.3,,\nHam,1.2,1,slice\n").unwrap();
+
+        let result = InMemoryPriceCatalog::from_price_list(&path, "EUR");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.err(), Some(String::from("Invalid price list row 3: Ham is already priced in row 1")));
+    }
+
+    #[actix_rt::test]
+    async fn should_set_and_delete_a_price() {
+        let catalog = InMemoryPriceCatalog::new("EUR");
+        let ham = |price| IngredientPrice::new("Ham", price, 1.0, Some(Unit::Kilogram)).unwrap();
+
+        assert!(catalog.upsert(ham(12.5)).await.unwrap());
+        assert!(!catalog.upsert(ham(13.0)).await.unwrap());
+        assert_eq!(catalog.find_all().await.unwrap(), vec![ham(13.0)]);
+
+        assert!(catalog.delete("ham").await.unwrap());
+        assert!(!catalog.delete("ham").await.unwrap());
+    }
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+
+use crate::domain::prices::IngredientPrice;
+
+pub mod in_memory_catalog;
+
+#[derive(Debug)]
+pub enum PriceCatalogError {
+    Unknown(String)
+}
+
+#[async_trait]
+pub trait PriceCatalog {
+
+    /// The currency all the prices are in
+    fn currency(&self) -> &str;
+
+    /// All the prices, sorted by ingredient
+    async fn find_all(&self) -> Result<Vec<IngredientPrice>, PriceCatalogError>;
+
+    /// The price of an ingredient, case and accent insensitive
+    async fn find_one(&self, ingredient: &str) -> Result<Option<IngredientPrice>, PriceCatalogError>;
+
+    /// Set the price of an ingredient, returning whether it had none before
+    async fn upsert(&self, price: IngredientPrice) -> Result<bool, PriceCatalogError>;
+
+    /// Forget the price of an ingredient, returning whether it had one
+    async fn delete(&self, ingredient: &str) -> Result<bool, PriceCatalogError>;
+}
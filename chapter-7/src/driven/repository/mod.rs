@@ -1,7 +1,9 @@
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 
 use crate::domain::Entity;
+use crate::domain::sandwich::{Ingredient, Unit};
 
 pub mod mongo_repository;
 pub mod sql_repository;
@@ -10,7 +12,80 @@ pub mod sql_repository;
 pub struct FindSandwich {
     pub id: Option<String>,
     pub name: String,
-    pub ingredients: Vec<String>,
+    pub ingredients: IngredientFilter,
+}
+
+/// Criteria on the ingredients of a sandwich, matched case insensitively
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IngredientFilter {
+    /// every one of them must be present
+    pub all_of: Vec<String>,
+    /// at least one of them must be present
+    pub any_of: Vec<String>,
+    /// none of them may be present
+    pub none_of: Vec<String>,
+}
+
+impl IngredientFilter {
+    pub fn all_of(ingredients: Vec<String>) -> Self {
+        IngredientFilter {
+            all_of: ingredients,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.all_of.is_empty() && self.any_of.is_empty() && self.none_of.is_empty()
+    }
+}
+
+/// An ingredient as persisted, structured, or as the free-text line written before ingredients were structured
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum StoredIngredient {
+    Structured {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quantity: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<Unit>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+    Line(String),
+}
+
+impl From<&Ingredient> for StoredIngredient {
+    fn from(i: &Ingredient) -> Self {
+        StoredIngredient::Structured {
+            name: i.name().to_string(),
+            quantity: i.quantity(),
+            unit: i.unit(),
+            note: i.note().map(String::from),
+        }
+    }
+}
+
+impl TryFrom<StoredIngredient> for Ingredient {
+    type Error = String;
+
+    fn try_from(stored: StoredIngredient) -> Result<Self, Self::Error> {
+        match stored {
+            StoredIngredient::Structured { name, quantity, unit, note } => Ingredient::new(name, quantity, unit, note),
+            // a line read as nothing better is kept whole as the name, rather than failing the whole sandwich
+            StoredIngredient::Line(line) => Ingredient::parse(&line)
+                .or_else(|_| Ingredient::new(line, None, None, None)),
+        }
+    }
+}
+
+/// read stored ingredients, parsing the legacy lines
+pub fn to_ingredients(stored: Vec<StoredIngredient>) -> Result<Vec<Ingredient>, String> {
+    stored.into_iter().map(Ingredient::try_from).collect()
+}
+
+pub fn to_stored_ingredients(ingredients: &[Ingredient]) -> Vec<StoredIngredient> {
+    ingredients.iter().map(StoredIngredient::from).collect()
 }
 
 #[derive(Debug)]
@@ -34,6 +109,7 @@ pub enum RepoFindAllError {
 pub enum RepoUpdateError {
     InvalidData(String),
     NotFound,
+    VersionMismatch,
     Unknown(String)
 }
 
@@ -41,6 +117,7 @@ pub enum RepoUpdateError {
 pub enum RepoDeleteError {
     NotFound,
     InvalidData(String),
+    VersionMismatch,
     Unknown(String)
 }
 
@@ -56,10 +133,37 @@ pub trait Repository<T> where T: Entity {
     /// Find and return all records corresponding to the search criteria from the persistence system
     async fn find_all(&self, sandwich: FindSandwich) -> Result<Vec<T>, RepoFindAllError>;
 
-    /// Update one single record already present in the persistence system
+    /// Stream every record from the persistence system, reading them as they are consumed
+    /// instead of loading the whole collection in memory
+    async fn stream_all(&self) -> Result<BoxStream<'static, Result<T, RepoFindAllError>>, RepoFindAllError>;
+
+    /// Find and return all records whose id is among the received ones, in a single round trip
+    async fn find_many_by_ids(&self, ids: Vec<String>) -> Result<Vec<T>, RepoFindAllError>;
+
+    /// Find and return all records whose name is among the received ones, in a single round trip
+    async fn find_many_by_names(&self, names: Vec<String>) -> Result<Vec<T>, RepoFindAllError>;
+
+    /// Full-text search on names and ingredients, returning at most `limit` records, the most relevant first
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<T>, RepoFindAllError>;
+
+    /// Update one single record already present in the persistence system.
+    /// When the entity carries a version, the record is updated only if its version still matches
     async fn update(&self, sandwich: T) -> Result<T, RepoUpdateError>;
 
-    /// Delete one single record from the persistence system
-    async fn delete(&self, id: &str) -> Result<(), RepoDeleteError>;
+    /// Delete one single record from the persistence system.
+    /// When a version is received, the record is deleted only if its version still matches
+    async fn delete(&self, id: &str, version: Option<i64>) -> Result<(), RepoDeleteError>;
+
+    /// Insert all the received entities, in as few round trips as possible.
+    /// One result is returned for each entity, in the same order
+    async fn create_many(&self, sandwiches: Vec<T>) -> Vec<Result<T, RepoCreateError>>;
+
+    /// Update all the received entities, checking their versions as `update` does.
+    /// One result is returned for each entity, in the same order
+    async fn update_many(&self, sandwiches: Vec<T>) -> Vec<Result<T, RepoUpdateError>>;
+
+    /// Delete all the records with the received ids and optional versions.
+    /// One result is returned for each id, in the same order
+    async fn delete_many(&self, ids: Vec<(String, Option<i64>)>) -> Vec<Result<(), RepoDeleteError>>;
 }
 
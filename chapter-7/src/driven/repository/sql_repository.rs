@@ -1,18 +1,31 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::to_string;
 use sqlx::{Error, FromRow, MySql, Pool, query_as};
 use sqlx::mysql::MySqlPoolOptions;
 
 use crate::config::MariaDBConfig;
-use crate::domain::sandwich::{Sandwich, SandwichType};
-use crate::driven::repository::{FindSandwich, RepoCreateError, RepoDeleteError, RepoFindAllError, RepoSelectError, Repository, RepoUpdateError};
+use crate::domain::sandwich::{PreparationStep, Sandwich, SandwichServings, SandwichTag, SandwichType};
+use crate::domain::search::SearchIndex;
+use crate::driven::repository::{FindSandwich, IngredientFilter, RepoCreateError, RepoDeleteError, RepoFindAllError, RepoSelectError, Repository, RepoUpdateError, StoredIngredient, to_ingredients, to_stored_ingredients};
+use crate::helpers::empty_find_sandwich;
 
 const SANDWICH_TABLE: &str = "sandwich";
 const SANDWICH_ID_FIELD: &str = "id";
 const SANDWICH_NAME_FIELD: &str = "name";
 const SANDWICH_INGREDIENTS_FIELD: &str = "ingredients";
 const SANDWICH_STARS_FIELD: &str = "stars";
+const SANDWICH_SERVINGS_FIELD: &str = "servings";
+const SANDWICH_STEPS_FIELD: &str = "steps";
+const SANDWICH_TYPE_FIELD: &str = "sandwich_type";
+const SANDWICH_TAGS_FIELD: &str = "tags";
+// rows inserted by a single statement, so that a large batch does not hold its locks for too long
+const MAX_ROWS_PER_INSERT: usize = 1000;
+// rows read per query when streaming the whole table
+const ROWS_PER_PAGE: usize = 500;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct SandwichSql {
@@ -20,6 +33,10 @@ pub struct SandwichSql {
     name: String,
     ingredients: String,
     stars: i32,
+    servings: i32,
+    steps: String,
+    sandwich_type: String,
+    tags: String,
 }
 
 impl From<Sandwich> for SandwichSql {
@@ -29,13 +46,17 @@ impl From<Sandwich> for SandwichSql {
             None => ""
         };
 
-        let ingredients_json = serde_json::to_string(sandwich.ingredients().value()).unwrap();
+        let ingredients_json = serde_json::to_string(&to_stored_ingredients(sandwich.ingredients().value())).unwrap();
 
         let sand_sql = SandwichSql {
             id: id.parse::<i64>().unwrap(),
             name: sandwich.name().value().to_string(),
             ingredients: ingredients_json,
             stars: 0,
+            servings: sandwich.servings().value() as i32,
+            steps: serde_json::to_string(sandwich.steps().value()).unwrap(),
+            sandwich_type: sandwich.sandwich_type().name().to_string(),
+            tags: serde_json::to_string(sandwich.tags()).unwrap(),
         };
 
         sand_sql
@@ -46,19 +67,72 @@ impl TryInto<Sandwich> for SandwichSql {
     type Error = String;
 
     fn try_into(self) -> Result<Sandwich, Self::Error> {
+        let servings = SandwichServings::try_from(u32::try_from(self.servings).unwrap_or(0))?;
+        let tags = serde_json::from_str::<Vec<SandwichTag>>(&self.tags).map_err(|e| e.to_string())?;
+
         Sandwich::new(self.id.to_string(),
                       self.name,
-                      serde_json::from_str(&self.ingredients).unwrap(),
-                      SandwichType::Undefined,
+                      to_ingredients(serde_json::from_str::<Vec<StoredIngredient>>(&self.ingredients).map_err(|e| e.to_string())?)?,
+                      serde_json::from_str::<Vec<PreparationStep>>(&self.steps).map_err(|e| e.to_string())?,
+                      SandwichType::parse(&self.sandwich_type).unwrap_or(SandwichType::Undefined),
                       self.stars)
+            .map(|s| s.with_tags(tags).with_servings(servings))
+    }
+}
+
+impl SandwichSql {
+    /// the sandwich of the row, an invalid one being an error naming the row rather than a row left out
+    fn into_sandwich(self) -> Result<Sandwich, RepoFindAllError> {
+        let id = self.id;
+        self.try_into()
+            .map_err(|e| RepoFindAllError::Unknown(format!("Invalid sandwich {}: {}", id, e)))
     }
 }
 
+/// translate the criteria in SQL conditions and their parameters.
+/// Ingredients are stored as a JSON array of objects, their names are lowercased on both sides to match case insensitively
+fn compose_conditions(sandwich: &FindSandwich) -> (Vec<String>, Vec<String>) {
+    let mut conditions = vec![];
+    let mut params = vec![];
+
+    if !sandwich.name.is_empty() {
+        conditions.push(format!("{} = ?", SANDWICH_NAME_FIELD));
+        params.push(sandwich.name.clone());
+    }
+
+    let IngredientFilter { all_of, any_of, none_of } = &sandwich.ingredients;
+    let contains = format!("JSON_CONTAINS(LOWER(JSON_EXTRACT({}, '$[*].name')), JSON_QUOTE(LOWER(?)))", SANDWICH_INGREDIENTS_FIELD);
+
+    for ingredient in all_of {
+        conditions.push(contains.clone());
+        params.push(ingredient.clone());
+    }
+    if !any_of.is_empty() {
+        conditions.push(format!("({})", vec![contains.as_str(); any_of.len()].join(" OR ")));
+        params.extend(any_of.iter().cloned());
+    }
+    for ingredient in none_of {
+        conditions.push(format!("NOT {}", contains));
+        params.push(ingredient.clone());
+    }
+
+    (conditions, params)
+}
+
+/// The search index of all the sandwiches, built by the first search after a write
+#[derive(Default)]
+struct SearchCache {
+    // bumped by every write, so that an index built from rows read before it is not kept
+    generation: u64,
+    index: Option<Arc<SearchIndex>>,
+}
 
 #[derive(Clone)]
 pub struct SandwichSqlRepository {
     database: String,
     conn_uri: String,
+    // shared by the clones, as they write to the same table
+    search_cache: Arc<RwLock<SearchCache>>,
 }
 
 impl SandwichSqlRepository {
@@ -71,15 +145,60 @@ impl SandwichSqlRepository {
         Ok(SandwichSqlRepository {
             database: config.database,
             conn_uri,
+            search_cache: Arc::new(RwLock::new(SearchCache::default())),
         })
     }
 
+    /// drop the search index after a write, the next search rebuilds it
+    fn invalidate_search(&self) {
+        let mut cache = self.search_cache.write().unwrap_or_else(PoisonError::into_inner);
+        cache.generation += 1;
+        cache.index = None;
+    }
+
     async fn open_connection(&self) -> Result<Pool<MySql>, Error> {
         MySqlPoolOptions::new()
             .max_connections(5)     // FIXME
             .connect(self.conn_uri.as_str())
             .await
     }
+
+    /// Rewrite as objects the ingredients stored as free-text lines, before they were structured,
+    /// for the filters on the ingredient names to match them. Returns the number of migrated sandwiches
+    pub async fn migrate_ingredients(&self) -> Result<u64, String> {
+        let pool = self.open_connection().await
+            .map_err(|e| e.to_string())?;
+
+        let query = format!("SELECT {}, {} FROM {} WHERE JSON_TYPE(JSON_EXTRACT({}, '$[0]')) = 'STRING'",
+                            SANDWICH_ID_FIELD, SANDWICH_INGREDIENTS_FIELD, SANDWICH_TABLE, SANDWICH_INGREDIENTS_FIELD);
+        let legacy: Vec<(i64, String)> = query_as(&query)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let update = format!("UPDATE {} SET {} = ? WHERE {} = ?", SANDWICH_TABLE, SANDWICH_INGREDIENTS_FIELD, SANDWICH_ID_FIELD);
+        let mut migrated = 0;
+        for (id, ingredients) in legacy {
+            sqlx::query(&update)
+                .bind(structured_ingredients(&ingredients)?)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .map_err(|e| e.to_string())?;
+            migrated += 1;
+        }
+
+        if migrated > 0 {
+            self.invalidate_search();
+        }
+        Ok(migrated)
+    }
+}
+
+/// the JSON of stored ingredients, with the legacy lines parsed into objects
+fn structured_ingredients(json: &str) -> Result<String, String> {
+    let stored = serde_json::from_str::<Vec<StoredIngredient>>(json).map_err(|e| e.to_string())?;
+    to_string(&to_stored_ingredients(&to_ingredients(stored)?)).map_err(|e| e.to_string())
 }
 
 #[async_trait]
@@ -90,18 +209,27 @@ impl Repository<Sandwich> for SandwichSqlRepository {
         let poll = self.open_connection().await
             .map_err(|e| RepoCreateError::Unknown(e.to_string()))?;
 
-        let query = format!("INSERT INTO {} ({}, {}, {}) VALUES (?, ?, ?)",
-                            SANDWICH_TABLE, SANDWICH_NAME_FIELD, SANDWICH_INGREDIENTS_FIELD, SANDWICH_STARS_FIELD);
+        let query = format!("INSERT INTO {} ({}, {}, {}, {}, {}, {}, {}) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                            SANDWICH_TABLE, SANDWICH_NAME_FIELD, SANDWICH_INGREDIENTS_FIELD, SANDWICH_STARS_FIELD, SANDWICH_SERVINGS_FIELD, SANDWICH_STEPS_FIELD, SANDWICH_TYPE_FIELD, SANDWICH_TAGS_FIELD);
 
-        let ingredients_json = to_string(&*sandwich.ingredients().value())
+        let ingredients_json = to_string(&to_stored_ingredients(sandwich.ingredients().value()))
+            .map_err(|e| RepoCreateError::Unknown(e.to_string()))?;
+        let steps_json = to_string(sandwich.steps().value())
+            .map_err(|e| RepoCreateError::Unknown(e.to_string()))?;
+        let tags_json = to_string(sandwich.tags())
             .map_err(|e| RepoCreateError::Unknown(e.to_string()))?;
 
         let result = sqlx::query(&query)
             .bind(sandwich.name().value())
             .bind(ingredients_json)
             .bind(sandwich.stars().value())
+            .bind(sandwich.servings().value())
+            .bind(steps_json)
+            .bind(sandwich.sandwich_type().name())
+            .bind(tags_json)
             .execute(&poll)
             .await;
+        self.invalidate_search();
 
         match result {
             Ok(_) => Ok(sandwich),
@@ -117,31 +245,140 @@ impl Repository<Sandwich> for SandwichSqlRepository {
         let pool = self.open_connection().await
             .map_err(|e| RepoFindAllError::Unknown(e.to_string()))?;
 
-        let query = format!("SELECT * FROM {}", SANDWICH_TABLE);
+        let (conditions, params) = compose_conditions(&sandwich);
+        let query = if conditions.is_empty() {
+            format!("SELECT * FROM {}", SANDWICH_TABLE)
+        } else {
+            format!("SELECT * FROM {} WHERE {}", SANDWICH_TABLE, conditions.join(" AND "))
+        };
+
+        let mut select = query_as::<MySql, SandwichSql>(&query);
+        for param in params.iter() {
+            select = select.bind(param);
+        }
 
-        let result: Result<Vec<SandwichSql>, sqlx::Error> = query_as::<MySql, SandwichSql>(&query)
+        let result: Result<Vec<SandwichSql>, sqlx::Error> = select
             .fetch_all(&pool)
             .await;
 
         match result {
-            Ok(sandwich_sql_vec) => {
-                let sandwiches: Vec<Sandwich> = sandwich_sql_vec
-                    .into_iter()
-                    .map(|sandwich_sql| sandwich_sql.try_into())
-                    .filter_map(Result::ok)
-                    .collect();
-
-                Ok(sandwiches)
-            }
+            Ok(sandwich_sql_vec) => sandwich_sql_vec
+                .into_iter()
+                .map(SandwichSql::into_sandwich)
+                .collect(),
             Err(e) => Err(RepoFindAllError::Unknown(e.to_string())),
         }
     }
 
+    /// stream all the sandwiches, reading them a page at a time in id order. An invalid row is an error item of the stream
+    async fn stream_all(&self) -> Result<BoxStream<'static, Result<Sandwich, RepoFindAllError>>, RepoFindAllError> {
+        let pool = self.open_connection().await
+            .map_err(|e| RepoFindAllError::Unknown(e.to_string()))?;
+
+        let pages = stream::try_unfold((pool, Some(0_i64)), |(pool, after)| async move {
+            let after = match after {
+                Some(after) => after,
+                None => return Ok(None),
+            };
+
+            let query = format!("SELECT * FROM {} WHERE {} > ? ORDER BY {} LIMIT {}", SANDWICH_TABLE, SANDWICH_ID_FIELD, SANDWICH_ID_FIELD, ROWS_PER_PAGE);
+            let page: Vec<SandwichSql> = query_as::<MySql, SandwichSql>(&query)
+                .bind(after)
+                .fetch_all(&pool)
+                .await
+                .map_err(|e| RepoFindAllError::Unknown(e.to_string()))?;
+
+            // a short page is the last one
+            let next = if page.len() < ROWS_PER_PAGE { None } else { page.last().map(|s| s.id) };
+            let sandwiches: Vec<Result<Sandwich, RepoFindAllError>> = page
+                .into_iter()
+                .map(SandwichSql::into_sandwich)
+                .collect();
+
+            Ok(Some((stream::iter(sandwiches), (pool, next))))
+        });
+
+        Ok(pages.try_flatten().boxed())
+    }
+
+    async fn find_many_by_ids(&self, ids: Vec<String>) -> Result<Vec<Sandwich>, RepoFindAllError> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let pool = self.open_connection().await
+            .map_err(|e| RepoFindAllError::Unknown(e.to_string()))?;
+
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let query = format!("SELECT * FROM {} WHERE {} IN ({})", SANDWICH_TABLE, SANDWICH_ID_FIELD, placeholders);
+
+        let mut select = query_as::<MySql, SandwichSql>(&query);
+        for id in ids.iter() {
+            select = select.bind(id);
+        }
+
+        let result: Result<Vec<SandwichSql>, sqlx::Error> = select
+            .fetch_all(&pool)
+            .await;
+
+        match result {
+            Ok(sandwich_sql_vec) => sandwich_sql_vec
+                .into_iter()
+                .map(SandwichSql::into_sandwich)
+                .collect(),
+            Err(e) => Err(RepoFindAllError::Unknown(e.to_string())),
+        }
+    }
+
+    async fn find_many_by_names(&self, names: Vec<String>) -> Result<Vec<Sandwich>, RepoFindAllError> {
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let pool = self.open_connection().await
+            .map_err(|e| RepoFindAllError::Unknown(e.to_string()))?;
+
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let query = format!("SELECT * FROM {} WHERE {} IN ({})", SANDWICH_TABLE, SANDWICH_NAME_FIELD, placeholders);
+
+        let mut select = query_as::<MySql, SandwichSql>(&query);
+        for name in names.iter() {
+            select = select.bind(name);
+        }
+
+        select.fetch_all(&pool).await
+            .map_err(|e| RepoFindAllError::Unknown(e.to_string()))?
+            .into_iter()
+            .map(SandwichSql::into_sandwich)
+            .collect()
+    }
+
+    // the read model is small enough to be searched through an in-process index of all its records,
+    // kept until the next write through this repository
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<Sandwich>, RepoFindAllError> {
+        let generation = {
+            let cache = self.search_cache.read().unwrap_or_else(PoisonError::into_inner);
+            if let Some(index) = &cache.index {
+                return Ok(index.search(query, limit));
+            }
+            cache.generation
+        };
+
+        let index = Arc::new(SearchIndex::new(self.find_all(empty_find_sandwich()).await?));
+
+        let mut cache = self.search_cache.write().unwrap_or_else(PoisonError::into_inner);
+        if cache.generation == generation {
+            cache.index = Some(index.clone());
+        }
+        Ok(index.search(query, limit))
+    }
+
     async fn update(&self, _: Sandwich) -> Result<Sandwich, RepoUpdateError> {
         Err(RepoUpdateError::Unknown("Not implemented".to_string()))
     }
 
-    async fn delete(&self, id: &str) -> Result<(), RepoDeleteError> {
+    // the read model is rebuilt from the write model, so versions are not checked
+    async fn delete(&self, id: &str, _version: Option<i64>) -> Result<(), RepoDeleteError> {
         let pool = self.open_connection().await
             .map_err(|e| RepoDeleteError::Unknown(e.to_string()))?;
 
@@ -151,14 +388,131 @@ impl Repository<Sandwich> for SandwichSqlRepository {
             .bind(id)
             .execute(&pool)
             .await;
+        self.invalidate_search();
 
         match result {
             Ok(_) => Ok(()),
             Err(e) => Err(RepoDeleteError::Unknown(e.to_string())),
         }
     }
+
+    // each chunk is inserted by a single statement, which either fails or succeeds as a whole
+    async fn create_many(&self, sandwiches: Vec<Sandwich>) -> Vec<Result<Sandwich, RepoCreateError>> {
+
+        let pool = match self.open_connection().await {
+            Ok(p) => p,
+            Err(e) => return sandwiches.iter().map(|_| Err(RepoCreateError::Unknown(e.to_string()))).collect()
+        };
+
+        let mut results = Vec::with_capacity(sandwiches.len());
+
+        for chunk in sandwiches.chunks(MAX_ROWS_PER_INSERT) {
+            match insert_chunk(&pool, chunk).await {
+                Ok(created) => results.extend(created.into_iter().map(Ok)),
+                Err(e) => results.extend(chunk.iter().map(|_| Err(RepoCreateError::Unknown(e.clone())))),
+            }
+        }
+        self.invalidate_search();
+
+        results
+    }
+
+    async fn update_many(&self, sandwiches: Vec<Sandwich>) -> Vec<Result<Sandwich, RepoUpdateError>> {
+        sandwiches.iter()
+            .map(|_| Err(RepoUpdateError::Unknown("Not implemented".to_string())))
+            .collect()
+    }
+
+    // the read model is rebuilt from the write model, so versions are not checked
+    async fn delete_many(&self, ids: Vec<(String, Option<i64>)>) -> Vec<Result<(), RepoDeleteError>> {
+
+        if ids.is_empty() {
+            return vec![];
+        }
+
+        let pool = match self.open_connection().await {
+            Ok(p) => p,
+            Err(e) => return ids.iter().map(|_| Err(RepoDeleteError::Unknown(e.to_string()))).collect()
+        };
+
+        let query = format!("DELETE FROM {} WHERE {} = ?", SANDWICH_TABLE, SANDWICH_ID_FIELD);
+
+        // deleted one by one in a transaction, to tell the ids not found
+        let deleted = delete_ids(&pool, &query, &ids).await;
+        self.invalidate_search();
+
+        match deleted {
+            Ok(deleted) => deleted.into_iter()
+                .map(|found| if found { Ok(()) } else { Err(RepoDeleteError::NotFound) })
+                .collect(),
+            Err(e) => ids.iter().map(|_| Err(RepoDeleteError::Unknown(e.to_string()))).collect(),
+        }
+    }
+}
+
+/// the multi-row insert of the given number of sandwiches, returning their generated ids in the order of the rows
+fn compose_insert(rows: usize) -> String {
+    format!("INSERT INTO {} ({}, {}, {}, {}, {}, {}, {}) VALUES {} RETURNING {}",
+            SANDWICH_TABLE, SANDWICH_NAME_FIELD, SANDWICH_INGREDIENTS_FIELD, SANDWICH_STARS_FIELD, SANDWICH_SERVINGS_FIELD, SANDWICH_STEPS_FIELD, SANDWICH_TYPE_FIELD, SANDWICH_TAGS_FIELD,
+            vec!["(?, ?, ?, ?, ?, ?, ?)"; rows].join(", "),
+            SANDWICH_ID_FIELD)
 }
 
+/// the JSON columns of a sandwich row: its ingredients, steps and tags
+fn json_columns(sandwich: &Sandwich) -> Result<(String, String, String), serde_json::Error> {
+    Ok((to_string(&to_stored_ingredients(sandwich.ingredients().value()))?,
+        to_string(sandwich.steps().value())?,
+        to_string(sandwich.tags())?))
+}
+
+/// insert the sandwiches of a chunk in a single statement, returning them with their generated ids
+async fn insert_chunk(pool: &Pool<MySql>, chunk: &[Sandwich]) -> Result<Vec<Sandwich>, String> {
+    let columns = chunk.iter()
+        .map(json_columns)
+        .collect::<Result<Vec<(String, String, String)>, serde_json::Error>>()
+        .map_err(|e| e.to_string())?;
+
+    let query = compose_insert(chunk.len());
+    let mut insert = query_as::<MySql, (i64,)>(&query);
+    for (sandwich, (ingredients_json, steps_json, tags_json)) in chunk.iter().zip(columns) {
+        insert = insert
+            .bind(sandwich.name().value())
+            .bind(ingredients_json)
+            .bind(sandwich.stars().value())
+            .bind(sandwich.servings().value())
+            .bind(steps_json)
+            .bind(sandwich.sandwich_type().name())
+            .bind(tags_json);
+    }
+
+    let ids = insert.fetch_all(pool).await
+        .map_err(|e| e.to_string())?;
+    if ids.len() != chunk.len() {
+        return Err(format!("{} sandwiches inserted but {} ids returned", chunk.len(), ids.len()));
+    }
+
+    Ok(chunk.iter()
+        .zip(ids)
+        .map(|(sandwich, (id,))| sandwich.clone().with_id(id.to_string()))
+        .collect())
+}
+
+/// delete the received ids in a single transaction, telling for each of them whether a row was deleted
+async fn delete_ids(pool: &Pool<MySql>, query: &str, ids: &[(String, Option<i64>)]) -> Result<Vec<bool>, Error> {
+    let mut tx = pool.begin().await?;
+    let mut deleted = Vec::with_capacity(ids.len());
+
+    for (id, _) in ids {
+        let result = sqlx::query(query)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        deleted.push(result.rows_affected() > 0);
+    }
+
+    tx.commit().await?;
+    Ok(deleted)
+}
 
 fn create_connection_uri(config: &MariaDBConfig) -> String {
     format!("mysql://{}:{}@{}/{}",
@@ -171,4 +525,81 @@ fn create_connection_uri(config: &MariaDBConfig) -> String {
                     + port.to_string().as_str(),
             },
             config.database)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_compose_the_ingredient_conditions() {
+        let find = FindSandwich {
+            id: None,
+            name: String::from(""),
+            ingredients: IngredientFilter {
+                all_of: vec![String::from("Bread")],
+                any_of: vec![String::from("Cheese"), String::from("Gruyère")],
+                none_of: vec![String::from("Pork")],
+            },
+        };
+
+        let (conditions, params) = compose_conditions(&find);
+
+        assert_eq!(conditions, vec![
+            "JSON_CONTAINS(LOWER(JSON_EXTRACT(ingredients, '$[*].name')), JSON_QUOTE(LOWER(?)))",
+            "(JSON_CONTAINS(LOWER(JSON_EXTRACT(ingredients, '$[*].name')), JSON_QUOTE(LOWER(?))) OR JSON_CONTAINS(LOWER(JSON_EXTRACT(ingredients, '$[*].name')), JSON_QUOTE(LOWER(?))))",
+            "NOT JSON_CONTAINS(LOWER(JSON_EXTRACT(ingredients, '$[*].name')), JSON_QUOTE(LOWER(?)))",
+        ]);
+        assert_eq!(params, vec!["Bread", "Cheese", "Gruyère", "Pork"]);
+    }
+
+    #[test]
+    fn should_drop_the_cached_search_index_on_a_write() {
+        let repository = SandwichSqlRepository::new(&MariaDBConfig {
+            host: String::from("localhost"),
+            port: None,
+            user: String::from("user"),
+            password: String::from("password"),
+            database: String::from("sandwiches"),
+        }).unwrap();
+        repository.search_cache.write().unwrap().index = Some(Arc::new(SearchIndex::new(vec![])));
+
+        repository.clone().invalidate_search();
+
+        let cache = repository.search_cache.read().unwrap();
+        assert!(cache.index.is_none());
+        assert_eq!(cache.generation, 1);
+    }
+
+    #[test]
+    fn should_insert_a_chunk_in_a_single_statement() {
+        assert_eq!(compose_insert(2),
+                   "INSERT INTO sandwich (name, ingredients, stars, servings, steps, sandwich_type, tags) VALUES (?, ?, ?, ?, ?, ?, ?), (?, ?, ?, ?, ?, ?, ?) RETURNING id");
+    }
+
+    #[test]
+    fn should_tell_an_invalid_row_instead_of_leaving_it_out() {
+        let row = SandwichSql {
+            id: 7,
+            name: String::from("Hot dog"),
+            ingredients: String::from(r#"[{"name":"bun"}]"#),
+            stars: 0,
+            servings: 1,
+            steps: String::from("[]"),
+            sandwich_type: String::from("meat"),
+            tags: String::from("not json"),
+        };
+
+        match row.into_sandwich() {
+            Err(RepoFindAllError::Unknown(e)) => assert!(e.starts_with("Invalid sandwich 7: "), "{}", e),
+            Ok(_) => panic!("An invalid row must be an error"),
+        }
+    }
+
+    #[test]
+    fn should_structure_the_legacy_ingredient_lines() {
+        let structured = structured_ingredients(r#"["2 slices of bread, toasted","Salt, pepper","200"]"#).unwrap();
+
+        assert_eq!(structured, r#"[{"name":"bread","quantity":2.0,"unit":"slice","note":"toasted"},{"name":"Salt, pepper"},{"name":"200"}]"#);
+    }
+}
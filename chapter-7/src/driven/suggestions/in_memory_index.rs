@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{PoisonError, RwLock};
+
+use async_trait::async_trait;
+
+use crate::domain::sandwich::Sandwich;
+use crate::domain::search::tokenize;
+use crate::driven::suggestions::{Suggestion, SuggestionField, SuggestionIndex};
+
+/// Distinct values sorted by their normalized form, so that the values sharing a prefix are contiguous
+#[derive(Default)]
+struct PrefixTree {
+    values: BTreeMap<String, Suggestion>,
+}
+
+impl PrefixTree {
+    fn add(&mut self, value: &str) {
+        self.values.entry(key(value))
+            .or_insert_with(|| Suggestion { value: value.to_string(), count: 0 })
+            .count += 1;
+    }
+
+    fn remove(&mut self, value: &str) {
+        let key = key(value);
+        if let Some(suggestion) = self.values.get_mut(&key) {
+            suggestion.count -= 1;
+            if suggestion.count == 0 {
+                self.values.remove(&key);
+            }
+        }
+    }
+
+    fn starting_with(&self, prefix: &str) -> Vec<Suggestion> {
+        let prefix = key(prefix);
+        self.values.range(prefix.clone()..)
+            .take_while(|(k, _)| k.starts_with(&prefix))
+            .map(|(_, s)| s.clone())
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    // what is indexed for each sandwich, to unindex it on update and delete
+    sandwiches: HashMap<String, (String, Vec<String>)>,
+    names: PrefixTree,
+    ingredients: PrefixTree,
+}
+
+impl Entries {
+    fn unindex(&mut self, id: &str) {
+        if let Some((name, ingredients)) = self.sandwiches.remove(id) {
+            self.names.remove(&name);
+            for ingredient in distinct(&ingredients) {
+                self.ingredients.remove(&ingredient);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct InMemorySuggestionIndex {
+    entries: RwLock<Entries>,
+}
+
+impl InMemorySuggestionIndex {
+    /// new constructor function
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SuggestionIndex for InMemorySuggestionIndex {
+
+    async fn index(&self, sandwich: &Sandwich) {
+        let id = match sandwich.id().value() {
+            Some(id) => id.clone(),
+            None => return,
+        };
+        let name = sandwich.name().value().to_string();
+        let ingredients = sandwich.ingredients().names();
+
+        // the index only holds derived data, it stays usable after a panic of another writer
+        let mut entries = self.entries.write().unwrap_or_else(PoisonError::into_inner);
+        entries.unindex(&id);
+
+        entries.names.add(&name);
+        for ingredient in distinct(&ingredients) {
+            entries.ingredients.add(&ingredient);
+        }
+        entries.sandwiches.insert(id, (name, ingredients));
+    }
+
+    async fn remove(&self, id: &str) {
+        self.entries.write().unwrap_or_else(PoisonError::into_inner).unindex(id);
+    }
+
+    async fn suggest(&self, field: SuggestionField, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+
+        let mut suggestions = match field {
+            SuggestionField::Name => entries.names.starting_with(prefix),
+            SuggestionField::Ingredient => entries.ingredients.starting_with(prefix),
+        };
+
+        suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        suggestions.truncate(limit);
+        suggestions
+    }
+}
+
+/// the normalized form of a value, lowercase, without accents and with single spaces between words
+fn key(value: &str) -> String {
+    tokenize(value).join(" ")
+}
+
+/// a sandwich listing an ingredient twice counts once
+fn distinct(ingredients: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    ingredients.iter()
+        .filter(|i| seen.insert(key(i)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::sandwich::SandwichType;
+
+    use super::*;
+
+    fn sandwich(id: &str, name: &str, ingredients: &[&str]) -> Sandwich {
+        Sandwich::new(id.to_string(), name.to_string(), ingredients.iter().map(|i| i.to_string()).collect::<Vec<String>>(), vec![], SandwichType::Undefined, 0).unwrap()
+    }
+
+    async fn index() -> InMemorySuggestionIndex {
+        let index = InMemorySuggestionIndex::new();
+        index.index(&sandwich("1", "Ham and cheese", &["Ham", "Cheese", "Bread"])).await;
+        index.index(&sandwich("2", "Hamburger", &["Beef", "Cheese", "Bun"])).await;
+        index.index(&sandwich("3", "Croque-monsieur", &["Ham", "Gruyère", "Bread"])).await;
+        index
+    }
+
+    fn values(suggestions: Vec<Suggestion>) -> Vec<(String, usize)> {
+        suggestions.into_iter().map(|s| (s.value, s.count)).collect()
+    }
+
+    #[actix_rt::test]
+    async fn should_suggest_the_most_frequent_values_first() {
+        let index = index().await;
+
+        assert_eq!(values(index.suggest(SuggestionField::Name, "HAM", 10).await),
+                   vec![(String::from("Ham and cheese"), 1), (String::from("Hamburger"), 1)]);
+        assert_eq!(values(index.suggest(SuggestionField::Ingredient, "", 2).await),
+                   vec![(String::from("Bread"), 2), (String::from("Cheese"), 2)]);
+        assert_eq!(values(index.suggest(SuggestionField::Ingredient, "gruye", 10).await),
+                   vec![(String::from("Gruyère"), 1)]);
+    }
+
+    #[actix_rt::test]
+    async fn should_follow_updates_and_deletions() {
+        let index = index().await;
+
+        index.index(&sandwich("2", "Hamburger", &["Beef", "Bun"])).await;
+        index.remove("3").await;
+
+        assert_eq!(values(index.suggest(SuggestionField::Ingredient, "", 10).await),
+                   vec![(String::from("Beef"), 1), (String::from("Bread"), 1), (String::from("Bun"), 1), (String::from("Cheese"), 1), (String::from("Ham"), 1)]);
+        assert_eq!(values(index.suggest(SuggestionField::Name, "cro", 10).await), vec![]);
+    }
+}
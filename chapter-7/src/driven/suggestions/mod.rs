@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+
+use crate::domain::sandwich::Sandwich;
+
+pub mod in_memory_index;
+
+/// The values that can be suggested while typing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SuggestionField {
+    Name,
+    Ingredient,
+}
+
+/// A distinct value and the number of sandwiches having it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub value: String,
+    pub count: usize,
+}
+
+#[async_trait]
+pub trait SuggestionIndex {
+
+    /// Index the name and ingredients of a sandwich, replacing those indexed under its id
+    async fn index(&self, sandwich: &Sandwich);
+
+    /// Forget the sandwich with the received id
+    async fn remove(&self, id: &str);
+
+    /// The values starting with the prefix, case and accent insensitive, the most frequent first
+    async fn suggest(&self, field: SuggestionField, prefix: &str, limit: usize) -> Vec<Suggestion>;
+}
@@ -0,0 +1,166 @@
+use std::cmp::max;
+use std::collections::HashMap;
+use std::fmt;
+
+use juniper::{DefaultScalarValue, Definition, Selection};
+use juniper::parser::parse_document_source;
+
+use crate::config::GraphQLConfig;
+use crate::driving::graphql::schema::Schema;
+
+#[derive(Debug, PartialEq)]
+pub enum LimitError {
+    TooDeep { depth: usize, max_depth: usize },
+    TooComplex { complexity: usize, max_complexity: usize },
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LimitError::TooDeep { depth, max_depth } =>
+                write!(f, "Query depth {} exceeds the maximum allowed depth of {}", depth, max_depth),
+            LimitError::TooComplex { complexity, max_complexity } =>
+                write!(f, "Query complexity {} exceeds the maximum allowed complexity of {}", complexity, max_complexity),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct QueryCost {
+    depth: usize,
+    complexity: usize,
+}
+
+type Fragments<'a> = HashMap<&'a str, &'a Vec<Selection<'a, DefaultScalarValue>>>;
+
+/// Check the received query against the configured depth and complexity limits before executing it.
+/// Every field counts 1 towards the complexity, introspection fields are not counted.
+pub fn check_query_limits(query: &str, schema: &Schema, config: &GraphQLConfig) -> Result<(), LimitError> {
+
+    // an unparsable document is left to juniper, that reports the syntax error on execution
+    let document = match parse_document_source(query, &schema.schema) {
+        Ok(d) => d,
+        Err(_) => return Ok(())
+    };
+
+    let fragments: Fragments = document.iter()
+        .filter_map(|definition| match definition {
+            Definition::Fragment(f) => Some((f.item.name.item, &f.item.selection_set)),
+            _ => None
+        })
+        .collect();
+
+    for definition in document.iter() {
+        if let Definition::Operation(operation) = definition {
+            let cost = measure(&operation.item.selection_set, &fragments, &mut vec![]);
+
+            if cost.depth > config.max_depth {
+                return Err(LimitError::TooDeep { depth: cost.depth, max_depth: config.max_depth });
+            }
+
+            if cost.complexity > config.max_complexity {
+                return Err(LimitError::TooComplex { complexity: cost.complexity, max_complexity: config.max_complexity });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn measure<'a>(selections: &'a [Selection<'a, DefaultScalarValue>], fragments: &Fragments<'a>, visiting: &mut Vec<&'a str>) -> QueryCost {
+
+    let mut cost = QueryCost::default();
+
+    for selection in selections {
+        let nested = match selection {
+            Selection::Field(field) => {
+                if field.item.name.item.starts_with("__") {
+                    continue;
+                }
+
+                let children = match &field.item.selection_set {
+                    Some(s) => measure(s, fragments, visiting),
+                    None => QueryCost::default()
+                };
+
+                QueryCost {
+                    depth: children.depth + 1,
+                    complexity: children.complexity + 1,
+                }
+            }
+            Selection::FragmentSpread(spread) => {
+                let name = spread.item.name.item;
+
+                // unknown or cyclic fragments are rejected later by juniper validation
+                match fragments.get(name) {
+                    Some(s) if !visiting.contains(&name) => {
+                        visiting.push(name);
+                        let fragment_cost = measure(s, fragments, visiting);
+                        visiting.pop();
+                        fragment_cost
+                    }
+                    _ => QueryCost::default()
+                }
+            }
+            Selection::InlineFragment(inline) => measure(&inline.item.selection_set, fragments, visiting),
+        };
+
+        cost.depth = max(cost.depth, nested.depth);
+        cost.complexity += nested.complexity;
+    }
+
+    cost
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driving::graphql::schema::create_schema;
+
+    use super::*;
+
+    fn limits(max_depth: usize, max_complexity: usize) -> GraphQLConfig {
+        GraphQLConfig {
+            max_depth,
+            max_complexity,
+            timeout_ms: 1000,
+            allow_list_path: None,
+            max_persisted_queries: 10,
+        }
+    }
+
+    #[test]
+    fn should_accept_a_query_within_limits() {
+        let query = "{ sandwiches { name ingredients stars } }";
+
+        let res = check_query_limits(query, &create_schema(), &limits(2, 4));
+
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn should_reject_a_query_too_deep() {
+        let query = "{ sandwiches { name } }";
+
+        let res = check_query_limits(query, &create_schema(), &limits(1, 100));
+
+        assert_eq!(res, Err(LimitError::TooDeep { depth: 2, max_depth: 1 }));
+    }
+
+    #[test]
+    fn should_reject_a_query_too_complex_counting_fragments() {
+        let query = "{ sandwiches { ...recipe } } fragment recipe on SandwichGraphQL { name ingredients stars }";
+
+        let res = check_query_limits(query, &create_schema(), &limits(10, 3));
+
+        assert_eq!(res, Err(LimitError::TooComplex { complexity: 4, max_complexity: 3 }));
+    }
+
+    #[test]
+    fn should_not_count_introspection_fields() {
+        let query = "{ __schema { types { name fields { name type { name ofType { name } } } } } }";
+
+        let res = check_query_limits(query, &create_schema(), &limits(1, 1));
+
+        assert_eq!(res, Ok(()));
+    }
+}
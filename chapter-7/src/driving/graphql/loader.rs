@@ -0,0 +1,115 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::domain::sandwich::Sandwich;
+use crate::driven::repository::Repository;
+
+/// DataLoader-style batching of sandwich lookups, living as long as a single GraphQL request.
+/// The ids requested by resolvers running concurrently are collected and fetched with a single
+/// `find_many_by_ids` call, then served from a per request cache.
+pub struct SandwichLoader {
+    repository: Arc<dyn Repository<Sandwich> + Send + Sync>,
+    pending: Mutex<HashSet<String>>,
+    loaded: Mutex<HashMap<String, Option<Sandwich>>>,
+    dispatching: tokio::sync::Mutex<()>,
+}
+
+impl SandwichLoader {
+    /// new constructor function
+    pub fn new(repository: Arc<dyn Repository<Sandwich> + Send + Sync>) -> Self {
+        SandwichLoader {
+            repository,
+            pending: Mutex::new(HashSet::new()),
+            loaded: Mutex::new(HashMap::new()),
+            dispatching: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// load a sandwich by id, None if it does not exist
+    pub async fn load(&self, id: &str) -> Result<Option<Sandwich>, String> {
+        if let Some(cached) = self.cached(id) {
+            return Ok(cached);
+        }
+
+        self.pending.lock().unwrap().insert(id.to_string());
+
+        // give the sibling resolvers the chance to enqueue their ids before dispatching
+        actix_rt::task::yield_now().await;
+
+        self.dispatch().await?;
+
+        // an id neither loaded nor pending belonged to a batch that failed
+        self.cached(id)
+            .ok_or_else(|| format!("Error loading the sandwich {}", id))
+    }
+
+    fn cached(&self, id: &str) -> Option<Option<Sandwich>> {
+        self.loaded.lock().unwrap().get(id).cloned()
+    }
+
+    /// fetch all the pending ids in one batch, waiting for any batch already in flight
+    async fn dispatch(&self) -> Result<(), String> {
+        let _guard = self.dispatching.lock().await;
+
+        let ids: Vec<String> = self.pending.lock().unwrap().drain().collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let sandwiches = self.repository.find_many_by_ids(ids.clone()).await
+            .map_err(|e| format!("Error loading sandwiches: {:?}", e))?;
+
+        let mut loaded = self.loaded.lock().unwrap();
+        for id in ids {
+            loaded.insert(id, None);
+        }
+        for sandwich in sandwiches {
+            if let Some(id) = sandwich.id().value().clone() {
+                loaded.insert(id, Some(sandwich));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::get_testing_mongodb_config;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn should_batch_concurrent_loads_into_one_call() {
+        let repo = Arc::new(SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap());
+        let loader = SandwichLoader::new(repo.clone());
+
+        let (first, second, again) = tokio::join!(loader.load("id-1"), loader.load("id-2"), loader.load("id-1"));
+
+        assert_eq!(first.unwrap().unwrap().id().value().as_ref().unwrap(), "id-1");
+        assert_eq!(second.unwrap().unwrap().id().value().as_ref().unwrap(), "id-2");
+        assert!(again.unwrap().is_some());
+        assert_eq!(repo.find_many_calls(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn should_serve_already_loaded_ids_from_cache() {
+        let repo = Arc::new(SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap());
+        let loader = SandwichLoader::new(repo.clone());
+
+        loader.load("id-1").await.unwrap();
+        loader.load("id-1").await.unwrap();
+
+        assert_eq!(repo.find_many_calls(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn should_fail_when_the_batch_fails() {
+        let mut repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        repo.set_error(true);
+        let loader = SandwichLoader::new(Arc::new(repo));
+
+        assert!(loader.load("id-1").await.is_err());
+    }
+}
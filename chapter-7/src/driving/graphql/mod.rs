@@ -1,2 +1,5 @@
 pub mod sandwiches;
-pub mod schema;
\ No newline at end of file
+pub mod schema;
+pub mod limits;
+pub mod persisted_queries;
+pub mod loader;
\ No newline at end of file
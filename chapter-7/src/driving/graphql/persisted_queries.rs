@@ -0,0 +1,190 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::driven::persisted_queries::{PersistedQueryStore, sha256_hash, StoreRegisterError};
+
+/// the only version of the automatic persisted queries protocol
+const PERSISTED_QUERY_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestExtensions {
+    #[serde(rename = "persistedQuery")]
+    pub persisted_query: Option<PersistedQueryExtension>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PersistedQueryExtension {
+    pub version: u8,
+    #[serde(rename = "sha256Hash")]
+    pub sha256_hash: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PersistedQueryError {
+    NotFound,
+    NotAllowed,
+    UnsupportedVersion,
+    HashMismatch,
+    MissingQuery,
+    Unknown(String),
+}
+
+impl PersistedQueryError {
+    /// error code returned in the GraphQL error extensions, as expected by automatic persisted queries clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            PersistedQueryError::NotFound => "PERSISTED_QUERY_NOT_FOUND",
+            PersistedQueryError::NotAllowed => "PERSISTED_QUERY_NOT_ALLOWED",
+            PersistedQueryError::UnsupportedVersion => "PERSISTED_QUERY_UNSUPPORTED_VERSION",
+            PersistedQueryError::HashMismatch => "PERSISTED_QUERY_HASH_MISMATCH",
+            PersistedQueryError::MissingQuery => "MISSING_QUERY",
+            PersistedQueryError::Unknown(_) => "INTERNAL_SERVER_ERROR",
+        }
+    }
+}
+
+impl fmt::Display for PersistedQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PersistedQueryError::NotFound => write!(f, "PersistedQueryNotFound"),
+            PersistedQueryError::NotAllowed => write!(f, "Only allow-listed queries can be executed"),
+            PersistedQueryError::UnsupportedVersion => write!(f, "Only version {} of persisted queries is supported", PERSISTED_QUERY_VERSION),
+            PersistedQueryError::HashMismatch => write!(f, "The provided sha256Hash does not match the query"),
+            PersistedQueryError::MissingQuery => write!(f, "A query or a persisted query hash is required"),
+            PersistedQueryError::Unknown(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl From<StoreRegisterError> for PersistedQueryError {
+    fn from(e: StoreRegisterError) -> Self {
+        match e {
+            StoreRegisterError::Unknown(m) => PersistedQueryError::Unknown(m),
+        }
+    }
+}
+
+/// A query to execute, with the hash to register it under once it passed the limits
+#[derive(Debug, PartialEq)]
+pub struct ResolvedQuery {
+    pub query: String,
+    hash_to_register: Option<String>,
+}
+
+impl ResolvedQuery {
+    /// register the query sent with an unknown hash, to be run by hash afterwards.
+    /// Called after the query was validated, so that no rejected query gets stored
+    pub async fn register<P: PersistedQueryStore>(&self, store: &P) -> Result<(), PersistedQueryError> {
+        match &self.hash_to_register {
+            Some(hash) => Ok(store.register(hash, &self.query).await?),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Resolve the query to execute, looking it up by SHA-256 hash. A query sent with its hash is to be registered.
+/// In allow-list-only mode nothing gets registered and only already known queries are accepted.
+pub async fn resolve_query<P: PersistedQueryStore>(
+    store: &P,
+    allow_list_only: bool,
+    query: Option<String>,
+    extensions: Option<RequestExtensions>,
+) -> Result<ResolvedQuery, PersistedQueryError> {
+
+    let persisted_query = extensions.and_then(|e| e.persisted_query);
+    if persisted_query.as_ref().is_some_and(|p| p.version != PERSISTED_QUERY_VERSION) {
+        return Err(PersistedQueryError::UnsupportedVersion);
+    }
+    let hash = persisted_query.map(|p| p.sha256_hash);
+
+    match (query, hash) {
+        (Some(query), Some(hash)) => {
+            if sha256_hash(&query) != hash {
+                return Err(PersistedQueryError::HashMismatch);
+            }
+
+            let known = store.find(&hash).await.is_some();
+            if allow_list_only && !known {
+                return Err(PersistedQueryError::NotAllowed);
+            }
+
+            Ok(ResolvedQuery { query, hash_to_register: (!known).then_some(hash) })
+        }
+        (None, Some(hash)) => store.find(&hash).await
+            .map(|query| ResolvedQuery { query, hash_to_register: None })
+            .ok_or(PersistedQueryError::NotFound),
+        (Some(query), None) => {
+            if allow_list_only && store.find(&sha256_hash(&query)).await.is_none() {
+                return Err(PersistedQueryError::NotAllowed);
+            }
+
+            Ok(ResolvedQuery { query, hash_to_register: None })
+        }
+        (None, None) => Err(PersistedQueryError::MissingQuery),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driven::persisted_queries::in_memory_store::InMemoryPersistedQueryStore;
+
+    use super::*;
+
+    const QUERY: &str = "{ sandwiches { name } }";
+
+    fn hash_extension(hash: &str, version: u8) -> Option<RequestExtensions> {
+        Some(RequestExtensions {
+            persisted_query: Some(PersistedQueryExtension {
+                version,
+                sha256_hash: hash.to_string(),
+            })
+        })
+    }
+
+    #[actix_rt::test]
+    async fn should_register_on_miss_and_then_find_by_hash() {
+        let store = InMemoryPersistedQueryStore::default();
+        let hash = sha256_hash(QUERY);
+
+        let res = resolve_query(&store, false, None, hash_extension(&hash, 1)).await;
+        assert_eq!(res, Err(PersistedQueryError::NotFound));
+
+        let res = resolve_query(&store, false, Some(QUERY.to_string()), hash_extension(&hash, 1)).await.unwrap();
+        assert_eq!(res.query, QUERY);
+        // nothing is stored before the query is validated and registered
+        assert_eq!(store.find(&hash).await, None);
+        res.register(&store).await.unwrap();
+
+        let res = resolve_query(&store, false, None, hash_extension(&hash, 1)).await.unwrap();
+        assert_eq!(res.query, QUERY);
+    }
+
+    #[actix_rt::test]
+    async fn should_reject_a_hash_not_matching_the_query() {
+        let store = InMemoryPersistedQueryStore::default();
+
+        let res = resolve_query(&store, false, Some(QUERY.to_string()), hash_extension("not-the-hash", 1)).await;
+        assert_eq!(res, Err(PersistedQueryError::HashMismatch));
+
+        let res = resolve_query(&store, false, Some(QUERY.to_string()), hash_extension(&sha256_hash(QUERY), 2)).await;
+        assert_eq!(res, Err(PersistedQueryError::UnsupportedVersion));
+    }
+
+    #[actix_rt::test]
+    async fn should_only_run_known_queries_in_allow_list_mode() {
+        let store = InMemoryPersistedQueryStore::default();
+        let other_query = "{ sandwiches { stars } }";
+        store.register(&sha256_hash(QUERY), QUERY).await.unwrap();
+
+        let res = resolve_query(&store, true, Some(QUERY.to_string()), None).await.unwrap();
+        assert_eq!(res.query, QUERY);
+
+        let res = resolve_query(&store, true, Some(other_query.to_string()), None).await;
+        assert_eq!(res, Err(PersistedQueryError::NotAllowed));
+
+        let res = resolve_query(&store, true, Some(other_query.to_string()), hash_extension(&sha256_hash(other_query), 1)).await;
+        assert_eq!(res, Err(PersistedQueryError::NotAllowed));
+        assert_eq!(store.find(&sha256_hash(other_query)).await, None);
+    }
+}
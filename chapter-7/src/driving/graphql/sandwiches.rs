@@ -1,22 +1,113 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use actix_web::{HttpResponse, web};
 use actix_web::http::Error;
 use juniper::http::GraphQLRequest;
+use juniper::InputValue;
+use serde::Deserialize;
+use serde_json::json;
 
+use crate::config::GraphQLConfig;
 use crate::domain::sandwich::Sandwich;
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::persisted_queries::PersistedQueryStore;
 use crate::driven::repository::Repository;
+use crate::driven::suggestions::SuggestionIndex;
+use crate::driving::graphql::limits::check_query_limits;
+use crate::driving::graphql::loader::SandwichLoader;
+use crate::driving::graphql::persisted_queries::{PersistedQueryError, RequestExtensions, resolve_query};
 use crate::driving::graphql::schema::{Context, Schema};
 
-/// find sandwich recipes graphql
-pub async fn sandwiches_graph<T: Repository<Sandwich> + Send + Sync + 'static>(
-    repository: web::Data<T>,
+/// The body of a GraphQL request, kept apart from juniper's one to inspect the query before executing it.
+/// The query can be omitted when a persisted query hash is sent in the extensions
+#[derive(Debug, Deserialize)]
+pub struct GraphQLRequestBody {
+    pub query: Option<String>,
+    #[serde(rename = "operationName")]
+    pub operation_name: Option<String>,
+    pub variables: Option<InputValue>,
+    pub extensions: Option<RequestExtensions>,
+}
+
+/// The driven ports the mutations write through besides the command repository, shared by all the workers
+pub struct GraphQLPorts {
+    pub suggestions: Arc<dyn SuggestionIndex + Send + Sync>,
+    pub ingredients: Arc<dyn IngredientCatalog + Send + Sync>,
+}
+
+/// find and manage sandwich recipes graphql
+pub async fn sandwiches_graph<T: Repository<Sandwich> + Send + Sync + 'static,
+    U: Repository<Sandwich> + Send + Sync + 'static,
+    P: PersistedQueryStore + Send + Sync + 'static>(
+    command_repository: web::Data<T>,
+    query_repository: web::Data<U>,
+    ports: web::Data<GraphQLPorts>,
+    persisted_queries: web::Data<P>,
     schema: web::Data<Schema>,
-    graph_request: web::Json<GraphQLRequest>,
+    config: web::Data<GraphQLConfig>,
+    graph_request: web::Json<GraphQLRequestBody>,
 ) -> Result<HttpResponse, Error> {
+
+    let body = graph_request.into_inner();
+    let allow_list_only = config.allow_list_path.is_some();
+
+    let resolved = match resolve_query(persisted_queries.get_ref(), allow_list_only, body.query, body.extensions).await {
+        Ok(q) => q,
+        // automatic persisted queries clients expect a 200 to send the full query on a miss
+        Err(PersistedQueryError::NotFound) => return Ok(HttpResponse::Ok().json(
+            graphql_error_with_code(PersistedQueryError::NotFound.to_string(), PersistedQueryError::NotFound.code()))),
+        Err(e) => return Ok(HttpResponse::BadRequest().json(graphql_error_with_code(e.to_string(), e.code()))),
+    };
+
+    if let Err(e) = check_query_limits(&resolved.query, &schema, &config) {
+        return Ok(HttpResponse::BadRequest().json(graphql_error(e.to_string())));
+    }
+
+    if let Err(e) = resolved.register(persisted_queries.get_ref()).await {
+        return Ok(HttpResponse::InternalServerError().json(graphql_error_with_code(e.to_string(), e.code())));
+    }
+
+    let query_repository = query_repository.into_inner();
     let ctx = Context {
-        repository: repository.into_inner(),
+        command_repository: command_repository.into_inner(),
+        query_repository: query_repository.clone(),
+        sandwich_loader: SandwichLoader::new(query_repository),
+        suggestions: ports.suggestions.clone(),
+        ingredients: ports.ingredients.clone(),
     };
 
-    let res = graph_request.execute(&schema, &ctx).await;
+    let graph_request = GraphQLRequest::new(resolved.query, body.operation_name, body.variables);
+    let timeout = Duration::from_millis(config.timeout_ms);
+
+    match actix_rt::time::timeout(timeout, graph_request.execute(&schema, &ctx)).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(_) => Ok(HttpResponse::GatewayTimeout().json(graphql_error(
+            format!("Query execution exceeded the timeout of {} ms", config.timeout_ms)))),
+    }
+}
+
+/// export the graphql schema as SDL, for clients to generate their types
+pub async fn graphql_schema(schema: web::Data<Schema>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(schema.as_schema_language())
+}
+
+/// Compose a GraphQL compliant error body
+fn graphql_error(message: String) -> serde_json::Value {
+    json!({
+        "errors": [
+            { "message": message }
+        ]
+    })
+}
 
-    Ok(HttpResponse::Ok().json(res))
-}
\ No newline at end of file
+/// Compose a GraphQL compliant error body carrying an error code in the extensions
+fn graphql_error_with_code(message: String, code: &str) -> serde_json::Value {
+    json!({
+        "errors": [
+            { "message": message, "extensions": { "code": code } }
+        ]
+    })
+}
@@ -2,66 +2,288 @@ use std::sync::Arc;
 
 use actix_web::web::Data;
 use async_trait::async_trait;
-use juniper::{EmptyMutation, EmptySubscription, FieldResult, graphql_object, GraphQLObject, RootNode};
+use futures::stream::BoxStream;
+use juniper::{EmptySubscription, FieldError, FieldResult, graphql_object, graphql_value, GraphQLInputObject, GraphQLObject, RootNode};
 use juniper_codegen::GraphQLEnum;
 use serde::{Deserialize, Serialize};
 
 use crate::domain;
+use crate::domain::commands::{SandwichCommand, SandwichPorts};
+use crate::domain::delete_one_sandwich::DeleteOneError;
 use crate::domain::Entity;
-use crate::domain::sandwich::{Sandwich, SandwichType};
-use crate::driven::repository::{FindSandwich, RepoCreateError, RepoDeleteError, RepoFindAllError, RepoSelectError, Repository, RepoUpdateError};
+use crate::domain::nutrition::{bundled_table, NutritionEstimate, Nutrients};
+use crate::domain::sandwich::{PreparationStep, Sandwich, sandwich_tags, sandwich_types, SandwichTag, SandwichType};
+use crate::domain::update_sandwich::UpdateError;
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::repository::{FindSandwich, IngredientFilter, RepoCreateError, RepoDeleteError, RepoFindAllError, RepoSelectError, Repository, RepoUpdateError};
+use crate::driven::suggestions::SuggestionIndex;
+use crate::driving::graphql::loader::SandwichLoader;
 
 #[derive(Clone, Debug, GraphQLObject)]
-#[graphql(description="A sandwich recipe")]
+#[graphql(description="A preparation step")]
+pub struct StepGraphQL {
+    pub instruction: String,
+    pub duration_minutes: Option<i32>,
+    #[graphql(description="waiting, counted in the total time but not in the preparation time")]
+    pub passive: bool,
+}
+
+impl From<&PreparationStep> for StepGraphQL {
+
+    fn from(s: &PreparationStep) -> Self {
+
+        StepGraphQL {
+            instruction: s.instruction().to_string(),
+            duration_minutes: s.duration_minutes().map(|d| d as i32),
+            passive: s.passive(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, GraphQLObject)]
+#[graphql(description="Energy and macronutrients, in kcal and grams")]
+pub struct NutrientsGraphQL {
+    pub calories_kcal: f64,
+    pub protein_g: f64,
+    pub fat_g: f64,
+    pub carbs_g: f64,
+}
+
+impl From<Nutrients> for NutrientsGraphQL {
+
+    fn from(n: Nutrients) -> Self {
+
+        NutrientsGraphQL {
+            calories_kcal: n.calories_kcal,
+            protein_g: n.protein_g,
+            fat_g: n.fat_g,
+            carbs_g: n.carbs_g,
+        }
+    }
+}
+
+#[derive(Clone, Debug, GraphQLObject)]
+#[graphql(description="Nutrients estimated from the ingredients the nutrition table knows")]
+pub struct NutritionGraphQL {
+    pub total: NutrientsGraphQL,
+    pub per_serving: NutrientsGraphQL,
+    #[graphql(description="missing from the nutrition table, left out of the estimate")]
+    pub unknown_ingredients: Vec<String>,
+    #[graphql(description="without a quantity that can be weighed, left out of the estimate")]
+    pub unquantified_ingredients: Vec<String>,
+}
+
+impl From<NutritionEstimate> for NutritionGraphQL {
+
+    fn from(e: NutritionEstimate) -> Self {
+
+        NutritionGraphQL {
+            total: NutrientsGraphQL::from(e.total),
+            per_serving: NutrientsGraphQL::from(e.per_serving),
+            unknown_ingredients: e.unknown_ingredients,
+            unquantified_ingredients: e.unquantified_ingredients,
+        }
+    }
+}
+
+/// A sandwich recipe, its fields resolved from the domain one when queried,
+/// so that the costly ones, as the nutrition, are only computed when asked for
+#[derive(Clone, Debug)]
 pub struct SandwichGraphQL {
-    pub id: String,
-    pub name: String,
-    pub ingredients: Vec<String>,
-    pub sandwich_type: SandwichTypeGraphQL,
-    pub stars: i32
+    sandwich: Sandwich,
 }
 
 impl From<Sandwich> for SandwichGraphQL {
 
-    fn from(s: Sandwich) -> Self {
+    fn from(sandwich: Sandwich) -> Self {
 
-        let sand_graph = SandwichGraphQL {
-            id: s.id().value().clone().unwrap(),
-            name: s.name().value().to_string(),
-            ingredients: s.ingredients().value().clone(),
-            sandwich_type: SandwichTypeGraphQL::from(s.sandwich_type().clone()),
-            stars: s.stars().value(),
-        };
-
-        sand_graph
+        SandwichGraphQL { sandwich }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, GraphQLEnum)]
-#[graphql(description="A sandwich type")]
-pub enum SandwichTypeGraphQL {
-    Meat,
-    Fish,
-    Veggie,
-    Undefined
+#[graphql_object(description = "A sandwich recipe")]
+impl SandwichGraphQL {
+
+    fn id(&self) -> String {
+        // empty for a sandwich not stored yet, so that listing one cannot fail the whole query
+        self.sandwich.id().value().clone().unwrap_or_default()
+    }
+
+    fn name(&self) -> &str {
+        self.sandwich.name().value()
+    }
+
+    fn ingredients(&self) -> Vec<String> {
+        self.sandwich.ingredients().lines()
+    }
+
+    fn steps(&self) -> Vec<StepGraphQL> {
+        self.sandwich.steps().value().iter().map(StepGraphQL::from).collect()
+    }
+
+    #[graphql(description = "minutes of the active steps, null when no step is timed")]
+    fn prep_time_minutes(&self) -> Option<i32> {
+        self.sandwich.steps().prep_time_minutes().map(|m| m as i32)
+    }
+
+    #[graphql(description = "minutes of all the steps, null when no step is timed")]
+    fn total_time_minutes(&self) -> Option<i32> {
+        self.sandwich.steps().total_time_minutes().map(|m| m as i32)
+    }
+
+    fn sandwich_type(&self) -> SandwichTypeGraphQL {
+        SandwichTypeGraphQL::from(self.sandwich.sandwich_type().clone())
+    }
+
+    fn tags(&self) -> Vec<SandwichTagGraphQL> {
+        self.sandwich.tags().iter().cloned().map(SandwichTagGraphQL::from).collect()
+    }
+
+    fn stars(&self) -> i32 {
+        self.sandwich.stars().value()
+    }
+
+    #[graphql(description = "version to send back when updating or deleting the sandwich")]
+    fn version(&self) -> i32 {
+        self.sandwich.version().value().unwrap_or_default() as i32
+    }
+
+    #[graphql(description = "number of people the quantities are meant for")]
+    fn servings(&self) -> i32 {
+        self.sandwich.servings().value() as i32
+    }
+
+    #[graphql(description = "allergens derived from the ingredients")]
+    fn allergens(&self) -> Vec<String> {
+        self.sandwich.allergens().found.iter().map(|a| a.name().to_string()).collect()
+    }
+
+    #[graphql(description = "ingredients whose allergens are unknown, which may contain any of them")]
+    fn allergens_unknown(&self) -> Vec<String> {
+        self.sandwich.allergens().unknown
+    }
+
+    #[graphql(description = "calories, protein, fat and carbs estimated from the ingredients")]
+    fn nutrition(&self) -> NutritionGraphQL {
+        NutritionGraphQL::from(bundled_table().estimate(&self.sandwich))
+    }
 }
 
-impl From<SandwichType> for SandwichTypeGraphQL {
+/// The GraphQL enum of a domain one, with the same variants as it is generated from the same list
+macro_rules! graphql_enum {
+    ($graphql:ident, $description:tt, $domain:ident { $($variant:ident => $name:tt),+ $(,)? }) => {
+        #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, GraphQLEnum)]
+        #[graphql(description = $description)]
+        pub enum $graphql {
+            $($variant),+
+        }
+
+        impl From<$domain> for $graphql {
 
-    fn from(s: SandwichType) -> Self {
+            fn from(s: $domain) -> Self {
 
-        match s {
-            SandwichType::Meat => SandwichTypeGraphQL::Meat,
-            SandwichType::Fish => SandwichTypeGraphQL::Fish,
-            SandwichType::Veggie => SandwichTypeGraphQL::Veggie,
-            SandwichType::Undefined => SandwichTypeGraphQL::Undefined
+                match s {
+                    $($domain::$variant => $graphql::$variant),+
+                }
+            }
         }
+
+        impl From<$graphql> for $domain {
+
+            fn from(s: $graphql) -> Self {
+
+                match s {
+                    $($graphql::$variant => $domain::$variant),+
+                }
+            }
+        }
+    };
+}
+
+sandwich_types!(graphql_enum, SandwichTypeGraphQL, "A sandwich type");
+sandwich_tags!(graphql_enum, SandwichTagGraphQL, "A sandwich tag");
+
+#[derive(Clone, Debug, GraphQLInputObject)]
+#[graphql(description="A preparation step")]
+pub struct StepInputGraphQL {
+    pub instruction: String,
+    pub duration_minutes: Option<i32>,
+    pub passive: Option<bool>,
+}
+
+impl From<&StepInputGraphQL> for PreparationStep {
+
+    fn from(s: &StepInputGraphQL) -> Self {
+
+        // a negative duration is rejected by the domain as a zero one
+        PreparationStep::new(s.instruction.clone(),
+                             s.duration_minutes.map(|d| u32::try_from(d).unwrap_or(0)),
+                             s.passive.unwrap_or(false))
     }
 }
 
+#[derive(Clone, Debug, GraphQLInputObject)]
+#[graphql(description="A new sandwich recipe")]
+pub struct NewSandwichGraphQL {
+    pub name: String,
+    pub ingredients: Vec<String>,
+    pub steps: Option<Vec<StepInputGraphQL>>,
+    pub sandwich_type: SandwichTypeGraphQL,
+    pub tags: Option<Vec<SandwichTagGraphQL>>,
+    pub servings: Option<i32>,
+}
+
+#[derive(Clone, Debug, GraphQLInputObject)]
+#[graphql(description="An updated sandwich recipe")]
+pub struct UpdateSandwichGraphQL {
+    pub id: String,
+    pub name: String,
+    pub ingredients: Vec<String>,
+    pub steps: Option<Vec<StepInputGraphQL>>,
+    pub sandwich_type: SandwichTypeGraphQL,
+    pub tags: Option<Vec<SandwichTagGraphQL>>,
+    pub servings: Option<i32>,
+    #[graphql(description="version read with the sandwich, the update is refused if it changed since")]
+    pub version: i32,
+}
+
+fn tags(tags: &Option<Vec<SandwichTagGraphQL>>) -> Vec<SandwichTag> {
+    tags.iter().flatten().cloned().map(SandwichTag::from).collect()
+}
+
+fn steps(steps: &Option<Vec<StepInputGraphQL>>) -> Vec<PreparationStep> {
+    steps.iter().flatten().map(PreparationStep::from).collect()
+}
+
+/// the error of a write refused because the sandwich changed since the sent version was read, as a 412 in REST
+fn precondition_failed() -> FieldError {
+    FieldError::new("The sandwich has been modified since it was read", graphql_value!({ "code": "PRECONDITION_FAILED" }))
+}
+
+/// GraphQL has no unsigned integers
+fn servings(servings: Option<i32>) -> Result<Option<u32>, String> {
+    servings.map(|s| u32::try_from(s).map_err(|_| String::from("The servings must be positive"))).transpose()
+}
+
 // This struct represents our context.
+// Following CQRS, mutations are sent to the command repository (write model)
+// while queries are served by the query repository (read model).
 pub struct Context {
-    pub repository: Arc<dyn Repository<Sandwich> + Send + Sync>,
+    pub command_repository: Arc<dyn Repository<Sandwich> + Send + Sync>,
+    pub query_repository: Arc<dyn Repository<Sandwich> + Send + Sync>,
+    // batches the lookups by id of the current request on the query repository
+    pub sandwich_loader: SandwichLoader,
+    // kept current by the mutations, as by the REST API
+    pub suggestions: Arc<dyn SuggestionIndex + Send + Sync>,
+    // names the ingredients of the created and updated sandwiches canonically
+    pub ingredients: Arc<dyn IngredientCatalog + Send + Sync>,
+}
+
+impl Context {
+    /// the ports the mutations write through besides the command repository
+    fn ports(&self) -> SandwichPorts<'_, dyn SuggestionIndex + Send + Sync, dyn IngredientCatalog + Send + Sync> {
+        SandwichPorts { suggestions: self.suggestions.as_ref(), catalog: self.ingredients.as_ref() }
+    }
 }
 
 // Mark the Context struct as a valid context type for Juniper
@@ -81,12 +303,40 @@ impl<T: ?Sized + Repository<U> + Sync + Send, U: Entity + Send + 'static> Reposi
         (**self).find_all(sandwich).await
     }
 
+    async fn find_many_by_ids(&self, ids: Vec<String>) -> Result<Vec<U>, RepoFindAllError> {
+        (**self).find_many_by_ids(ids).await
+    }
+
+    async fn find_many_by_names(&self, names: Vec<String>) -> Result<Vec<U>, RepoFindAllError> {
+        (**self).find_many_by_names(names).await
+    }
+
+    async fn stream_all(&self) -> Result<BoxStream<'static, Result<U, RepoFindAllError>>, RepoFindAllError> {
+        (**self).stream_all().await
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<U>, RepoFindAllError> {
+        (**self).search(query, limit).await
+    }
+
     async fn update(&self, sandwich: U) -> Result<U, RepoUpdateError> {
         (**self).update(sandwich).await
     }
 
-    async fn delete(&self, id: &str) -> Result<(), RepoDeleteError> {
-        (**self).delete(id).await
+    async fn delete(&self, id: &str, version: Option<i64>) -> Result<(), RepoDeleteError> {
+        (**self).delete(id, version).await
+    }
+
+    async fn create_many(&self, sandwiches: Vec<U>) -> Vec<Result<U, RepoCreateError>> {
+        (**self).create_many(sandwiches).await
+    }
+
+    async fn update_many(&self, sandwiches: Vec<U>) -> Vec<Result<U, RepoUpdateError>> {
+        (**self).update_many(sandwiches).await
+    }
+
+    async fn delete_many(&self, ids: Vec<(String, Option<i64>)>) -> Vec<Result<(), RepoDeleteError>> {
+        (**self).delete_many(ids).await
     }
 }
 
@@ -98,9 +348,9 @@ impl Query {
     #[graphql(description = "List of all sandwiches")]
     async fn sandwiches(context: &Context) -> FieldResult<Vec<SandwichGraphQL>> {
 
-        let repository = context.repository.clone();
+        let repository = context.query_repository.clone();
 
-        let sandwiches = domain::find_all_sandwiches::find_all_sandwiches(Data::new(repository), "", &vec![]).await
+        let sandwiches = domain::find_all_sandwiches::find_all_sandwiches(Data::new(repository), context.ingredients.as_ref(), "", IngredientFilter::default(), &[], None).await
             .expect("Error finding sandwiches");
 
         let res: Vec<SandwichGraphQL> = sandwiches.into_iter()
@@ -109,12 +359,246 @@ impl Query {
 
         Ok(res)
     }
+
+    #[graphql(description = "Find a sandwich by id")]
+    async fn sandwich(context: &Context, id: String) -> FieldResult<Option<SandwichGraphQL>> {
+
+        let sandwich = context.sandwich_loader.load(&id).await?;
+
+        Ok(sandwich.map(SandwichGraphQL::from))
+    }
+}
+
+pub struct Mutation;
+
+#[graphql_object(Context = Context)]
+impl Mutation {
+
+    #[graphql(description = "Create a new sandwich")]
+    async fn create_sandwich(context: &Context, sandwich: NewSandwichGraphQL) -> FieldResult<SandwichGraphQL> {
+
+        let repository = context.command_repository.clone();
+
+        let command = SandwichCommand {
+            name: sandwich.name,
+            ingredients: sandwich.ingredients,
+            steps: steps(&sandwich.steps),
+            sandwich_type: SandwichType::from(sandwich.sandwich_type),
+            tags: tags(&sandwich.tags),
+            servings: servings(sandwich.servings)?,
+            version: None,
+        };
+
+        let created = domain::create_sandwich::create_sandwich(Data::new(repository), &context.ports(), command).await
+            .map_err(|e| format!("Error creating the sandwich: {:?}", e))?;
+
+        Ok(SandwichGraphQL::from(created))
+    }
+
+    #[graphql(description = "Update an existing sandwich")]
+    async fn update_sandwich(context: &Context, sandwich: UpdateSandwichGraphQL) -> FieldResult<SandwichGraphQL> {
+
+        let repository = context.command_repository.clone();
+
+        // the steps, tags and servings left out are kept from the stored sandwich, as by the batch update
+        let stored = if sandwich.steps.is_none() || sandwich.tags.is_none() || sandwich.servings.is_none() {
+            let found = domain::find_one_sandwich::find_one_sandwich(Data::new(repository.clone()), &sandwich.id, "", &vec![]).await
+                .map_err(|e| format!("Error updating the sandwich: {:?}", e))?;
+            Some(found)
+        } else {
+            None
+        };
+
+        let command = SandwichCommand {
+            name: sandwich.name,
+            ingredients: sandwich.ingredients,
+            steps: match (&sandwich.steps, &stored) {
+                (None, Some(stored)) => stored.steps().value().clone(),
+                (steps_in, _) => steps(steps_in),
+            },
+            sandwich_type: SandwichType::from(sandwich.sandwich_type),
+            tags: match (&sandwich.tags, &stored) {
+                (None, Some(stored)) => stored.tags().clone(),
+                (tags_in, _) => tags(tags_in),
+            },
+            servings: match (sandwich.servings, &stored) {
+                (None, Some(stored)) => Some(stored.servings().value()),
+                (servings_in, _) => servings(servings_in)?,
+            },
+            version: Some(i64::from(sandwich.version)),
+        };
+
+        let updated = domain::update_sandwich::update_sandwich(Data::new(repository), &context.ports(), &sandwich.id, command).await
+            .map_err(|e| match e {
+                UpdateError::PreconditionFailed => precondition_failed(),
+                e => FieldError::from(format!("Error updating the sandwich: {:?}", e)),
+            })?;
+
+        Ok(SandwichGraphQL::from(updated))
+    }
+
+    #[graphql(description = "Delete a sandwich by id, refused if it changed since its version was read")]
+    async fn delete_sandwich(context: &Context, id: String, version: i32) -> FieldResult<bool> {
+
+        let repository = context.command_repository.clone();
+
+        domain::delete_one_sandwich::delete_one_sandwich(Data::new(repository), context.suggestions.as_ref(), &id, Some(i64::from(version))).await
+            .map_err(|e| match e {
+                DeleteOneError::PreconditionFailed => precondition_failed(),
+                e => FieldError::from(format!("Error deleting the sandwich: {:?}", e)),
+            })?;
+
+        Ok(true)
+    }
 }
 
 // A root schema consists of a query and a mutation.
 // Request queries can be executed against a RootNode.
-pub type Schema = RootNode<'static, Query, EmptyMutation<Context>, EmptySubscription<Context>>;
+pub type Schema = RootNode<'static, Query, Mutation, EmptySubscription<Context>>;
 
 pub fn create_schema() -> Schema {
-    Schema::new(Query, EmptyMutation::new(), EmptySubscription::new())
+    Schema::new(Query, Mutation, EmptySubscription::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use juniper::{DefaultScalarValue, Value, Variables};
+
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::{CHEESEBURGER_NAME, get_testing_mongodb_config, SANDWICH_ID, SANDWICH_NAME, stub_stored_sandwich};
+
+    use super::*;
+
+    fn double_context(command_error: bool, query_error: bool) -> Context {
+        let mut command_repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        command_repo.set_error(command_error);
+        let mut query_repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        query_repo.set_error(query_error);
+
+        let query_repo: Arc<dyn Repository<Sandwich> + Send + Sync> = Arc::new(query_repo);
+
+        Context {
+            command_repository: Arc::new(command_repo),
+            query_repository: query_repo.clone(),
+            sandwich_loader: SandwichLoader::new(query_repo),
+            suggestions: Arc::new(InMemorySuggestionIndex::new()),
+            ingredients: Arc::new(InMemoryIngredientCatalog::new()),
+        }
+    }
+
+    async fn execute(query: &str, ctx: &Context) -> Value<DefaultScalarValue> {
+        let (res, errors) = juniper::execute(query, None, &create_schema(), &Variables::new(), ctx).await.unwrap();
+        assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+        res
+    }
+
+    #[actix_rt::test]
+    async fn should_query_the_read_model() {
+        // the command repo fails: queries must not reach it
+        let ctx = double_context(true, false);
+
+        let res = execute("{ sandwiches { name } }", &ctx).await;
+
+        let names: Vec<String> = res.as_object_value().unwrap()
+            .get_field_value("sandwiches").unwrap()
+            .as_list_value().unwrap()
+            .iter()
+            .map(|s| s.as_object_value().unwrap().get_field_value("name").unwrap().as_scalar_value::<String>().unwrap().clone())
+            .collect();
+
+        assert_eq!(names, vec![SANDWICH_NAME.to_string(), CHEESEBURGER_NAME.to_string()]);
+    }
+
+    #[actix_rt::test]
+    async fn should_find_sandwiches_by_id_through_the_loader() {
+        let ctx = double_context(true, false);
+
+        let res = execute(r#"{ first: sandwich(id: "id-1") { id } second: sandwich(id: "id-2") { id } }"#, &ctx).await;
+
+        let res = res.as_object_value().unwrap();
+        for (alias, id) in [("first", "id-1"), ("second", "id-2")] {
+            let found = res.get_field_value(alias).unwrap()
+                .as_object_value().unwrap()
+                .get_field_value("id").unwrap()
+                .as_scalar_value::<String>().unwrap();
+            assert_eq!(found, id);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn should_send_mutations_to_the_write_model() {
+        // the query repo fails: mutations must not reach it
+        let ctx = double_context(false, true);
+
+        let res = execute(r#"mutation { deleteSandwich(id: "sand-id", version: 1) }"#, &ctx).await;
+
+        let deleted = *res.as_object_value().unwrap()
+            .get_field_value("deleteSandwich").unwrap()
+            .as_scalar_value::<bool>().unwrap();
+
+        assert!(deleted);
+    }
+
+    #[actix_rt::test]
+    async fn should_refuse_a_write_with_a_stale_version() {
+        let ctx = double_context(false, false);
+        let update = format!(r#"mutation {{ updateSandwich(sandwich: {{ id: "{}", name: "Renamed", ingredients: ["bread"], sandwichType: VEGGIE, version: 3 }}) {{ id }} }}"#, SANDWICH_ID);
+
+        for mutation in [update.as_str(), r#"mutation { deleteSandwich(id: "sand-id", version: 3) }"#] {
+            let (_, errors) = juniper::execute(mutation, None, &create_schema(), &Variables::new(), &ctx).await.unwrap();
+
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].error().extensions(), &graphql_value!({ "code": "PRECONDITION_FAILED" }));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn should_keep_the_stored_steps_tags_and_servings_on_update() {
+        let ctx = double_context(false, false);
+        let update = format!(r#"mutation {{ updateSandwich(sandwich: {{ id: "{}", name: "Renamed", ingredients: ["bread"], sandwichType: VEGGIE, version: 1 }}) {{ steps {{ instruction }} tags servings }} }}"#, SANDWICH_ID);
+
+        let res = execute(&update, &ctx).await;
+
+        let updated = res.as_object_value().unwrap()
+            .get_field_value("updateSandwich").unwrap()
+            .as_object_value().unwrap();
+        let stored = stub_stored_sandwich(SANDWICH_ID);
+
+        assert_eq!(updated.get_field_value("steps").unwrap().as_list_value().unwrap().len(), stored.steps().value().len());
+        assert_eq!(updated.get_field_value("tags").unwrap(), &graphql_value!(["KIDS"]));
+        assert_eq!(*updated.get_field_value("servings").unwrap().as_scalar_value::<i32>().unwrap(), stored.servings().value() as i32);
+    }
+
+    /// Compare the SDL of the schema with the committed snapshot, ignoring the order of the definitions.
+    /// Run with UPDATE_SCHEMA_SNAPSHOT=1 to accept an intended schema change
+    #[test]
+    fn should_match_the_schema_snapshot() {
+        let mut snapshot_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        snapshot_path.push("resources/schema.graphql");
+
+        let sdl = create_schema().as_schema_language();
+
+        if std::env::var("UPDATE_SCHEMA_SNAPSHOT").is_ok() {
+            fs::write(&snapshot_path, &sdl).unwrap();
+        }
+
+        let snapshot = fs::read_to_string(&snapshot_path).unwrap();
+
+        assert_eq!(sdl_definitions(&sdl), sdl_definitions(&snapshot),
+                   "The GraphQL schema changed: if intended, run the tests with UPDATE_SCHEMA_SNAPSHOT=1 and commit resources/schema.graphql");
+    }
+
+    fn sdl_definitions(sdl: &str) -> Vec<String> {
+        let mut definitions: Vec<String> = sdl.split("\n\n")
+            .map(|d| d.lines().map(str::trim_end).collect::<Vec<&str>>().join("\n").trim().to_string())
+            .filter(|d| !d.is_empty())
+            .collect();
+        definitions.sort();
+        definitions
+    }
 }
\ No newline at end of file
@@ -0,0 +1,121 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::domain;
+use crate::domain::autocomplete::AutocompleteError;
+use crate::driven::suggestions::{Suggestion, SuggestionField, SuggestionIndex};
+use crate::driving::rest_handler::errors::{ApiError, ProblemDetails};
+use crate::driving::rest_handler::negotiation::negotiate;
+use crate::helpers::respond;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AutocompleteField {
+    Name,
+    Ingredient,
+}
+
+impl From<AutocompleteField> for SuggestionField {
+    fn from(f: AutocompleteField) -> Self {
+        match f {
+            AutocompleteField::Name => SuggestionField::Name,
+            AutocompleteField::Ingredient => SuggestionField::Ingredient,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AutocompleteRequest {
+    pub field: AutocompleteField,
+
+    /// beginning of the value being typed, case and accent insensitive
+    #[serde(default)]
+    pub prefix: String,
+
+    /// maximum number of suggestions, 10 by default and at most 50
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SuggestionResponse {
+    pub value: String,
+    pub count: usize,
+}
+
+impl From<Suggestion> for SuggestionResponse {
+    fn from(s: Suggestion) -> Self {
+        SuggestionResponse {
+            value: s.value,
+            count: s.count,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct AutocompleteResponse {
+    pub suggestions: Vec<SuggestionResponse>,
+}
+
+/// suggest names or ingredients while they are typed
+#[utoipa::path(
+    get,
+    path = "/autocomplete",
+    params(AutocompleteRequest),
+    responses(
+        (status = 200, description = "Distinct values starting with the prefix, the most frequent first", body = AutocompleteResponse),
+        (status = 400, description = "Unknown field or invalid limit", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn autocomplete<S: SuggestionIndex + 'static>(
+    suggestions: web::Data<S>,
+    autocomplete_req: web::Query<AutocompleteRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+
+    let result = domain::autocomplete::autocomplete(
+        suggestions.get_ref(),
+        SuggestionField::from(autocomplete_req.field),
+        &autocomplete_req.prefix,
+        autocomplete_req.limit).await;
+
+    result
+        .map(|v| respond(media_type, AutocompleteResponse {
+            suggestions: v.into_iter().map(SuggestionResponse::from).collect()
+        }))
+        .map_err(|e| match e {
+            AutocompleteError::InvalidLimit(m) => ApiError::BadRequest(m),
+            AutocompleteError::Unknown(m) => ApiError::Unknown(m),
+        })?
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test};
+    use actix_web::test::TestRequest;
+    use actix_web::web::Data;
+
+    use crate::domain::sandwich::Sandwich;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::test_utils::shared::{stub_ingredients, SANDWICH_NAME, SANDWICH_TYPE};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn should_suggest_ingredients() {
+        let suggestions = InMemorySuggestionIndex::new();
+        suggestions.index(&Sandwich::new(String::from("1"), String::from(SANDWICH_NAME), stub_ingredients(), vec![], SANDWICH_TYPE, 0).unwrap()).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(suggestions))
+                .route("/autocomplete", web::get().to(autocomplete::<InMemorySuggestionIndex>))).await;
+
+        let req = TestRequest::get().uri("/autocomplete?field=ingredient&prefix=WU").to_request();
+        let resp: AutocompleteResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.suggestions, vec![SuggestionResponse { value: String::from("Wurst"), count: 1 }]);
+    }
+}
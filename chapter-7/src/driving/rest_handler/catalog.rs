@@ -0,0 +1,484 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use actix_web::web::Bytes;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{domain, Repository, Sandwich};
+use crate::domain::commands::SandwichPorts;
+use crate::domain::export_sandwiches::ExportError;
+use crate::domain::import_sandwiches::{AcceptedRow, ConflictPolicy, ImportError, ImportOutcome, ImportReport, ImportRow, RejectedRow};
+use crate::domain::sandwich::SandwichType;
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::suggestions::SuggestionIndex;
+use crate::driving::rest_handler::errors::{ApiError, ProblemDetails};
+
+/// the ingredients of a sandwich share a single CSV column
+const CSV_INGREDIENTS_SEPARATOR: &str = ";";
+const CSV_HEADERS: [&str; 5] = ["id", "name", "ingredients", "sandwich_type", "servings"];
+
+/// upper bound of an import body
+pub const MAX_IMPORT_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl CatalogFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            CatalogFormat::Csv => "text/csv",
+            CatalogFormat::Json => "application/json",
+            CatalogFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            CatalogFormat::Csv => "csv",
+            CatalogFormat::Json => "json",
+            CatalogFormat::Ndjson => "ndjson",
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+        [CatalogFormat::Csv, CatalogFormat::Json, CatalogFormat::Ndjson].into_iter()
+            .find(|f| f.content_type() == mime)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    Skip,
+    Overwrite,
+    Fail,
+}
+
+impl From<OnConflict> for ConflictPolicy {
+    fn from(c: OnConflict) -> Self {
+        match c {
+            OnConflict::Skip => ConflictPolicy::Skip,
+            OnConflict::Overwrite => ConflictPolicy::Overwrite,
+            OnConflict::Fail => ConflictPolicy::Fail,
+        }
+    }
+}
+
+//
+// REQUESTS
+//
+
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportRequest {
+    /// defaults to json
+    pub format: Option<CatalogFormat>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportRequest {
+    /// defaults to fail
+    pub on_conflict: Option<OnConflict>,
+}
+
+/// A sandwich of the catalog, as exported and imported in JSON and NDJSON
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct CatalogRecord {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub ingredients: Vec<String>,
+    pub sandwich_type: SandwichType,
+    /// always exported; when missing from an import, a new sandwich gets the default, an overwritten one keeps its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub servings: Option<u32>,
+}
+
+impl From<Sandwich> for CatalogRecord {
+    fn from(s: Sandwich) -> Self {
+        CatalogRecord {
+            id: s.id().value().clone(),
+            name: s.name().value().to_string(),
+            ingredients: s.ingredients().lines(),
+            sandwich_type: s.sandwich_type().clone(),
+            servings: Some(s.servings().value()),
+        }
+    }
+}
+
+impl From<CatalogRecord> for ImportRow {
+    fn from(r: CatalogRecord) -> Self {
+        ImportRow {
+            name: r.name,
+            ingredients: r.ingredients,
+            sandwich_type: r.sandwich_type,
+            servings: r.servings,
+        }
+    }
+}
+
+/// A sandwich of the catalog as a CSV row
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CsvRecord {
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    ingredients: String,
+    sandwich_type: SandwichType,
+    #[serde(default)]
+    servings: Option<u32>,
+}
+
+impl From<CatalogRecord> for CsvRecord {
+    fn from(r: CatalogRecord) -> Self {
+        CsvRecord {
+            id: r.id,
+            name: r.name,
+            ingredients: r.ingredients.join(CSV_INGREDIENTS_SEPARATOR),
+            sandwich_type: r.sandwich_type,
+            servings: r.servings,
+        }
+    }
+}
+
+impl From<CsvRecord> for CatalogRecord {
+    fn from(r: CsvRecord) -> Self {
+        CatalogRecord {
+            id: r.id,
+            name: r.name,
+            ingredients: r.ingredients.split(CSV_INGREDIENTS_SEPARATOR)
+                .map(|i| i.trim().to_string())
+                .filter(|i| !i.is_empty())
+                .collect(),
+            sandwich_type: r.sandwich_type,
+            servings: r.servings,
+        }
+    }
+}
+
+//
+// RESPONSES
+//
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportOutcomeResponse {
+    Created,
+    Overwritten,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct AcceptedRowResponse {
+    pub row: usize,
+    pub id: String,
+    pub outcome: ImportOutcomeResponse,
+}
+
+impl From<AcceptedRow> for AcceptedRowResponse {
+    fn from(r: AcceptedRow) -> Self {
+        AcceptedRowResponse {
+            row: r.row,
+            id: r.id,
+            outcome: match r.outcome {
+                ImportOutcome::Created => ImportOutcomeResponse::Created,
+                ImportOutcome::Overwritten => ImportOutcomeResponse::Overwritten,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct RejectedRowResponse {
+    pub row: usize,
+    pub reason: String,
+}
+
+impl From<RejectedRow> for RejectedRowResponse {
+    fn from(r: RejectedRow) -> Self {
+        RejectedRowResponse {
+            row: r.row,
+            reason: r.reason,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct ImportReportResponse {
+    pub accepted: Vec<AcceptedRowResponse>,
+    pub rejected: Vec<RejectedRowResponse>,
+}
+
+impl From<ImportReport> for ImportReportResponse {
+    fn from(r: ImportReport) -> Self {
+        ImportReportResponse {
+            accepted: r.accepted.into_iter().map(AcceptedRowResponse::from).collect(),
+            rejected: r.rejected.into_iter().map(RejectedRowResponse::from).collect(),
+        }
+    }
+}
+
+/// export the whole catalog, encoding and sending each sandwich as it is read from the repository
+#[utoipa::path(
+    get,
+    path = "/sandwiches/export",
+    params(ExportRequest),
+    responses(
+        (status = 200, description = "The catalog in the requested format", content(
+            (Vec<CatalogRecord> = "application/json"),
+            (String = "application/x-ndjson"),
+            (String = "text/csv"),
+        )),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn export_sandwiches<T: Repository<Sandwich>>(
+    repository: web::Data<T>,
+    export_req: web::Query<ExportRequest>,
+) -> Result<HttpResponse, ApiError> {
+
+    let format = export_req.format.unwrap_or(CatalogFormat::Json);
+
+    let sandwiches = domain::export_sandwiches::export_sandwiches(repository).await
+        .map_err(|e| match e {
+            ExportError::Unknown(m) => ApiError::Unknown(m),
+        })?;
+
+    // a failure once the response has started can only abort it
+    let records = sandwiches.enumerate()
+        .map(move |(index, sandwich)| match sandwich {
+            Ok(s) => encode(format, index, CatalogRecord::from(s)),
+            Err(ExportError::Unknown(m)) => Err(m),
+        });
+
+    let body = stream::iter(opening(format))
+        .chain(records)
+        .chain(stream::iter(closing(format)))
+        .map(|chunk| chunk.map_err(actix_web::error::ErrorInternalServerError));
+
+    Ok(HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, format.content_type()))
+        .insert_header((CONTENT_DISPOSITION, format!("attachment; filename=\"sandwiches.{}\"", format.extension())))
+        .streaming(body))
+}
+
+/// import a catalog, whose format is given by the Content-Type
+#[utoipa::path(
+    post,
+    path = "/sandwiches/import",
+    params(ImportRequest),
+    request_body(content(
+        (Vec<CatalogRecord> = "application/json"),
+        (String = "application/x-ndjson"),
+        (String = "text/csv"),
+    )),
+    responses(
+        (status = 200, description = "The accepted and rejected rows", body = ImportReportResponse),
+        (status = 400, description = "Empty or unreadable import", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Conflicting names with the fail policy, nothing has been imported", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported format", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn import_sandwiches<T: Repository<Sandwich>, S: SuggestionIndex + 'static, C: IngredientCatalog + 'static>(
+    repository: web::Data<T>,
+    suggestions: web::Data<S>,
+    ingredients: web::Data<C>,
+    import_req: web::Query<ImportRequest>,
+    req: HttpRequest,
+    body: Bytes,
+) -> Result<HttpResponse, ApiError> {
+
+    let format = req.headers().get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(CatalogFormat::from_content_type)
+        .ok_or_else(|| ApiError::UnsupportedMediaType(String::from("Content-Type must be text/csv, application/json or application/x-ndjson")))?;
+
+    let rows = parse(format, &body)?;
+    let policy = ConflictPolicy::from(import_req.on_conflict.unwrap_or(OnConflict::Fail));
+
+    let ports = SandwichPorts { suggestions: suggestions.get_ref(), catalog: ingredients.get_ref() };
+
+    let report = domain::import_sandwiches::import_sandwiches(repository, &ports, rows, policy).await
+        .map_err(|e| match e {
+            ImportError::Empty => ApiError::BadRequest(String::from("The import contains no sandwiches")),
+            ImportError::Conflict(m) => ApiError::Conflict(m),
+            ImportError::Unknown(m) => ApiError::Unknown(m),
+        })?;
+
+    Ok(HttpResponse::Ok().json(ImportReportResponse::from(report)))
+}
+
+/// what precedes the first sandwich
+fn opening(format: CatalogFormat) -> Option<Result<Bytes, String>> {
+    match format {
+        CatalogFormat::Json => Some(Ok(Bytes::from_static(b"["))),
+        CatalogFormat::Csv => Some(to_csv_line(&CSV_HEADERS)),
+        CatalogFormat::Ndjson => None,
+    }
+}
+
+/// what follows the last sandwich
+fn closing(format: CatalogFormat) -> Option<Result<Bytes, String>> {
+    match format {
+        CatalogFormat::Json => Some(Ok(Bytes::from_static(b"]"))),
+        CatalogFormat::Csv | CatalogFormat::Ndjson => None,
+    }
+}
+
+fn encode(format: CatalogFormat, index: usize, record: CatalogRecord) -> Result<Bytes, String> {
+    match format {
+        CatalogFormat::Json => {
+            let json = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+            let separator = if index == 0 { "" } else { "," };
+            Ok(Bytes::from(format!("{}{}", separator, json)))
+        }
+        CatalogFormat::Ndjson => {
+            let json = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+            Ok(Bytes::from(format!("{}\n", json)))
+        }
+        CatalogFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(vec![]);
+            writer.serialize(CsvRecord::from(record)).map_err(|e| e.to_string())?;
+            writer.into_inner().map(Bytes::from).map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn to_csv_line(fields: &[&str]) -> Result<Bytes, String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(fields).map_err(|e| e.to_string())?;
+    writer.into_inner().map(Bytes::from).map_err(|e| e.to_string())
+}
+
+/// read the rows of an import; an unreadable row is reported as such, without failing the whole import
+fn parse(format: CatalogFormat, body: &[u8]) -> Result<Vec<Result<ImportRow, String>>, ApiError> {
+    match format {
+        CatalogFormat::Json => {
+            let values: Vec<serde_json::Value> = serde_json::from_slice(body)
+                .map_err(|e| ApiError::BadRequest(format!("The import must be a JSON array: {}", e)))?;
+
+            Ok(values.into_iter()
+                .map(|v| serde_json::from_value::<CatalogRecord>(v)
+                    .map(ImportRow::from)
+                    .map_err(|e| e.to_string()))
+                .collect())
+        }
+        CatalogFormat::Ndjson => {
+            let text = std::str::from_utf8(body)
+                .map_err(|e| ApiError::BadRequest(format!("The import must be UTF-8: {}", e)))?;
+
+            Ok(text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| serde_json::from_str::<CatalogRecord>(line)
+                    .map(ImportRow::from)
+                    .map_err(|e| e.to_string()))
+                .collect())
+        }
+        CatalogFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(body);
+
+            Ok(reader.deserialize::<CsvRecord>()
+                .map(|r| r
+                    .map(|r| ImportRow::from(CatalogRecord::from(r)))
+                    .map_err(|e| e.to_string()))
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test};
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use actix_web::web::Data;
+
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::tests::test_utils::shared::get_testing_mongodb_config;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn should_export_the_catalog_as_csv() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .route("/export", web::get().to(export_sandwiches::<SandwichRepoDouble>))).await;
+        let req = TestRequest::get().uri("/export?format=csv").to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/csv");
+
+        let body = test::read_body(resp).await;
+        let lines: Vec<&str> = std::str::from_utf8(&body).unwrap().lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "id,name,ingredients,sandwich_type,servings");
+        assert!(lines[1].contains("Wurst;Ketchup"));
+        assert!(lines[1].ends_with(",1"));
+    }
+
+    #[actix_web::test]
+    async fn should_export_the_catalog_as_a_json_array() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .route("/export", web::get().to(export_sandwiches::<SandwichRepoDouble>))).await;
+        let req = TestRequest::get().uri("/export").to_request();
+
+        let records: Vec<CatalogRecord> = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(records.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn should_report_unreadable_csv_rows() {
+        let csv = "name,ingredients,sandwich_type\nClub sandwich,Bacon;Lettuce,Meat\nBroken,Bread,NotAType\n";
+
+        let rows = parse(CatalogFormat::Csv, csv.as_bytes()).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_ref().unwrap().ingredients, vec![String::from("Bacon"), String::from("Lettuce")]);
+        assert!(rows[1].is_err());
+    }
+
+    #[actix_web::test]
+    async fn should_import_ndjson_with_a_report() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/import", web::post().to(import_sandwiches::<SandwichRepoDouble, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+        let req = TestRequest::post()
+            .uri("/import?on_conflict=skip")
+            .insert_header((CONTENT_TYPE, "application/x-ndjson"))
+            .set_payload("{\"name\":\"Club sandwich\",\"ingredients\":[\"Bacon\"],\"sandwich_type\":\"Meat\"}\nnot json\n")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let report: ImportReportResponse = test::read_body_json(resp).await;
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(report.rejected[0].row, 2);
+    }
+}
@@ -6,6 +6,14 @@ use actix_web::{
     http::StatusCode,
     HttpResponse,
 };
+use actix_web::http::header::ContentType;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// base of the `type` URI of the problems, resolved against the API host
+const PROBLEM_TYPE_BASE: &str = "/recipes/problems/";
 
 #[derive(Debug, PartialEq)]
 pub enum ApiError {
@@ -15,7 +23,117 @@ pub enum ApiError {
     InvalidData(String),
     Unknown(String),
     Conflict(String),
-    ValidationError(Vec<String>),
+    UnsupportedMediaType(String),
+    NotAcceptable(String),
+    PreconditionFailed(String),
+    PreconditionRequired(String),
+    ValidationError(Vec<FieldViolation>),
+}
+
+/// A single invalid field of a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldViolation {
+    pub field: String,
+    pub message: String,
+}
+
+/// An error rendered as an RFC 7807 problem document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub correlation_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<FieldViolation>,
+}
+
+impl ProblemDetails {
+    /// A generic problem for errors not raised by the API itself, e.g. by the extractors
+    pub fn from_status(status: StatusCode, detail: String) -> Self {
+        ProblemDetails {
+            problem_type: String::from("about:blank"),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail,
+            instance: None,
+            correlation_id: None,
+            errors: vec![],
+        }
+    }
+
+    pub fn with_instance(mut self, instance: String) -> Self {
+        self.instance = Some(instance);
+        self
+    }
+
+    pub fn with_correlation_id(mut self, correlation_id: String) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn to_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type(ContentType(PROBLEM_JSON_CONTENT_TYPE.parse().unwrap()))
+            .json(self)
+    }
+}
+
+impl ApiError {
+    /// problem type slug and title of each error
+    fn kind(&self) -> (&'static str, &'static str) {
+        match self {
+            ApiError::BadRequest(_) => ("bad-request", "Bad request"),
+            ApiError::InternalServerError(_) => ("internal-server-error", "Internal server error"),
+            ApiError::NotFound(_) => ("not-found", "Resource not found"),
+            ApiError::InvalidData(_) => ("invalid-data", "Invalid data"),
+            ApiError::Unknown(_) => ("unknown", "Unexpected error"),
+            ApiError::Conflict(_) => ("conflict", "Resource already exists"),
+            ApiError::UnsupportedMediaType(_) => ("unsupported-media-type", "Unsupported media type"),
+            ApiError::NotAcceptable(_) => ("not-acceptable", "Not acceptable"),
+            ApiError::PreconditionFailed(_) => ("precondition-failed", "Precondition failed"),
+            ApiError::PreconditionRequired(_) => ("precondition-required", "Precondition required"),
+            ApiError::ValidationError(_) => ("validation-error", "Validation failed"),
+        }
+    }
+
+    pub fn problem(&self) -> ProblemDetails {
+        let (slug, title) = self.kind();
+
+        let (detail, errors) = match self {
+            ApiError::ValidationError(violations) =>
+                (String::from("One or more fields of the request are invalid"), violations.to_vec()),
+            ApiError::BadRequest(m)
+                | ApiError::InternalServerError(m)
+                | ApiError::NotFound(m)
+                | ApiError::InvalidData(m)
+                | ApiError::Unknown(m)
+                | ApiError::Conflict(m)
+                | ApiError::UnsupportedMediaType(m)
+                | ApiError::NotAcceptable(m)
+                | ApiError::PreconditionFailed(m)
+                | ApiError::PreconditionRequired(m) => (m.clone(), vec![]),
+        };
+
+        ProblemDetails {
+            problem_type: format!("{}{}", PROBLEM_TYPE_BASE, slug),
+            title: title.to_string(),
+            status: self.status_code().as_u16(),
+            detail,
+            instance: None,
+            correlation_id: None,
+            errors,
+        }
+    }
 }
 
 impl Display for ApiError {
@@ -26,39 +144,71 @@ impl Display for ApiError {
                 | ApiError::NotFound(err)
                 | ApiError::InvalidData(err)
                 | ApiError::Conflict(err)
+                | ApiError::UnsupportedMediaType(err)
+                | ApiError::NotAcceptable(err)
+                | ApiError::PreconditionFailed(err)
+                | ApiError::PreconditionRequired(err)
                 | ApiError::Unknown(err) => writeln!(f, "{},", err),
-            ApiError::ValidationError(mex_vec) => {
-                mex_vec.iter().fold(Ok(()), |result, err| {
-                    result.and_then(|_| writeln!(f, "{}, ", err))
-                })
+            ApiError::ValidationError(violations) => {
+                violations.iter().try_for_each(|v| writeln!(f, "{}: {}, ", v.field, v.message))
             },
         }
     }
 }
 
-/// Automatically convert ApiErrors to external ResponseError
+/// Automatically convert ApiErrors to external ResponseError.
+/// The instance and the correlation id are added by the problem details middleware, which knows the request.
 impl ResponseError for ApiError {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match self {
-            ApiError::BadRequest(error) => {
-                HttpResponse::BadRequest().json(error)
-            }
-            ApiError::NotFound(message) => {
-                HttpResponse::NotFound().json(message)
-            }
-            ApiError::ValidationError(errors) => {
-                HttpResponse::UnprocessableEntity().json(&errors.to_vec())
-            }
-            ApiError::InternalServerError(error) => {
-                HttpResponse::Unauthorized().json(error)
-            }
-            ApiError::Conflict(error) => {
-                HttpResponse::Conflict().json(error)
-            }
-            ApiError::InvalidData(error) => {
-                HttpResponse::BadRequest().json(error)
-            }
-            ApiError::Unknown(_) => HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+            ApiError::BadRequest(_) | ApiError::InvalidData(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiError::NotAcceptable(_) => StatusCode::NOT_ACCEPTABLE,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::InternalServerError(_) | ApiError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        self.problem().to_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::to_bytes;
+    use actix_web::http::header::CONTENT_TYPE;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn should_render_an_internal_server_error_as_a_500_problem() {
+        let resp = ApiError::InternalServerError(String::from("boom")).error_response();
+
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), PROBLEM_JSON_CONTENT_TYPE);
+
+        let problem: ProblemDetails = serde_json::from_slice(&to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(problem.status, 500);
+        assert_eq!(problem.detail, "boom");
+        assert_eq!(problem.problem_type, "/recipes/problems/internal-server-error");
+    }
+
+    #[actix_web::test]
+    async fn should_render_validation_errors_field_by_field() {
+        let err = ApiError::ValidationError(vec![FieldViolation {
+            field: String::from("name"),
+            message: String::from("name is required"),
+        }]);
+
+        let problem = err.problem();
+
+        assert_eq!(problem.status, 422);
+        assert_eq!(problem.errors[0].field, "name");
+        assert_eq!(problem.errors[0].message, "name is required");
+    }
 }
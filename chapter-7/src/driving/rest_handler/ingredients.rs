@@ -0,0 +1,196 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::domain;
+use crate::domain::ingredient_catalog::IngredientError;
+use crate::domain::ingredients::CatalogIngredient;
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driving::rest_handler::errors::{ApiError, ProblemDetails};
+use crate::driving::rest_handler::negotiation::{negotiate, Negotiated};
+use crate::helpers::{respond, respond_with_status};
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct SetIngredientRequest {
+    /// other names of the ingredient, replaced by its canonical name in the sandwiches
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct IngredientResponse {
+    pub name: String,
+    pub synonyms: Vec<String>,
+}
+
+impl From<CatalogIngredient> for IngredientResponse {
+    fn from(ingredient: CatalogIngredient) -> Self {
+        IngredientResponse {
+            name: ingredient.name().to_string(),
+            synonyms: ingredient.synonyms().clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct IngredientListResponse {
+    pub ingredients: Vec<IngredientResponse>,
+}
+
+/// list the catalog ingredients
+#[utoipa::path(
+    get,
+    path = "/ingredients",
+    responses(
+        (status = 200, description = "All the catalog ingredients, sorted by name", body = IngredientListResponse),
+    )
+)]
+pub async fn find_ingredients<C: IngredientCatalog + 'static>(
+    catalog: web::Data<C>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+
+    let result = domain::ingredient_catalog::find_ingredients(catalog.get_ref()).await;
+
+    result
+        .map(|v| respond(media_type, IngredientListResponse {
+            ingredients: v.into_iter().map(IngredientResponse::from).collect()
+        }))
+        .map_err(ingredient_error)?
+}
+
+/// get a catalog ingredient
+#[utoipa::path(
+    get,
+    path = "/ingredients/{name}",
+    params(("name" = String, Path, description = "Canonical name or synonym, case and accent insensitive")),
+    responses(
+        (status = 200, description = "The ingredient known under this name", body = IngredientResponse),
+        (status = 404, description = "No ingredient known under this name", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_ingredient<C: IngredientCatalog + 'static>(
+    catalog: web::Data<C>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+    let name = path.into_inner();
+
+    let result = domain::ingredient_catalog::find_ingredient(catalog.get_ref(), &name).await;
+
+    result
+        .map(|i| respond(media_type, IngredientResponse::from(i)))
+        .map_err(ingredient_error)?
+}
+
+/// set the synonyms of a catalog ingredient
+#[utoipa::path(
+    put,
+    path = "/ingredients/{name}",
+    params(("name" = String, Path, description = "Canonical name, case and accent insensitive")),
+    request_body = SetIngredientRequest,
+    responses(
+        (status = 200, description = "The ingredient, replacing the previous one", body = IngredientResponse),
+        (status = 201, description = "An ingredient new to the catalog", body = IngredientResponse),
+        (status = 400, description = "Empty name or synonym", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "A name already belonging to another ingredient", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn set_ingredient<C: IngredientCatalog + 'static>(
+    catalog: web::Data<C>,
+    path: web::Path<String>,
+    request: Negotiated<SetIngredientRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+    let name = path.into_inner();
+
+    let result = domain::ingredient_catalog::set_ingredient(catalog.get_ref(), &name, request.synonyms.clone()).await;
+
+    result
+        .map(|(i, created)| {
+            let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+            respond_with_status(status, media_type, IngredientResponse::from(i), &None)
+        })
+        .map_err(ingredient_error)?
+}
+
+/// delete a catalog ingredient
+#[utoipa::path(
+    delete,
+    path = "/ingredients/{name}",
+    params(("name" = String, Path, description = "Canonical name, case and accent insensitive")),
+    responses(
+        (status = 200, description = "The ingredient was deleted"),
+        (status = 404, description = "No ingredient with this canonical name", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_ingredient<C: IngredientCatalog + 'static>(
+    catalog: web::Data<C>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let name = path.into_inner();
+
+    let result = domain::ingredient_catalog::delete_ingredient(catalog.get_ref(), &name).await;
+
+    result
+        .map(|_| HttpResponse::Ok().finish())
+        .map_err(ingredient_error)
+}
+
+fn ingredient_error(e: IngredientError) -> ApiError {
+    match e {
+        IngredientError::InvalidData(m) => ApiError::InvalidData(m),
+        IngredientError::Conflict(m) => ApiError::Conflict(m),
+        IngredientError::NotFound => ApiError::NotFound(String::from("No ingredient known under the received name")),
+        IngredientError::Unknown(m) => ApiError::Unknown(m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test};
+    use actix_web::test::TestRequest;
+    use actix_web::web::Data;
+
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn should_set_get_and_delete_an_ingredient() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/ingredients", web::get().to(find_ingredients::<InMemoryIngredientCatalog>))
+                .route("/ingredients/{name}", web::get().to(get_ingredient::<InMemoryIngredientCatalog>))
+                .route("/ingredients/{name}", web::put().to(set_ingredient::<InMemoryIngredientCatalog>))
+                .route("/ingredients/{name}", web::delete().to(delete_ingredient::<InMemoryIngredientCatalog>))).await;
+
+        let set = |name: &str, synonyms: Vec<&str>| TestRequest::put()
+            .uri(&format!("/ingredients/{}", name))
+            .set_json(SetIngredientRequest { synonyms: synonyms.into_iter().map(String::from).collect() })
+            .to_request();
+
+        assert_eq!(test::call_service(&app, set("mayo", vec![])).await.status(), StatusCode::CREATED);
+        assert_eq!(test::call_service(&app, set("mayo", vec!["Mayonnaise"])).await.status(), StatusCode::OK);
+        assert_eq!(test::call_service(&app, set("mayonnaise", vec![])).await.status(), StatusCode::CONFLICT);
+        assert_eq!(test::call_service(&app, set("aioli", vec![" "])).await.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::get().uri("/ingredients/MAYONNAISE").to_request();
+        let resp: IngredientResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp, IngredientResponse { name: String::from("mayo"), synonyms: vec![String::from("Mayonnaise")] });
+
+        let req = TestRequest::delete().uri("/ingredients/mayo").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let req = TestRequest::get().uri("/ingredients").to_request();
+        let resp: IngredientListResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert!(resp.ingredients.is_empty());
+    }
+}
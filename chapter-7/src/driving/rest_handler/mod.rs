@@ -1,3 +1,11 @@
 pub mod sandwiches;
 pub mod errors;
 mod validate;
+pub mod preconditions;
+pub mod problem_details;
+pub mod openapi;
+pub mod catalog;
+pub mod negotiation;
+pub mod autocomplete;
+pub mod prices;
+pub mod ingredients;
@@ -0,0 +1,185 @@
+use std::ops::Deref;
+
+use actix_web::{FromRequest, HttpRequest};
+use actix_web::dev::Payload;
+use actix_web::http::header::{Accept, CONTENT_TYPE, Header};
+use actix_web::web::Bytes;
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::driving::rest_handler::errors::ApiError;
+
+/// The representations of the sandwiches, in requests and responses
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaType {
+    Json,
+    Yaml,
+    MessagePack,
+    Cbor,
+}
+
+impl MediaType {
+    /// the content type of the responses
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            MediaType::Json => "application/json",
+            MediaType::Yaml => "application/yaml",
+            MediaType::MessagePack => "application/msgpack",
+            MediaType::Cbor => "application/cbor",
+        }
+    }
+
+    /// recognize a media type, including the aliases in use before their registration
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime.split(';').next().unwrap_or("").trim().to_lowercase().as_str() {
+            "application/json" => Some(MediaType::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" => Some(MediaType::Yaml),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(MediaType::MessagePack),
+            "application/cbor" => Some(MediaType::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, data: &T) -> Result<Vec<u8>, ApiError> {
+        let encoded = match self {
+            MediaType::Json => serde_json::to_vec(data).map_err(|e| e.to_string()),
+            MediaType::Yaml => serde_yaml::to_string(data).map(String::into_bytes).map_err(|e| e.to_string()),
+            // structs are encoded as maps, so that consumers do not depend on the field order
+            MediaType::MessagePack => rmp_serde::to_vec_named(data).map_err(|e| e.to_string()),
+            MediaType::Cbor => {
+                let mut buffer = vec![];
+                ciborium::ser::into_writer(data, &mut buffer).map(|_| buffer).map_err(|e| e.to_string())
+            }
+        };
+
+        encoded.map_err(|e| ApiError::InternalServerError(format!("Cannot encode the response: {}", e)))
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, ApiError> {
+        let decoded = match self {
+            MediaType::Json => serde_json::from_slice(body).map_err(|e| e.to_string()),
+            MediaType::Yaml => serde_yaml::from_slice(body).map_err(|e| e.to_string()),
+            MediaType::MessagePack => rmp_serde::from_slice(body).map_err(|e| e.to_string()),
+            MediaType::Cbor => ciborium::de::from_reader(body).map_err(|e| e.to_string()),
+        };
+
+        decoded.map_err(|e| ApiError::BadRequest(format!("Invalid request body: {}", e)))
+    }
+}
+
+/// Choose the representation of the response from the Accept header, JSON when any is fine
+pub fn negotiate(req: &HttpRequest) -> Result<MediaType, ApiError> {
+
+    if !req.headers().contains_key(Accept::name()) {
+        return Ok(MediaType::Json);
+    }
+
+    let accept = Accept::parse(req)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid Accept header: {}", e)))?;
+
+    accept.ranked().iter()
+        .find_map(|mime| match (mime.type_().as_str(), mime.subtype().as_str()) {
+            ("*", "*") | ("application", "*") => Some(MediaType::Json),
+            _ => MediaType::from_mime(mime.essence_str()),
+        })
+        .ok_or_else(|| ApiError::NotAcceptable(String::from("Supported media types are application/json, application/yaml, application/msgpack and application/cbor")))
+}
+
+/// The representation of the request body, JSON when not declared
+pub fn request_media_type(req: &HttpRequest) -> Result<MediaType, ApiError> {
+    match req.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+        None => Ok(MediaType::Json),
+        Some(content_type) => MediaType::from_mime(content_type)
+            .ok_or_else(|| ApiError::UnsupportedMediaType(String::from("Content-Type must be application/json, application/yaml, application/msgpack or application/cbor"))),
+    }
+}
+
+/// Extractor of a request body in any of the supported media types
+#[derive(Debug)]
+pub struct Negotiated<T>(pub T);
+
+impl<T> Negotiated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Negotiated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Negotiated<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let media_type = request_media_type(req);
+        let body = Bytes::from_request(req, payload);
+
+        Box::pin(async move {
+            let media_type = media_type?;
+            let body = body.await?;
+            Ok(Negotiated(media_type.decode(&body)?))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::ACCEPT;
+    use actix_web::test::TestRequest;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Stub {
+        name: String,
+        ingredients: Vec<String>,
+    }
+
+    fn stub() -> Stub {
+        Stub { name: String::from("Hot dog"), ingredients: vec![String::from("Wurst")] }
+    }
+
+    #[actix_web::test]
+    async fn should_pick_the_preferred_supported_media_type() {
+        let req = TestRequest::default()
+            .insert_header((ACCEPT, "text/html, application/cbor;q=0.5, application/x-yaml;q=0.9"))
+            .to_http_request();
+
+        assert_eq!(negotiate(&req), Ok(MediaType::Yaml));
+    }
+
+    #[actix_web::test]
+    async fn should_default_to_json() {
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(negotiate(&req), Ok(MediaType::Json));
+
+        let req = TestRequest::default().insert_header((ACCEPT, "*/*")).to_http_request();
+        assert_eq!(negotiate(&req), Ok(MediaType::Json));
+    }
+
+    #[actix_web::test]
+    async fn should_not_accept_unsupported_media_types() {
+        let req = TestRequest::default().insert_header((ACCEPT, "text/html")).to_http_request();
+
+        assert!(matches!(negotiate(&req), Err(ApiError::NotAcceptable(_))));
+    }
+
+    #[actix_web::test]
+    async fn should_round_trip_every_media_type() {
+        for media_type in [MediaType::Json, MediaType::Yaml, MediaType::MessagePack, MediaType::Cbor] {
+            let encoded = media_type.encode(&stub()).unwrap();
+
+            let decoded: Stub = media_type.decode(&encoded).unwrap();
+
+            assert_eq!(decoded, stub());
+        }
+    }
+}
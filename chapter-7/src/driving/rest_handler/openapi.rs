@@ -0,0 +1,198 @@
+use std::borrow::Cow;
+
+use utoipa::{OpenApi, PartialSchema, ToSchema};
+use utoipa::openapi::{ObjectBuilder, RefOr, Schema, Type};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::domain::allergens::Allergen;
+use crate::domain::sandwich::{SandwichTag, SandwichType};
+use crate::driving::rest_handler::autocomplete::{self, AutocompleteField, AutocompleteResponse, SuggestionResponse};
+use crate::driving::rest_handler::catalog::{self, AcceptedRowResponse, CatalogFormat, CatalogRecord, ImportOutcomeResponse, ImportReportResponse, OnConflict, RejectedRowResponse};
+use crate::driving::rest_handler::errors::{FieldViolation, ProblemDetails};
+use crate::driving::rest_handler::ingredients::{self, IngredientListResponse, IngredientResponse, SetIngredientRequest};
+use crate::driving::rest_handler::prices::{self, PriceListResponse, PriceResponse, SetPriceRequest};
+use crate::driving::rest_handler::sandwiches::{self, BatchItemResponse, BatchOperationRequest, BatchSandwichRequest, BatchSandwichResponse, CreateSandwichRequest, FindSandwichRequest, MakeableSandwichRequest, MakeableSandwichResponse, NearMissResponse, NutrientsResponse, NutritionResponse, SandwichCostResponse, SandwichListResponse, SandwichResponse, StepRequest, StepResponse, UnitsRequest, UpdateSandwichRequest};
+
+pub const OPENAPI_PATH: &str = "/recipes/api/v1/openapi.json";
+const SWAGGER_UI_PATH: &str = "/recipes/swagger-ui/{_:.*}";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Sandwich recipes", description = "Manage sandwich recipes"),
+    servers((url = "/recipes/api/v1")),
+    paths(
+        sandwiches::find_sandwiches,
+        sandwiches::search_sandwiches,
+        sandwiches::find_makeable_sandwiches,
+        sandwiches::get_by_id,
+        sandwiches::get_nutrition,
+        sandwiches::get_cost,
+        sandwiches::create_sandwich,
+        sandwiches::update_sandwich,
+        sandwiches::patch_sandwich,
+        sandwiches::delete_one_sandwich,
+        sandwiches::batch_sandwiches,
+        catalog::export_sandwiches,
+        catalog::import_sandwiches,
+        autocomplete::autocomplete,
+        prices::find_prices,
+        prices::get_price,
+        prices::set_price,
+        prices::delete_price,
+        ingredients::find_ingredients,
+        ingredients::get_ingredient,
+        ingredients::set_ingredient,
+        ingredients::delete_ingredient,
+    ),
+    components(schemas(
+        CreateSandwichRequest,
+        UpdateSandwichRequest,
+        FindSandwichRequest,
+        UnitsRequest,
+        StepRequest,
+        StepResponse,
+        SandwichResponse,
+        SandwichListResponse,
+        MakeableSandwichRequest,
+        MakeableSandwichResponse,
+        NearMissResponse,
+        NutrientsResponse,
+        NutritionResponse,
+        SandwichCostResponse,
+        BatchOperationRequest,
+        BatchSandwichRequest,
+        BatchItemResponse,
+        BatchSandwichResponse,
+        CatalogFormat,
+        CatalogRecord,
+        OnConflict,
+        ImportOutcomeResponse,
+        AutocompleteField,
+        SuggestionResponse,
+        AutocompleteResponse,
+        AcceptedRowResponse,
+        RejectedRowResponse,
+        ImportReportResponse,
+        SetPriceRequest,
+        PriceResponse,
+        PriceListResponse,
+        SetIngredientRequest,
+        IngredientResponse,
+        IngredientListResponse,
+        SandwichType,
+        SandwichTag,
+        Allergen,
+        ProblemDetails,
+        FieldViolation,
+    ))
+)]
+pub struct ApiDoc;
+
+/// the domain enum is documented here, so that the domain does not depend on the REST adapter
+impl PartialSchema for SandwichType {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .enum_values(Some(SandwichType::ALL.iter().map(SandwichType::name)))
+            .into()
+    }
+}
+
+impl ToSchema for SandwichType {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("SandwichType")
+    }
+}
+
+impl PartialSchema for Allergen {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .enum_values(Some(Allergen::ALL.map(|a| a.name())))
+            .into()
+    }
+}
+
+impl ToSchema for Allergen {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("Allergen")
+    }
+}
+
+impl PartialSchema for SandwichTag {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .enum_values(Some(SandwichTag::ALL.iter().map(SandwichTag::name)))
+            .into()
+    }
+}
+
+impl ToSchema for SandwichTag {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("SandwichTag")
+    }
+}
+
+/// Swagger UI, also serving the OpenAPI document it renders
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test};
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::test::TestRequest;
+    use utoipa::openapi::HttpMethod;
+
+    use super::*;
+
+    const API_BASE_PATH: &str = "/recipes/api/v1";
+
+    const ALL_METHODS: [(HttpMethod, Method); 5] = [
+        (HttpMethod::Get, Method::GET),
+        (HttpMethod::Post, Method::POST),
+        (HttpMethod::Put, Method::PUT),
+        (HttpMethod::Patch, Method::PATCH),
+        (HttpMethod::Delete, Method::DELETE),
+    ];
+
+    #[actix_web::test]
+    async fn should_serve_the_openapi_document() {
+        let app = test::init_service(App::new().configure(crate::routes)).await;
+
+        let spec: serde_json::Value = test::call_and_read_body_json(&app, TestRequest::get().uri(OPENAPI_PATH).to_request()).await;
+
+        assert_eq!(spec["servers"][0]["url"], API_BASE_PATH);
+        assert!(spec["components"]["schemas"]["ProblemDetails"].is_object());
+    }
+
+    #[actix_web::test]
+    async fn should_document_exactly_the_registered_routes() {
+        // no repository is registered, so matched routes fail on extraction, unmatched ones give 404 or 405
+        let app = test::init_service(App::new().configure(crate::routes)).await;
+        let spec = ApiDoc::openapi();
+
+        for (path, item) in spec.paths.paths.iter() {
+            let uri = format!("{}{}", API_BASE_PATH, path.replace("{id}", "an-id"));
+
+            for (http_method, method) in ALL_METHODS.iter() {
+                let documented = match http_method {
+                    HttpMethod::Get => item.get.is_some(),
+                    HttpMethod::Post => item.post.is_some(),
+                    HttpMethod::Put => item.put.is_some(),
+                    HttpMethod::Patch => item.patch.is_some(),
+                    HttpMethod::Delete => item.delete.is_some(),
+                    _ => false,
+                };
+
+                let req = TestRequest::default().method(method.clone()).uri(&uri).to_request();
+                let status = test::call_service(&app, req).await.status();
+                let registered = status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED;
+
+                assert_eq!(documented, registered, "{} {} documented: {}, registered: {}", method, uri, documented, registered);
+            }
+        }
+    }
+}
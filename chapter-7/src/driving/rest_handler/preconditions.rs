@@ -0,0 +1,63 @@
+use actix_web::http::header::IF_MATCH;
+use actix_web::HttpRequest;
+
+use crate::driving::rest_handler::errors::ApiError;
+
+/// format a sandwich version as a strong ETag
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// read the version the client expects from the mandatory If-Match header, None when it matches any version (`*`)
+pub fn if_match_version(req: &HttpRequest) -> Result<Option<i64>, ApiError> {
+    let value = req.headers().get(IF_MATCH)
+        .ok_or_else(|| ApiError::PreconditionRequired(String::from("The If-Match header is required")))?
+        .to_str()
+        .map_err(|_| ApiError::BadRequest(String::from("Invalid If-Match header")))?
+        .trim();
+
+    if value == "*" {
+        return Ok(None);
+    }
+
+    value.strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .and_then(|v| v.parse::<i64>().ok())
+        .map(Some)
+        .ok_or_else(|| ApiError::BadRequest(format!("Invalid If-Match header: {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn should_read_the_expected_version() {
+        let req = TestRequest::default().insert_header((IF_MATCH, etag(3))).to_http_request();
+
+        assert_eq!(if_match_version(&req), Ok(Some(3)));
+    }
+
+    #[test]
+    fn should_match_any_version_with_a_wildcard() {
+        let req = TestRequest::default().insert_header((IF_MATCH, "*")).to_http_request();
+
+        assert_eq!(if_match_version(&req), Ok(None));
+    }
+
+    #[test]
+    fn should_require_the_if_match_header() {
+        let req = TestRequest::default().to_http_request();
+
+        assert!(matches!(if_match_version(&req), Err(ApiError::PreconditionRequired(_))));
+    }
+
+    #[test]
+    fn should_reject_a_weak_or_malformed_etag() {
+        let req = TestRequest::default().insert_header((IF_MATCH, "W/\"3\"")).to_http_request();
+
+        assert!(matches!(if_match_version(&req), Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,225 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::domain;
+use crate::domain::price_catalog::PriceError;
+use crate::domain::prices::IngredientPrice;
+use crate::domain::sandwich::Unit;
+use crate::driven::prices::PriceCatalog;
+use crate::driving::rest_handler::errors::{ApiError, ProblemDetails};
+use crate::driving::rest_handler::negotiation::{negotiate, Negotiated};
+use crate::helpers::{respond, respond_with_status};
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct SetPriceRequest {
+    pub price: f64,
+
+    /// quantity the price is paid for, 1 by default
+    pub quantity: Option<f64>,
+
+    /// unit of the quantity, as kg, ml or slice. The ingredient is counted when absent, as eggs
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct PriceResponse {
+    pub ingredient: String,
+    pub price: f64,
+    pub quantity: f64,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub unit: Option<String>,
+    pub currency: String,
+}
+
+impl PriceResponse {
+    fn new(price: IngredientPrice, currency: &str) -> Self {
+        PriceResponse {
+            ingredient: price.ingredient().to_string(),
+            price: price.price(),
+            quantity: price.quantity(),
+            unit: price.unit().map(|u| u.symbol().to_string()),
+            currency: currency.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct PriceListResponse {
+    pub prices: Vec<PriceResponse>,
+}
+
+/// list the ingredient prices
+#[utoipa::path(
+    get,
+    path = "/prices",
+    responses(
+        (status = 200, description = "All the ingredient prices, sorted by ingredient", body = PriceListResponse),
+    )
+)]
+pub async fn find_prices<P: PriceCatalog + 'static>(
+    prices: web::Data<P>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+
+    let result = domain::price_catalog::find_prices(prices.get_ref()).await;
+
+    result
+        .map(|v| respond(media_type, PriceListResponse {
+            prices: v.into_iter().map(|p| PriceResponse::new(p, prices.currency())).collect()
+        }))
+        .map_err(price_error)?
+}
+
+/// get the price of an ingredient
+#[utoipa::path(
+    get,
+    path = "/prices/{ingredient}",
+    params(("ingredient" = String, Path, description = "Ingredient, case and accent insensitive")),
+    responses(
+        (status = 200, description = "The price of the ingredient", body = PriceResponse),
+        (status = 404, description = "No price for this ingredient", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_price<P: PriceCatalog + 'static>(
+    prices: web::Data<P>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+    let ingredient = path.into_inner();
+
+    let result = domain::price_catalog::find_price(prices.get_ref(), &ingredient).await;
+
+    result
+        .map(|p| respond(media_type, PriceResponse::new(p, prices.currency())))
+        .map_err(price_error)?
+}
+
+/// set the price of an ingredient
+///
+/// The price is kept in memory: it is lost when the service restarts, unless it is written in the price list file too
+#[utoipa::path(
+    put,
+    path = "/prices/{ingredient}",
+    params(("ingredient" = String, Path, description = "Ingredient, case and accent insensitive")),
+    request_body = SetPriceRequest,
+    responses(
+        (status = 200, description = "The price, replacing the previous one", body = PriceResponse),
+        (status = 201, description = "The price of an ingredient that had none", body = PriceResponse),
+        (status = 400, description = "Negative price, quantity not positive or unknown unit", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn set_price<P: PriceCatalog + 'static>(
+    prices: web::Data<P>,
+    path: web::Path<String>,
+    request: Negotiated<SetPriceRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+    let ingredient = path.into_inner();
+
+    let unit = match request.unit.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(u) => Some(Unit::parse(u).ok_or_else(|| ApiError::InvalidData(format!("Unknown unit: {}", u)))?),
+    };
+
+    let result = domain::price_catalog::set_price(
+        prices.get_ref(),
+        &ingredient,
+        request.price,
+        request.quantity.unwrap_or(1.0),
+        unit).await;
+
+    result
+        .map(|(p, created)| {
+            let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+            respond_with_status(status, media_type, PriceResponse::new(p, prices.currency()), &None)
+        })
+        .map_err(price_error)?
+}
+
+/// delete the price of an ingredient
+///
+/// The price is deleted from memory: it is back when the service restarts, unless it is removed from the price list file too
+#[utoipa::path(
+    delete,
+    path = "/prices/{ingredient}",
+    params(("ingredient" = String, Path, description = "Ingredient, case and accent insensitive")),
+    responses(
+        (status = 200, description = "The price was deleted"),
+        (status = 404, description = "No price for this ingredient", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_price<P: PriceCatalog + 'static>(
+    prices: web::Data<P>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let ingredient = path.into_inner();
+
+    let result = domain::price_catalog::delete_price(prices.get_ref(), &ingredient).await;
+
+    result
+        .map(|_| HttpResponse::Ok().finish())
+        .map_err(price_error)
+}
+
+fn price_error(e: PriceError) -> ApiError {
+    match e {
+        PriceError::InvalidData(m) => ApiError::InvalidData(m),
+        PriceError::NotFound => ApiError::NotFound(String::from("No price for the received ingredient")),
+        PriceError::Unknown(m) => ApiError::Unknown(m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, test};
+    use actix_web::test::TestRequest;
+    use actix_web::web::Data;
+
+    use crate::driven::prices::in_memory_catalog::InMemoryPriceCatalog;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn should_set_get_and_delete_a_price() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(InMemoryPriceCatalog::new("EUR")))
+                .route("/prices", web::get().to(find_prices::<InMemoryPriceCatalog>))
+                .route("/prices/{ingredient}", web::get().to(get_price::<InMemoryPriceCatalog>))
+                .route("/prices/{ingredient}", web::put().to(set_price::<InMemoryPriceCatalog>))
+                .route("/prices/{ingredient}", web::delete().to(delete_price::<InMemoryPriceCatalog>))).await;
+
+        let set = |price: f64| TestRequest::put()
+            .uri("/prices/Smoked%20Salmon")
+            .set_json(SetPriceRequest { price, quantity: Some(100.0), unit: Some(String::from("grams")) })
+            .to_request();
+
+        assert_eq!(test::call_service(&app, set(4.5)).await.status(), StatusCode::CREATED);
+        assert_eq!(test::call_service(&app, set(4.9)).await.status(), StatusCode::OK);
+        assert_eq!(test::call_service(&app, set(-1.0)).await.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::get().uri("/prices/smoked%20salmon").to_request();
+        let resp: PriceResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp, PriceResponse {
+            ingredient: String::from("Smoked Salmon"),
+            price: 4.9,
+            quantity: 100.0,
+            unit: Some(String::from("g")),
+            currency: String::from("EUR"),
+        });
+
+        let req = TestRequest::delete().uri("/prices/smoked%20salmon").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let req = TestRequest::get().uri("/prices").to_request();
+        let resp: PriceListResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert!(resp.prices.is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::Error;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+
+use crate::driving::rest_handler::errors::{ApiError, ProblemDetails};
+
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Middleware tagging every response with a correlation id, taken from the request when provided,
+/// and rendering every error as a problem document pointing to the failing request.
+pub async fn problem_details(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+
+    let correlation_id = req.headers().get(CORRELATION_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+        .unwrap_or_else(new_correlation_id);
+    let instance = req.path().to_string();
+
+    let res = next.call(req).await?;
+
+    let problem = res.response().error().map(|e| match e.as_error::<ApiError>() {
+        Some(api_error) => api_error.problem(),
+        None => ProblemDetails::from_status(res.status(), e.to_string()),
+    });
+
+    let mut res = match problem {
+        Some(problem) => {
+            let problem = problem
+                .with_instance(instance)
+                .with_correlation_id(correlation_id.clone());
+            let (req, _) = res.into_parts();
+            ServiceResponse::new(req, problem.to_response())
+        }
+        None => res.map_into_boxed_body()
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        res.headers_mut().insert(HeaderName::from_static(CORRELATION_ID_HEADER), value);
+    }
+
+    Ok(res)
+}
+
+fn new_correlation_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpResponse, test, web};
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::TestRequest;
+
+    use crate::driving::rest_handler::errors::FieldViolation;
+
+    use super::*;
+
+    async fn failing() -> Result<HttpResponse, ApiError> {
+        Err(ApiError::ValidationError(vec![FieldViolation {
+            field: String::from("name"),
+            message: String::from("name is required"),
+        }]))
+    }
+
+    #[actix_web::test]
+    async fn should_add_instance_and_correlation_id_to_problems() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(problem_details))
+                .route("/sandwiches", web::post().to(failing))).await;
+        let req = TestRequest::post()
+            .uri("/sandwiches")
+            .insert_header((CORRELATION_ID_HEADER, "abc-123"))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(resp.headers().get(CORRELATION_ID_HEADER).unwrap(), "abc-123");
+
+        let problem: ProblemDetails = test::read_body_json(resp).await;
+        assert_eq!(problem.instance, Some(String::from("/sandwiches")));
+        assert_eq!(problem.correlation_id, Some(String::from("abc-123")));
+        assert_eq!(problem.errors.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn should_render_extractor_errors_as_problems() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(problem_details))
+                .route("/sandwiches", web::post().to(|_: web::Json<serde_json::Value>| async { HttpResponse::Ok().finish() }))).await;
+        let req = TestRequest::post()
+            .uri("/sandwiches")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{ not json")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(resp.headers().contains_key(CORRELATION_ID_HEADER));
+
+        let problem: ProblemDetails = test::read_body_json(resp).await;
+        assert_eq!(problem.problem_type, "about:blank");
+        assert!(problem.correlation_id.is_some());
+    }
+}
@@ -1,25 +1,62 @@
-use actix_web::{HttpResponse, web};
-use actix_web::web::Json;
+use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::http::header::{CONTENT_TYPE, ETAG, HeaderValue, WARNING};
+use actix_web::http::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_qs::actix::QsQuery;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 use crate::{domain, Repository, Sandwich};
+use crate::domain::allergens::Allergen;
+use crate::domain::classification::classifier;
+use crate::domain::commands::{SandwichCommand, SandwichPorts};
+use crate::domain::batch_sandwiches::{BatchError, OperationError, OperationOutcome, OperationResult, SandwichOperation};
+use crate::domain::conversion::UnitSystem;
+use crate::domain::cost_sandwich::CostError;
 use crate::domain::create_sandwich::CreateError;
 use crate::domain::delete_one_sandwich::DeleteOneError;
 use crate::domain::find_all_sandwiches::FindAllError;
+use crate::domain::find_makeable_sandwiches::{MakeableError, MakeableSandwiches};
 use crate::domain::find_one_sandwich::FindOneError;
-use crate::domain::sandwich::SandwichType;
+use crate::domain::nutrition::{bundled_table, NutritionEstimate, Nutrients};
+use crate::domain::patch_sandwich::{PatchError, SandwichDraft};
+use crate::domain::prices::SandwichCost;
+use crate::domain::search_sandwiches::SearchError;
+use crate::domain::sandwich::{PreparationStep, SandwichServings, SandwichTag, SandwichType};
 use crate::domain::update_sandwich::UpdateError;
-use crate::driving::rest_handler::errors::ApiError;
+use crate::driven::ingredients::IngredientCatalog;
+use crate::driven::prices::PriceCatalog;
+use crate::driven::repository::IngredientFilter;
+use crate::driven::suggestions::SuggestionIndex;
+use crate::driving::rest_handler::errors::{ApiError, ProblemDetails};
+use crate::driving::rest_handler::negotiation::{MediaType, negotiate, Negotiated};
 use crate::driving::rest_handler::validate::validate;
-use crate::helpers::{respond_json, string_vec_to_vec_str};
+use crate::driving::rest_handler::preconditions::if_match_version;
+use crate::helpers::{respond, respond_with_etag, respond_with_status};
 
 //
 // REQUESTS
 //
 
-#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct StepRequest {
+    pub instruction: String,
+
+    /// minutes the step takes, between 1 and 1440
+    pub duration_minutes: Option<u32>,
+
+    /// waiting, as toasting or resting, counted in the total time but not in the preparation time
+    #[serde(default)]
+    pub passive: bool,
+}
+
+impl From<&StepRequest> for PreparationStep {
+    fn from(s: &StepRequest) -> Self {
+        PreparationStep::new(s.instruction.clone(), s.duration_minutes, s.passive)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate, ToSchema)]
 pub struct CreateSandwichRequest {
     #[validate(length(
     min = 3,
@@ -33,10 +70,22 @@ pub struct CreateSandwichRequest {
     ))]
     pub ingredients: Vec<String>,
 
+    /// preparation steps, in order
+    #[serde(default)]
+    pub steps: Vec<StepRequest>,
+
     pub sandwich_type: SandwichType,
+
+    /// as spicy or breakfast
+    #[serde(default)]
+    pub tags: Vec<SandwichTag>,
+
+    /// number of people the quantities are meant for, 1 by default
+    #[validate(range(min = 1, max = 100, message = "servings must be between 1 and 100"))]
+    pub servings: Option<u32>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, Validate)]
+#[derive(Clone, Debug, Deserialize, Serialize, Validate, ToSchema)]
 pub struct UpdateSandwichRequest {
     #[validate(length(
     min = 5,
@@ -56,30 +105,221 @@ pub struct UpdateSandwichRequest {
     ))]
     pub ingredients: Vec<String>,
 
+    /// preparation steps, in order
+    #[serde(default)]
+    pub steps: Vec<StepRequest>,
+
     // TODO add validation for enum?
     pub sandwich_type: SandwichType,
+
+    /// as spicy or breakfast
+    #[serde(default)]
+    pub tags: Vec<SandwichTag>,
+
+    /// number of people the quantities are meant for, 1 by default
+    #[validate(range(min = 1, max = 100, message = "servings must be between 1 and 100"))]
+    pub servings: Option<u32>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
 pub struct FindSandwichRequest {
     pub name: Option<String>,
 
+    /// same as `all_of`, kept for the existing clients
     pub ingredients: Option<Vec<String>>,
 
+    /// ingredients that must all be present, case insensitive
+    pub all_of: Option<Vec<String>>,
+
+    /// ingredients of which at least one must be present, case insensitive
+    pub any_of: Option<Vec<String>>,
+
+    /// ingredients that must not be present, case insensitive
+    pub none_of: Option<Vec<String>>,
+
+    /// allergens the sandwiches must not contain. The sandwiches with an ingredient whose allergens are unknown are left out
+    pub exclude_allergens: Option<Vec<Allergen>>,
+
+    /// highest cost per serving, in the catalog currency. The sandwiches with an ingredient that cannot be priced are left out
+    pub max_cost: Option<f64>,
+
     pub sandwich_type: Option<SandwichType>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchSandwichRequest {
+    /// words to look for in the names and ingredients, case and accent insensitive, every word must match
+    pub q: String,
+
+    /// maximum number of results, 20 by default and at most 100
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitsRequest {
+    Metric,
+    Imperial,
+}
+
+impl From<UnitsRequest> for UnitSystem {
+    fn from(u: UnitsRequest) -> Self {
+        match u {
+            UnitsRequest::Metric => UnitSystem::Metric,
+            UnitsRequest::Imperial => UnitSystem::Imperial,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScaleSandwichRequest {
+    /// number of people to scale the quantities to, between 1 and 100
+    pub servings: Option<u32>,
+
+    /// system to convert the quantities to, g↔oz, ml↔fl oz
+    pub units: Option<UnitsRequest>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct MakeableSandwichRequest {
+    #[validate(length(
+    min = 1,
+    message = "pantry is required and must be at least 1 item"
+    ))]
+    pub pantry: Vec<String>,
+
+    /// most ingredients a near miss may lack, 2 by default
+    pub max_missing: Option<usize>,
+}
+
+/// One operation of a batch, tagged by `op`.
+/// Updates and deletions carry the version of the sandwich, as the If-Match header does for single operations
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOperationRequest {
+    Create {
+        name: String,
+        ingredients: Vec<String>,
+        sandwich_type: SandwichType,
+    },
+    Update {
+        id: String,
+        name: String,
+        ingredients: Vec<String>,
+        sandwich_type: SandwichType,
+        version: i64,
+    },
+    Delete {
+        id: String,
+        version: i64,
+    },
+}
+
+impl From<BatchOperationRequest> for SandwichOperation {
+    fn from(op: BatchOperationRequest) -> Self {
+        match op {
+            BatchOperationRequest::Create { name, ingredients, sandwich_type } =>
+                SandwichOperation::Create { name, ingredients, sandwich_type },
+            BatchOperationRequest::Update { id, name, ingredients, sandwich_type, version } =>
+                SandwichOperation::Update { id, name, ingredients, sandwich_type, version: Some(version) },
+            BatchOperationRequest::Delete { id, version } =>
+                SandwichOperation::Delete { id, version: Some(version) },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct BatchSandwichRequest {
+    pub operations: Vec<BatchOperationRequest>,
+}
+
+const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// A partial update, either a JSON Merge Patch (RFC 7396) or a JSON Patch (RFC 6902)
+#[derive(Clone, Debug)]
+pub enum PatchSandwichRequest {
+    Merge(serde_json::Value),
+    Json(json_patch::Patch),
+}
+
+impl PatchSandwichRequest {
+    /// parse the body according to the received content type
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Self, ApiError> {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+        match mime.as_str() {
+            MERGE_PATCH_CONTENT_TYPE => serde_json::from_slice(body)
+                .map(PatchSandwichRequest::Merge)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid merge patch: {}", e))),
+            JSON_PATCH_CONTENT_TYPE => serde_json::from_slice(body)
+                .map(PatchSandwichRequest::Json)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid JSON patch: {}", e))),
+            _ => Err(ApiError::UnsupportedMediaType(format!("Content-Type must be {} or {}", MERGE_PATCH_CONTENT_TYPE, JSON_PATCH_CONTENT_TYPE)))
+        }
+    }
+
+    /// apply the patch to a draft of the sandwich
+    pub fn apply(&self, draft: SandwichDraft) -> Result<SandwichDraft, String> {
+        let mut doc = serde_json::to_value(draft)
+            .map_err(|e| e.to_string())?;
+
+        match self {
+            PatchSandwichRequest::Merge(p) => json_patch::merge(&mut doc, p),
+            PatchSandwichRequest::Json(p) => json_patch::patch(&mut doc, p)
+                .map_err(|e| e.to_string())?,
+        }
+
+        serde_json::from_value(doc)
+            .map_err(|e| format!("Invalid patched sandwich: {}", e))
+    }
+}
+
 //
 // RESPONSES
 //
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct StepResponse {
+    pub instruction: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub duration_minutes: Option<u32>,
+    pub passive: bool,
+}
+
+impl From<&PreparationStep> for StepResponse {
+    fn from(s: &PreparationStep) -> Self {
+        StepResponse {
+            instruction: s.instruction().to_string(),
+            duration_minutes: s.duration_minutes(),
+            passive: s.passive(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct SandwichResponse {
     pub id: String,
     pub name: String,
     pub ingredients: Vec<String>,
+    pub steps: Vec<StepResponse>,
+    /// minutes of the active steps, absent when no step is timed
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub prep_time_minutes: Option<u32>,
+    /// minutes of all the steps, absent when no step is timed
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub total_time_minutes: Option<u32>,
     pub sandwich_type: SandwichType,
+    pub tags: Vec<SandwichTag>,
     pub stars: i32,
+    pub servings: u32,
+    /// derived from the ingredients
+    pub allergens: Vec<Allergen>,
+    /// ingredients whose allergens are unknown, which may contain any of them
+    pub allergens_unknown: Vec<String>,
 }
 
 impl From<Sandwich> for SandwichResponse {
@@ -87,14 +327,21 @@ impl From<Sandwich> for SandwichResponse {
         SandwichResponse {
             id: s.id().value().clone().unwrap_or(String::from("")).to_string(),
             name: s.name().value().to_string(),
-            ingredients: s.ingredients().value().clone(),
+            ingredients: s.ingredients().lines(),
+            steps: s.steps().value().iter().map(StepResponse::from).collect(),
+            prep_time_minutes: s.steps().prep_time_minutes(),
+            total_time_minutes: s.steps().total_time_minutes(),
             sandwich_type: s.sandwich_type().clone(),
+            tags: s.tags().clone(),
             stars: s.stars().value().clone(),
+            servings: s.servings().value(),
+            allergens: s.allergens().found.into_iter().collect(),
+            allergens_unknown: s.allergens().unknown,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct SandwichListResponse {
     sandwiches: Vec<SandwichResponse>,
 }
@@ -111,35 +358,311 @@ impl From<Vec<Sandwich>> for SandwichListResponse {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct NearMissResponse {
+    pub sandwich: SandwichResponse,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct MakeableSandwichResponse {
+    pub makeable: Vec<SandwichResponse>,
+    pub near_misses: Vec<NearMissResponse>,
+}
+
+impl From<MakeableSandwiches> for MakeableSandwichResponse {
+    fn from(m: MakeableSandwiches) -> Self {
+        MakeableSandwichResponse {
+            makeable: m.makeable.into_iter().map(SandwichResponse::from).collect(),
+            near_misses: m.near_misses.into_iter()
+                .map(|n| NearMissResponse { sandwich: SandwichResponse::from(n.sandwich), missing: n.missing })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct NutrientsResponse {
+    pub calories_kcal: f64,
+    pub protein_g: f64,
+    pub fat_g: f64,
+    pub carbs_g: f64,
+}
+
+impl From<Nutrients> for NutrientsResponse {
+    fn from(n: Nutrients) -> Self {
+        NutrientsResponse {
+            calories_kcal: n.calories_kcal,
+            protein_g: n.protein_g,
+            fat_g: n.fat_g,
+            carbs_g: n.carbs_g,
+        }
+    }
+}
+
+/// Nutrients estimated from the ingredients the nutrition table knows, with the quantities given
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct NutritionResponse {
+    pub servings: u32,
+    pub total: NutrientsResponse,
+    pub per_serving: NutrientsResponse,
+    /// missing from the nutrition table, left out of the estimate
+    pub unknown_ingredients: Vec<String>,
+    /// without a quantity that can be weighed, left out of the estimate
+    pub unquantified_ingredients: Vec<String>,
+}
+
+impl From<NutritionEstimate> for NutritionResponse {
+    fn from(e: NutritionEstimate) -> Self {
+        NutritionResponse {
+            servings: e.servings,
+            total: NutrientsResponse::from(e.total),
+            per_serving: NutrientsResponse::from(e.per_serving),
+            unknown_ingredients: e.unknown_ingredients,
+            unquantified_ingredients: e.unquantified_ingredients,
+        }
+    }
+}
+
+/// What a sandwich costs, from the ingredients with a known price and a quantity it applies to
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SandwichCostResponse {
+    pub currency: String,
+    pub servings: u32,
+    pub total: f64,
+    pub per_serving: f64,
+    /// without a price, left out of the cost
+    pub unknown_ingredients: Vec<String>,
+    /// without a quantity the price applies to, left out of the cost
+    pub unquantified_ingredients: Vec<String>,
+}
+
+impl From<SandwichCost> for SandwichCostResponse {
+    fn from(c: SandwichCost) -> Self {
+        SandwichCostResponse {
+            currency: c.currency,
+            servings: c.servings,
+            total: c.total,
+            per_serving: c.per_serving,
+            unknown_ingredients: c.unknown_ingredients,
+            unquantified_ingredients: c.unquantified_ingredients,
+        }
+    }
+}
+
+/// Result of one operation of a batch, with the status it would have had if sent alone
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct BatchItemResponse {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sandwich: Option<SandwichResponse>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<ProblemDetails>,
+}
+
+impl BatchItemResponse {
+    fn new(index: usize, result: OperationResult) -> Self {
+        let error = match result {
+            Ok(OperationOutcome::Created(s)) | Ok(OperationOutcome::Updated(s)) => return BatchItemResponse {
+                index,
+                status: StatusCode::OK.as_u16(),
+                sandwich: Some(SandwichResponse::from(s)),
+                error: None,
+            },
+            Ok(OperationOutcome::Deleted) => return BatchItemResponse {
+                index,
+                status: StatusCode::OK.as_u16(),
+                sandwich: None,
+                error: None,
+            },
+            Err(OperationError::Unknown(m)) => ApiError::Unknown(m),
+            Err(OperationError::InvalidData(m)) => ApiError::InvalidData(m),
+            Err(OperationError::NotFound) => ApiError::NotFound(String::from("No sandwich corresponding to the specified id")),
+            Err(OperationError::Conflict(m)) => ApiError::Conflict(m),
+            Err(OperationError::PreconditionFailed) => ApiError::PreconditionFailed(String::from("The sandwich has been modified since it was read")),
+        };
+
+        let problem = error.problem();
+        BatchItemResponse {
+            index,
+            status: problem.status,
+            sandwich: None,
+            error: Some(problem),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct BatchSandwichResponse {
+    pub results: Vec<BatchItemResponse>,
+}
+
 /// find sandwich recipes
-pub async fn find_sandwiches<T: Repository<Sandwich>>(
+#[utoipa::path(
+    get,
+    path = "/sandwiches",
+    params(FindSandwichRequest),
+    responses(
+        (status = 200, description = "Sandwiches matching the criteria", body = SandwichListResponse),
+        (status = 400, description = "Negative maximum cost", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn find_sandwiches<T: Repository<Sandwich>, P: PriceCatalog + 'static, C: IngredientCatalog + 'static>(
     repository: web::Data<T>,
+    prices: web::Data<P>,
+    catalog: web::Data<C>,
     find_req: QsQuery<FindSandwichRequest>,
-) -> Result<Json<SandwichListResponse>, ApiError> {
-    let name = match &find_req.name {
-        Some(n) => n.as_str(),
-        None => ""
-    };
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+
+    let name = find_req.name.clone().unwrap_or_default();
 
-    let ingredients = match &find_req.ingredients {
-        Some(i) => string_vec_to_vec_str(&i),
-        None => vec![]
+    let find_req = find_req.into_inner();
+    let ingredients = IngredientFilter {
+        all_of: find_req.ingredients.into_iter().chain(find_req.all_of).flatten().collect(),
+        any_of: find_req.any_of.unwrap_or_default(),
+        none_of: find_req.none_of.unwrap_or_default(),
     };
-    let result = domain::find_all_sandwiches::find_all_sandwiches(repository, name, &ingredients).await;
+    let exclude_allergens = find_req.exclude_allergens.unwrap_or_default();
+    let max_cost = match find_req.max_cost {
+        Some(max) => Some(domain::cost_sandwich::cost_ceiling(prices.get_ref(), max).await
+            .map_err(cost_error)?),
+        None => None,
+    };
+    let result = domain::find_all_sandwiches::find_all_sandwiches(repository, catalog.get_ref(), &name, ingredients, &exclude_allergens, max_cost.as_ref()).await;
 
     result
-        .map(|v| respond_json(SandwichListResponse::from(v)))
+        .map(|v| respond(media_type, SandwichListResponse::from(v)))
         .map_err(|e| match e {
             FindAllError::Unknown(m) => ApiError::Unknown(m),
         })?
 }
 
+/// search sandwich recipes by relevance
+#[utoipa::path(
+    get,
+    path = "/sandwiches/search",
+    params(SearchSandwichRequest),
+    responses(
+        (status = 200, description = "Sandwiches matching the query, the most relevant first", body = SandwichListResponse),
+        (status = 400, description = "Empty query or invalid limit", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn search_sandwiches<T: Repository<Sandwich>>(
+    repository: web::Data<T>,
+    search_req: web::Query<SearchSandwichRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let media_type = negotiate(&req)?;
+
+    let result = domain::search_sandwiches::search_sandwiches(repository, &search_req.q, search_req.limit).await;
+
+    result
+        .map(|v| respond(media_type, SandwichListResponse::from(v)))
+        .map_err(|e| match e {
+            SearchError::InvalidQuery(m) => ApiError::BadRequest(m),
+            SearchError::Unknown(m) => ApiError::Unknown(m),
+        })?
+}
+
+/// sandwich recipes that can be made with what is in the pantry
+#[utoipa::path(
+    post,
+    path = "/sandwiches/makeable",
+    request_body = MakeableSandwichRequest,
+    responses(
+        (status = 200, description = "Sandwiches fully covered by the pantry, and near misses with their missing ingredients", body = MakeableSandwichResponse),
+        (status = 400, description = "Empty pantry", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn find_makeable_sandwiches<T: Repository<Sandwich>>(
+    repository: web::Data<T>,
+    request: Negotiated<MakeableSandwichRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+
+    let media_type = negotiate(&req)?;
+    validate(&*request)?;
+
+    let result = domain::find_makeable_sandwiches::find_makeable_sandwiches(repository, &request.pantry, request.max_missing).await;
+
+    result
+        .map(|m| respond(media_type, MakeableSandwichResponse::from(m)))
+        .map_err(|e| match e {
+            MakeableError::EmptyPantry => ApiError::BadRequest(String::from("The pantry contains no ingredients")),
+            MakeableError::Unknown(m) => ApiError::Unknown(m),
+        })?
+}
+
 /// get by id
+#[utoipa::path(
+    get,
+    path = "/sandwiches/{id}",
+    params(("id" = String, Path, description = "Sandwich id"), ScaleSandwichRequest),
+    responses(
+        (status = 200, description = "The sandwich, scaled and converted if asked, with its version as ETag unless scaled or converted", body = SandwichResponse),
+        (status = 400, description = "Invalid servings or units", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No sandwich with this id", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 pub async fn get_by_id<T: Repository<Sandwich>>(
     repository: web::Data<T>,
     path: web::Path<String>,
-) -> Result<Json<SandwichResponse>, ApiError> {
+    scale_req: web::Query<ScaleSandwichRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
 
+    let media_type = negotiate(&req)?;
+    let sandwich_id = path.into_inner();
+
+    let servings = scale_req.servings.map(SandwichServings::try_from).transpose()
+        .map_err(ApiError::BadRequest)?;
+    let units = scale_req.units.map(UnitSystem::from);
+
+    let result = domain::find_one_sandwich::find_one_sandwich(
+        repository,
+        sandwich_id.as_str(),
+        "",
+        vec![].as_ref()).await;
+
+    // the version tags the stored representation, not a scaled or converted one, which cannot be sent back as is
+    let transformed = servings.is_some() || units.is_some();
+
+    let sandwich = result
+        .map_err(|e| match e {
+            FindOneError::Unknown(m) => ApiError::Unknown(m),
+            FindOneError::NotFound => ApiError::NotFound(String::from("No sandwich found with the specified criteria")),
+        })?;
+
+    let mut response = respond_sandwich(media_type, domain::conversion::scale_recipe(sandwich, servings, units))?;
+    if transformed {
+        response.headers_mut().remove(ETAG);
+    }
+    Ok(response)
+}
+
+/// get the nutrition of a sandwich
+#[utoipa::path(
+    get,
+    path = "/sandwiches/{id}/nutrition",
+    params(("id" = String, Path, description = "Sandwich id")),
+    responses(
+        (status = 200, description = "Calories, protein, fat and carbs of the sandwich and of a serving, with the ingredients left out", body = NutritionResponse),
+        (status = 404, description = "No sandwich with this id", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_nutrition<T: Repository<Sandwich>>(
+    repository: web::Data<T>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+
+    let media_type = negotiate(&req)?;
     let sandwich_id = path.into_inner();
 
     let result = domain::find_one_sandwich::find_one_sandwich(
@@ -149,31 +672,86 @@ pub async fn get_by_id<T: Repository<Sandwich>>(
         vec![].as_ref()).await;
 
     result
-        .map(|v| respond_json(SandwichResponse::from(v)))
+        .map(|s| respond(media_type, NutritionResponse::from(bundled_table().estimate(&s))))
         .map_err(|e| match e {
             FindOneError::Unknown(m) => ApiError::Unknown(m),
             FindOneError::NotFound => ApiError::NotFound(String::from("No sandwich found with the specified criteria")),
         })?
 }
 
+/// get the cost of a sandwich
+#[utoipa::path(
+    get,
+    path = "/sandwiches/{id}/cost",
+    params(("id" = String, Path, description = "Sandwich id")),
+    responses(
+        (status = 200, description = "Cost of the sandwich and of a serving in the catalog currency, with the ingredients left out", body = SandwichCostResponse),
+        (status = 404, description = "No sandwich with this id", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn get_cost<T: Repository<Sandwich>, P: PriceCatalog + 'static>(
+    repository: web::Data<T>,
+    prices: web::Data<P>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+
+    let media_type = negotiate(&req)?;
+    let sandwich_id = path.into_inner();
+
+    let result = domain::cost_sandwich::cost_sandwich(repository, prices.get_ref(), sandwich_id.as_str()).await;
+
+    result
+        .map(|c| respond(media_type, SandwichCostResponse::from(c)))
+        .map_err(cost_error)?
+}
+
+fn cost_error(e: CostError) -> ApiError {
+    match e {
+        CostError::InvalidMaxCost(m) => ApiError::BadRequest(m),
+        CostError::NotFound => ApiError::NotFound(String::from("No sandwich found with the specified criteria")),
+        CostError::Unknown(m) => ApiError::Unknown(m),
+    }
+}
+
 /// create sandwich recipes
-pub async fn create_sandwich<T: Repository<Sandwich>>(
+#[utoipa::path(
+    post,
+    path = "/sandwiches",
+    request_body = CreateSandwichRequest,
+    responses(
+        (status = 200, description = "The created sandwich", body = SandwichResponse),
+        (status = 409, description = "A sandwich with the same name exists", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn create_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + 'static, C: IngredientCatalog + 'static>(
     repository: web::Data<T>,
-    request: Json<CreateSandwichRequest>,
-) -> Result<Json<SandwichResponse>, ApiError> {
+    suggestions: web::Data<S>,
+    ingredients: web::Data<C>,
+    request: Negotiated<CreateSandwichRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
 
-    validate(&request)?;
+    let media_type = negotiate(&req)?;
+    validate(&*request)?;
+
+    let request = request.into_inner();
+    let command = SandwichCommand {
+        name: request.name,
+        ingredients: request.ingredients,
+        steps: request.steps.iter().map(PreparationStep::from).collect(),
+        sandwich_type: request.sandwich_type,
+        tags: request.tags,
+        servings: request.servings,
+        version: None,
+    };
+    let ports = SandwichPorts { suggestions: suggestions.get_ref(), catalog: ingredients.get_ref() };
 
-    let result = domain::create_sandwich::create_sandwich(
-        repository,
-        &request.name,
-        string_vec_to_vec_str(&request.ingredients).as_ref(),
-        &request.sandwich_type).await;
+    let result = domain::create_sandwich::create_sandwich(repository, &ports, command).await;
 
     result
-        .map(|v| {
-            respond_json(SandwichResponse::from(v))
-        })
+        .map(|s| respond_sandwich(media_type, s))
         .map_err(|e| match e {
             CreateError::Unknown(m) => ApiError::Unknown(m),
             CreateError::InvalidData(m) => ApiError::InvalidData(m),
@@ -182,61 +760,229 @@ pub async fn create_sandwich<T: Repository<Sandwich>>(
 }
 
 /// update sandwich recipes
-pub async fn update_sandwich<T: Repository<Sandwich>>(
+#[utoipa::path(
+    put,
+    path = "/sandwiches",
+    request_body = UpdateSandwichRequest,
+    params(("If-Match" = String, Header, description = "ETag of the sandwich being updated, or *")),
+    responses(
+        (status = 200, description = "The updated sandwich", body = SandwichResponse),
+        (status = 404, description = "No sandwich with this id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The sandwich changed since it was read", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "Missing If-Match header", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn update_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + 'static, C: IngredientCatalog + 'static>(
     repository: web::Data<T>,
-    request: Json<UpdateSandwichRequest>,
-) -> Result<Json<SandwichResponse>, ApiError> {
+    suggestions: web::Data<S>,
+    ingredients: web::Data<C>,
+    request: Negotiated<UpdateSandwichRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
 
-    validate(&request)?;
+    let media_type = negotiate(&req)?;
+    validate(&*request)?;
+    let version = if_match_version(&req)?;
+
+    let request = request.into_inner();
+    let command = SandwichCommand {
+        name: request.name,
+        ingredients: request.ingredients,
+        steps: request.steps.iter().map(PreparationStep::from).collect(),
+        sandwich_type: request.sandwich_type,
+        tags: request.tags,
+        servings: request.servings,
+        version,
+    };
+    let ports = SandwichPorts { suggestions: suggestions.get_ref(), catalog: ingredients.get_ref() };
 
-    let result = domain::update_sandwich::update_sandwich(
-        repository,
-        request.id.as_str(),
-        request.name.as_str(),
-        string_vec_to_vec_str(&request.ingredients).as_ref(),
-        &request.sandwich_type).await;
+    let result = domain::update_sandwich::update_sandwich(repository, &ports, &request.id, command).await;
 
     result
-        .map(|v| respond_json(SandwichResponse::from(v)))
+        .map(|s| respond_sandwich(media_type, s))
         .map_err(|e| match e {
             UpdateError::Unknown(m) => ApiError::Unknown(m),
             UpdateError::InvalidData(m) => ApiError::InvalidData(m),
             UpdateError::NotFound => ApiError::NotFound(String::from("No sandwich to update corresponding to the specified criteria")),
-            UpdateError::Conflict(m) => ApiError::Conflict(m)
+            UpdateError::Conflict(m) => ApiError::Conflict(m),
+            UpdateError::PreconditionFailed => ApiError::PreconditionFailed(String::from("The sandwich has been modified since it was read"))
+        })?
+}
+
+/// patch sandwich recipes
+#[utoipa::path(
+    patch,
+    path = "/sandwiches/{id}",
+    params(
+        ("id" = String, Path, description = "Sandwich id"),
+        ("If-Match" = String, Header, description = "ETag of the sandwich being patched, or *"),
+    ),
+    request_body(content(
+        (Object = "application/merge-patch+json"),
+        (Vec<Object> = "application/json-patch+json"),
+    )),
+    responses(
+        (status = 200, description = "The patched sandwich", body = SandwichResponse),
+        (status = 404, description = "No sandwich with this id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The sandwich changed since it was read", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 415, description = "Unsupported patch format", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn patch_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + 'static, C: IngredientCatalog + 'static>(
+    repository: web::Data<T>,
+    suggestions: web::Data<S>,
+    ingredients: web::Data<C>,
+    path: web::Path<String>,
+    req: HttpRequest,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+
+    let media_type = negotiate(&req)?;
+    let sandwich_id = path.into_inner();
+    let version = if_match_version(&req)?;
+
+    let content_type = req.headers().get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let patch = PatchSandwichRequest::parse(content_type, &body)?;
+
+    let ports = SandwichPorts { suggestions: suggestions.get_ref(), catalog: ingredients.get_ref() };
+
+    let result = domain::patch_sandwich::patch_sandwich(
+        repository,
+        &ports,
+        sandwich_id.as_str(),
+        version,
+        |draft| patch.apply(draft)).await;
+
+    result
+        .map(|s| respond_sandwich(media_type, s))
+        .map_err(|e| match e {
+            PatchError::Unknown(m) => ApiError::Unknown(m),
+            PatchError::InvalidPatch(m) => ApiError::BadRequest(m),
+            PatchError::InvalidData(m) => ApiError::InvalidData(m),
+            PatchError::NotFound => ApiError::NotFound(String::from("No sandwich to patch corresponding to the specified criteria")),
+            PatchError::Conflict(m) => ApiError::Conflict(m),
+            PatchError::PreconditionFailed => ApiError::PreconditionFailed(String::from("The sandwich has been modified since it was read"))
         })?
 }
 
 
 /// delete one sandwich recipes
-pub async fn delete_one_sandwich<T: Repository<Sandwich>>(
+#[utoipa::path(
+    delete,
+    path = "/sandwiches/{id}",
+    params(
+        ("id" = String, Path, description = "Sandwich id"),
+        ("If-Match" = String, Header, description = "ETag of the sandwich being deleted, or *"),
+    ),
+    responses(
+        (status = 200, description = "The sandwich has been deleted"),
+        (status = 404, description = "No sandwich with this id", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The sandwich changed since it was read", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn delete_one_sandwich<T: Repository<Sandwich>, S: SuggestionIndex + 'static>(
     repository: web::Data<T>,
+    suggestions: web::Data<S>,
     path: web::Path<String>,
+    req: HttpRequest,
 ) -> Result<HttpResponse, ApiError> {
     let sandwich_id = path.into_inner();
+    let version = if_match_version(&req)?;
 
     let result = domain::delete_one_sandwich::delete_one_sandwich(
         repository,
-        sandwich_id.as_str()).await;
+        suggestions.get_ref(),
+        sandwich_id.as_str(),
+        version).await;
 
     result
         .map(|_| Ok(HttpResponse::Ok().finish()))
         .map_err(|e| match e {
             DeleteOneError::Unknown(m) => ApiError::Unknown(m),
             DeleteOneError::InvalidData(m) => ApiError::BadRequest(m),
-            DeleteOneError::NotFound => ApiError::NotFound(String::from("No sandwich to delete corresponding with the received id"))
+            DeleteOneError::NotFound => ApiError::NotFound(String::from("No sandwich to delete corresponding with the received id")),
+            DeleteOneError::PreconditionFailed => ApiError::PreconditionFailed(String::from("The sandwich has been modified since it was read"))
         })?
 }
 
+/// create, update and delete many sandwich recipes at once
+#[utoipa::path(
+    post,
+    path = "/sandwiches:batch",
+    request_body = BatchSandwichRequest,
+    responses(
+        (status = 207, description = "The result of each operation, in the received order", body = BatchSandwichResponse),
+        (status = 400, description = "Empty or too large batch", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn batch_sandwiches<T: Repository<Sandwich>, S: SuggestionIndex + 'static, C: IngredientCatalog + 'static>(
+    repository: web::Data<T>,
+    suggestions: web::Data<S>,
+    ingredients: web::Data<C>,
+    request: Negotiated<BatchSandwichRequest>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+
+    let media_type = negotiate(&req)?;
+
+    let operations = request.into_inner().operations
+        .into_iter()
+        .map(SandwichOperation::from)
+        .collect();
+
+    let ports = SandwichPorts { suggestions: suggestions.get_ref(), catalog: ingredients.get_ref() };
+
+    let results = domain::batch_sandwiches::batch_sandwiches(repository, &ports, operations).await
+        .map_err(|e| match e {
+            BatchError::Empty => ApiError::BadRequest(String::from("The batch contains no operations")),
+            BatchError::TooLarge(max) => ApiError::BadRequest(format!("A batch can contain at most {} operations", max)),
+            BatchError::Unknown(m) => ApiError::Unknown(m),
+        })?;
+
+    let response = BatchSandwichResponse {
+        results: results.into_iter()
+            .enumerate()
+            .map(|(index, result)| BatchItemResponse::new(index, result))
+            .collect()
+    };
+
+    respond_with_status(StatusCode::MULTI_STATUS, media_type, response, &None)
+}
+
+/// respond with a single sandwich, exposing its version as ETag
+fn respond_sandwich(media_type: MediaType, sandwich: Sandwich) -> Result<HttpResponse, ApiError> {
+    let version = *sandwich.version().value();
+    let contradiction = classifier().contradiction(sandwich.sandwich_type(), sandwich.ingredients());
+
+    let mut response = respond_with_etag(media_type, SandwichResponse::from(sandwich), &version)?;
+    // a veggie claim kept against the ingredients is flagged, names that cannot go in a header are left out
+    if let Some(warning) = contradiction.and_then(|c| HeaderValue::from_str(&format!("199 - \"{}\"", c)).ok()) {
+        response.headers_mut().insert(WARNING, warning);
+    }
+    Ok(response)
+}
+
 #[cfg(test)]
 mod tests {
     use actix_web::{App, FromRequest, Handler, Responder, Route, test};
+    use actix_web::http::header::{ACCEPT, IF_MATCH};
     use actix_web::test::TestRequest;
     use actix_web::web::Data;
     use serial_test::serial;
 
+    use crate::domain::prices::IngredientPrice;
+    use crate::domain::sandwich::Unit;
+    use crate::driven::ingredients::in_memory_catalog::InMemoryIngredientCatalog;
+    use crate::driven::prices::in_memory_catalog::InMemoryPriceCatalog;
     use crate::driven::repository::mongo_repository::SandwichMongoRepository;
+    use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
+    use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
+    use crate::driving::rest_handler::preconditions::etag;
     use crate::tests::test_utils::shared;
-    use crate::tests::test_utils::shared::{assert_on_ingredients, CHEESEBURGER_NAME, create_default_sandwich, delete_sandwich_from_sandwich_response, delete_sandwiches_from_list_response, empty_find_sandwich, get_testing_mongodb_config, SANDWICH_STARS, SANDWICH_NAME, SANDWICH_TYPE, stub_cheeseburger, stub_cheeseburger_ingredients, stub_ingredients, stub_sandwich};
+    use crate::tests::test_utils::shared::{assert_on_ingredients, CHEESEBURGER_NAME, create_default_sandwich, delete_sandwich_from_sandwich_response, delete_sandwiches_from_list_response, empty_find_sandwich, get_testing_mongodb_config, SANDWICH_ID, SANDWICH_STARS, SANDWICH_NAME, SANDWICH_TYPE, stub_cheeseburger, stub_cheeseburger_ingredients, stub_ingredients, stub_sandwich};
 
     use super::*;
 
@@ -252,7 +998,7 @@ mod tests {
                                                      None,
                                                      web::get(),
                                                      TestRequest::get(),
-                                                     find_sandwiches::<SandwichMongoRepository>,
+                                                     find_sandwiches::<SandwichMongoRepository, InMemoryPriceCatalog, InMemoryIngredientCatalog>,
                                                      None::<FindSandwichRequest>)
             .await;
 
@@ -271,7 +1017,10 @@ mod tests {
         let create_req = CreateSandwichRequest {
             name: SANDWICH_NAME.to_string(),
             ingredients: stub_ingredients(),
+            steps: vec![],
             sandwich_type: SANDWICH_TYPE,
+            tags: vec![],
+            servings: None,
         };
 
         let resp = execute::<>(&repo,
@@ -279,7 +1028,7 @@ mod tests {
                                None,
                                web::post(),
                                TestRequest::post(),
-                               create_sandwich::<SandwichMongoRepository>,
+                               create_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, InMemoryIngredientCatalog>,
                                Some(create_req))
             .await;
 
@@ -298,16 +1047,19 @@ mod tests {
             id: sandwich.id().value().as_ref().unwrap().to_string(),
             name: CHEESEBURGER_NAME.to_string(),
             ingredients: stub_cheeseburger_ingredients(),
+            steps: vec![],
             sandwich_type: SandwichType::Veggie,
+            tags: vec![],
+            servings: None,
         };
-        let expected = Sandwich::new(updt_req.id.clone(), updt_req.name.clone(), updt_req.ingredients.clone(), updt_req.sandwich_type.clone(), 0).unwrap();
+        let expected = Sandwich::new(updt_req.id.clone(), updt_req.name.clone(), updt_req.ingredients.clone(), vec![], updt_req.sandwich_type.clone(), 0).unwrap();
 
         let resp = execute::<>(&repo,
                                "/",
                                None,
                                web::put(),
-                               TestRequest::put(),
-                               update_sandwich::<SandwichMongoRepository>,
+                               TestRequest::put().insert_header((IF_MATCH, etag(sandwich.version().value().unwrap()))),
+                               update_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, InMemoryIngredientCatalog>,
                                Some(updt_req))
             .await;
 
@@ -316,6 +1068,300 @@ mod tests {
         delete_sandwich_from_sandwich_response(&repo, &resp).await;
     }
 
+    #[serial]
+    #[actix_web::test]
+    async fn should_patch_a_sandwich() {
+        let repo = SandwichMongoRepository::new(&get_testing_mongodb_config()).unwrap();
+        let sandwich = create_default_sandwich(&repo).await;
+        let uri_to_call = format!("/{}", sandwich.id().value().as_ref().unwrap());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo.clone()))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/{id}", web::patch().to(patch_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+        let req = TestRequest::patch()
+            .uri(&uri_to_call)
+            .insert_header((CONTENT_TYPE, JSON_PATCH_CONTENT_TYPE))
+            .insert_header((IF_MATCH, etag(sandwich.version().value().unwrap())))
+            .set_payload(r#"[{ "op": "add", "path": "/ingredients/-", "value": "Mustard" }]"#)
+            .to_request();
+
+        let resp: SandwichResponse = test::call_and_read_body_json(&app, req).await;
+
+        let mut expected_ingredients = stub_ingredients();
+        expected_ingredients.push(String::from("Mustard"));
+        assert_eq!(resp.name, SANDWICH_NAME);
+        assert_on_ingredients(&resp.ingredients, &expected_ingredients);
+
+        delete_sandwich_from_sandwich_response(&repo, &resp).await;
+    }
+
+    #[actix_web::test]
+    async fn should_apply_a_merge_patch() {
+        let patch = PatchSandwichRequest::parse(MERGE_PATCH_CONTENT_TYPE, br#"{ "sandwich_type": "Veggie" }"#).unwrap();
+        let draft = SandwichDraft::from(&stub_sandwich(false));
+
+        let patched = patch.apply(draft.clone()).unwrap();
+
+        assert_eq!(patched.sandwich_type, SandwichType::Veggie);
+        assert_eq!(patched.name, draft.name);
+        assert_eq!(patched.ingredients, draft.ingredients);
+    }
+
+    #[actix_web::test]
+    async fn should_reject_an_unsupported_patch_content_type() {
+        let res = PatchSandwichRequest::parse("application/json", b"{}");
+
+        assert!(matches!(res, Err(ApiError::UnsupportedMediaType(_))));
+    }
+
+    #[actix_web::test]
+    async fn should_answer_a_batch_with_multi_status() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/sandwiches:batch", web::post().to(batch_sandwiches::<SandwichRepoDouble, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+        let req = TestRequest::post()
+            .uri("/sandwiches:batch")
+            .set_payload(r#"{ "operations": [
+                { "op": "create", "name": "Club sandwich", "ingredients": ["Bacon"], "sandwich_type": "Meat" },
+                { "op": "create", "name": "Club sandwich", "ingredients": ["Bacon"], "sandwich_type": "Meat" }
+            ] }"#)
+            .insert_header((CONTENT_TYPE, "application/json"))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let resp: BatchSandwichResponse = test::read_body_json(resp).await;
+        assert_eq!(resp.results[0].status, 200);
+        assert_eq!(resp.results[0].sandwich.as_ref().unwrap().name, "Club sandwich");
+        assert_eq!(resp.results[1].status, 409);
+        assert!(resp.results[1].error.is_some());
+    }
+
+    #[actix_web::test]
+    async fn should_find_makeable_sandwiches() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .route("/sandwiches/makeable", web::post().to(find_makeable_sandwiches::<SandwichRepoDouble>))).await;
+        let req = TestRequest::post()
+            .uri("/sandwiches/makeable")
+            .set_json(MakeableSandwichRequest { pantry: stub_ingredients(), max_missing: None })
+            .to_request();
+
+        let resp: MakeableSandwichResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.makeable.len(), 1);
+        assert_eq!(resp.makeable[0].name, SANDWICH_NAME);
+        assert!(resp.near_misses.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn should_scale_a_sandwich_to_the_asked_servings() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .route("/sandwiches/{id}", web::get().to(get_by_id::<SandwichRepoDouble>))).await;
+
+        let req = TestRequest::get().uri(&format!("/sandwiches/{}", SANDWICH_ID)).to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.headers().contains_key(ETAG));
+
+        let req = TestRequest::get().uri(&format!("/sandwiches/{}?servings=3&units=imperial", SANDWICH_ID)).to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(!resp.headers().contains_key(ETAG));
+        let resp: SandwichResponse = test::read_body_json(resp).await;
+        assert_eq!(resp.servings, 3);
+        assert_eq!(resp.ingredients, stub_ingredients());
+
+        let req = TestRequest::get().uri(&format!("/sandwiches/{}?servings=0", SANDWICH_ID)).to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn should_estimate_the_nutrition_of_a_sandwich() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .route("/sandwiches/{id}/nutrition", web::get().to(get_nutrition::<SandwichRepoDouble>))).await;
+
+        let req = TestRequest::get().uri(&format!("/sandwiches/{}/nutrition", SANDWICH_ID)).to_request();
+        let resp: NutritionResponse = test::call_and_read_body_json(&app, req).await;
+
+        // the stub lists its ingredients without quantities
+        assert_eq!(resp.total.calories_kcal, 0.0);
+        assert!(resp.unknown_ingredients.is_empty());
+        assert_eq!(resp.unquantified_ingredients, stub_ingredients());
+    }
+
+    #[actix_web::test]
+    async fn should_cost_a_sandwich_and_filter_on_the_max_cost() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+        let prices = InMemoryPriceCatalog::new("EUR");
+        prices.upsert(IngredientPrice::new("Ketchup", 3.2, 500.0, Some(Unit::Milliliter)).unwrap()).await.unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .app_data(Data::new(prices))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/sandwiches", web::get().to(find_sandwiches::<SandwichRepoDouble, InMemoryPriceCatalog, InMemoryIngredientCatalog>))
+                .route("/sandwiches/{id}/cost", web::get().to(get_cost::<SandwichRepoDouble, InMemoryPriceCatalog>))).await;
+
+        let req = TestRequest::get().uri(&format!("/sandwiches/{}/cost", SANDWICH_ID)).to_request();
+        let resp: SandwichCostResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.currency, "EUR");
+        assert_eq!(resp.unknown_ingredients, vec!["Wurst"]);
+        assert_eq!(resp.unquantified_ingredients, vec!["Ketchup"]);
+
+        // neither stub can be fully priced
+        let req = TestRequest::get().uri("/sandwiches?max_cost=5").to_request();
+        let resp: SandwichListResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert!(resp.sandwiches.is_empty());
+
+        let req = TestRequest::get().uri("/sandwiches?max_cost=-1").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn should_update_a_sandwich_with_timed_steps() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/sandwiches", web::put().to(update_sandwich::<SandwichRepoDouble, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+
+        let step = |instruction: &str, duration_minutes: Option<u32>, passive: bool| StepRequest { instruction: instruction.to_string(), duration_minutes, passive };
+        let updt_req = UpdateSandwichRequest {
+            id: SANDWICH_ID.to_string(),
+            name: SANDWICH_NAME.to_string(),
+            ingredients: stub_ingredients(),
+            steps: vec![step("Grill the wurst", Some(8), false), step("Let it rest", Some(2), true), step("Add the ketchup", None, false)],
+            sandwich_type: SANDWICH_TYPE,
+            tags: vec![],
+            servings: None,
+        };
+        let req = TestRequest::put().uri("/sandwiches").insert_header((IF_MATCH, "*")).set_json(updt_req).to_request();
+        let resp: SandwichResponse = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.steps.len(), 3);
+        assert_eq!(resp.steps[1], StepResponse { instruction: String::from("Let it rest"), duration_minutes: Some(2), passive: true });
+        assert_eq!(resp.prep_time_minutes, Some(8));
+        assert_eq!(resp.total_time_minutes, Some(10));
+    }
+
+    #[actix_web::test]
+    async fn should_type_and_tag_a_sandwich_and_flag_a_false_veggie_claim() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/sandwiches", web::put().to(update_sandwich::<SandwichRepoDouble, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+
+        let updt_req = |sandwich_type| UpdateSandwichRequest {
+            id: SANDWICH_ID.to_string(),
+            name: SANDWICH_NAME.to_string(),
+            ingredients: stub_ingredients(),
+            steps: vec![],
+            sandwich_type,
+            tags: vec![SandwichTag::Kids, SandwichTag::Spicy, SandwichTag::Kids],
+            servings: None,
+        };
+
+        let req = TestRequest::put().uri("/sandwiches").insert_header((IF_MATCH, "*")).set_json(updt_req(SandwichType::Undefined)).to_request();
+        let resp: SandwichResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.sandwich_type, SandwichType::Meat);
+        assert_eq!(resp.tags, vec![SandwichTag::Spicy, SandwichTag::Kids]);
+
+        let req = TestRequest::put().uri("/sandwiches").insert_header((IF_MATCH, "*")).set_json(updt_req(SandwichType::Veggie)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(WARNING).unwrap(), "199 - \"A veggie sandwich cannot contain Wurst\"");
+    }
+
+    #[actix_web::test]
+    async fn should_negotiate_the_response_media_type() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .route("/{id}", web::get().to(get_by_id::<SandwichRepoDouble>))).await;
+
+        let req = TestRequest::get()
+            .uri("/an-id")
+            .insert_header((ACCEPT, "application/msgpack"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "application/msgpack");
+        let sandwich: SandwichResponse = rmp_serde::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(sandwich.name, SANDWICH_NAME);
+
+        let req = TestRequest::get()
+            .uri("/an-id")
+            .insert_header((ACCEPT, "text/html"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+
+    #[actix_web::test]
+    async fn should_decode_the_request_body_by_content_type() {
+        let repo = SandwichRepoDouble::new(&get_testing_mongodb_config()).unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/sandwiches:batch", web::post().to(batch_sandwiches::<SandwichRepoDouble, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+
+        let yaml = "operations:\n  - op: create\n    name: Club sandwich\n    ingredients: [Bacon]\n    sandwich_type: Meat\n";
+        let req = TestRequest::post()
+            .uri("/sandwiches:batch")
+            .insert_header((CONTENT_TYPE, "application/yaml"))
+            .set_payload(yaml)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+
+        let req = TestRequest::post()
+            .uri("/sandwiches:batch")
+            .insert_header((CONTENT_TYPE, "text/plain"))
+            .set_payload(yaml)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
     #[serial]
     #[actix_web::test]
     async fn should_find_a_sandwich_by_id() {
@@ -338,6 +1384,51 @@ mod tests {
         delete_sandwich_from_sandwich_response(&repo, &resp).await;
     }
 
+    #[serial]
+    #[actix_web::test]
+    async fn should_not_update_a_sandwich_with_a_stale_etag() {
+        let repo = SandwichMongoRepository::new(&get_testing_mongodb_config()).unwrap();
+        let sandwich = create_default_sandwich(&repo).await;
+
+        let updt_req = UpdateSandwichRequest {
+            id: sandwich.id().value().as_ref().unwrap().to_string(),
+            name: CHEESEBURGER_NAME.to_string(),
+            ingredients: stub_cheeseburger_ingredients(),
+            steps: vec![],
+            sandwich_type: SandwichType::Veggie,
+            tags: vec![],
+            servings: None,
+        };
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(repo.clone()))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
+                .route("/", web::put().to(update_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, InMemoryIngredientCatalog>))).await;
+
+        // a first update moves the sandwich to the next version
+        let req = TestRequest::put()
+            .uri("/")
+            .insert_header((IF_MATCH, etag(sandwich.version().value().unwrap())))
+            .set_json(&updt_req)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(ETAG).unwrap(), &etag(sandwich.version().value().unwrap() + 1));
+
+        // a second update with the same ETag is rejected
+        let req = TestRequest::put()
+            .uri("/")
+            .insert_header((IF_MATCH, etag(sandwich.version().value().unwrap())))
+            .set_json(&updt_req)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+        repo.delete(sandwich.id().value().as_ref().unwrap(), None).await.unwrap();
+    }
+
     #[serial]
     #[actix_web::test]
     async fn should_delete_a_sandwich() {
@@ -353,9 +1444,11 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(repo.clone()))
-                .route("/{id}", web::delete().to(delete_one_sandwich::<SandwichMongoRepository>))).await;
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .route("/{id}", web::delete().to(delete_one_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex>))).await;
         let req = TestRequest::delete()
             .uri(&uri_to_call)
+            .insert_header((IF_MATCH, etag(sandwich.version().value().unwrap())))
             .to_request();
 
         let resp = test::call_service(&app, req).await;
@@ -379,6 +1472,9 @@ mod tests {
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(repo.clone()))
+                .app_data(Data::new(InMemorySuggestionIndex::new()))
+                .app_data(Data::new(InMemoryPriceCatalog::new("EUR")))
+                .app_data(Data::new(InMemoryIngredientCatalog::new()))
                 .route(path, http_method.to(handler))).await;
 
         // set uri
@@ -398,6 +1494,6 @@ mod tests {
 
     fn assert_on_sandwich_response(actual: &SandwichResponse, expected: &Sandwich) {
         assert_eq!(&actual.name, expected.name().value());
-        assert_on_ingredients(&actual.ingredients, expected.ingredients().value());
+        assert_on_ingredients(&actual.ingredients, &expected.ingredients().lines());
     }
 }
@@ -1,9 +1,8 @@
-use actix_web::web::Json;
 use validator::{Validate, ValidationErrors};
 
-use crate::driving::rest_handler::errors::ApiError;
+use crate::driving::rest_handler::errors::{ApiError, FieldViolation};
 
-pub fn validate<T>(params: &Json<T>) -> Result<(), ApiError>
+pub fn validate<T>(params: &T) -> Result<(), ApiError>
     where
         T: Validate,
 {
@@ -13,17 +12,20 @@ pub fn validate<T>(params: &Json<T>) -> Result<(), ApiError>
     }
 }
 
-fn collect_errors(error: ValidationErrors) -> Vec<String> {
+fn collect_errors(error: ValidationErrors) -> Vec<FieldViolation> {
     error
         .field_errors()
         .into_iter()
         .map(|error| {
             let default_error = format!("{} is required", error.0);
-            error.1[0]
-                .message
-                .as_ref()
-                .unwrap_or(&std::borrow::Cow::Owned(default_error))
-                .to_string()
+            FieldViolation {
+                field: error.0.to_string(),
+                message: error.1[0]
+                    .message
+                    .as_ref()
+                    .unwrap_or(&std::borrow::Cow::Owned(default_error))
+                    .to_string(),
+            }
         })
         .collect()
 }
@@ -2,7 +2,7 @@ use actix_web::web;
 use rand::Rng;
 
 use crate::domain::sandwich::Sandwich;
-use crate::driven::repository::{RepoCreateError, RepoFindAllError};
+use crate::driven::repository::{RepoCreateError, RepoDeleteError, RepoFindAllError};
 use crate::helpers::empty_find_sandwich;
 use crate::Repository;
 
@@ -17,10 +17,13 @@ pub async fn sync_databases<'a, T: Repository<Sandwich>, U: Repository<Sandwich>
     // delete all from sql
     let sql_sands = sql_repository.find_all(empty_find_sandwich()).await
         .map_err(|_| SyncDbsError::Unknown("Error deleting all records from Sql DB".to_string()))?;
-    for s in sql_sands {
-        sql_repository.delete(s.id().value().as_ref().unwrap()).await
-            .map_err(|_| SyncDbsError::Unknown("Error deleting all records from Sql DB".to_string()))?;
-    }
+    let sql_ids = sql_sands.iter()
+        .map(|s| (s.id().value().clone().unwrap(), None))
+        .collect();
+    sql_repository.delete_many(sql_ids).await
+        .into_iter()
+        .collect::<Result<Vec<()>, RepoDeleteError>>()
+        .map_err(|_| SyncDbsError::Unknown("Error deleting all records from Sql DB".to_string()))?;
 
     // fetch all from mongo
     let sandwiches = mongo_repository.find_all(empty_find_sandwich()).await
@@ -28,24 +31,28 @@ pub async fn sync_databases<'a, T: Repository<Sandwich>, U: Repository<Sandwich>
             RepoFindAllError::Unknown(e) => SyncDbsError::Unknown(e)
         })?;
 
-    // insert all into sql
-    for s in sandwiches {
-
-        // emulate stars by randomization
-        let mut rng = rand::thread_rng();
-        let cloned = Sandwich::new(
+    // emulate stars by randomization
+    let mut rng = rand::thread_rng();
+    let cloned = sandwiches.iter()
+        .map(|s| Sandwich::new(
             "".to_string(),
             s.name().value().clone(),
             s.ingredients().value().clone(),
+            s.steps().value().clone(),
             s.sandwich_type().clone(),
             rng.gen_range(0..250))
-            .unwrap();
+            .unwrap()
+            .with_tags(s.tags().clone())
+            .with_servings(*s.servings()))
+        .collect();
 
-        sql_repository.create(cloned).await
-            .map_err(|e| return match e {
-                RepoCreateError::Unknown(e) | RepoCreateError::InvalidData(e) => SyncDbsError::Unknown(e)
-            })?;
-    }
+    // insert all into sql
+    sql_repository.create_many(cloned).await
+        .into_iter()
+        .collect::<Result<Vec<Sandwich>, RepoCreateError>>()
+        .map_err(|e| return match e {
+            RepoCreateError::Unknown(e) | RepoCreateError::InvalidData(e) => SyncDbsError::Unknown(e)
+        })?;
 
     Ok(())
 }
\ No newline at end of file
@@ -1,30 +1,51 @@
-use actix_web::web::Json;
+use actix_web::http::header::{ETAG, VARY};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
 use serde::Serialize;
 
-use crate::driven::repository::FindSandwich;
+use crate::driven::repository::{FindSandwich, IngredientFilter};
 use crate::driving::rest_handler::errors::ApiError;
+use crate::driving::rest_handler::negotiation::MediaType;
+use crate::driving::rest_handler::preconditions::etag;
 
-/// Helper function to reduce boilerplate of an OK/Json response
-pub fn respond_json<T>(data: T) -> Result<Json<T>, ApiError>
+/// Helper function to reduce boilerplate of an OK response in the negotiated media type
+pub fn respond<T>(media_type: MediaType, data: T) -> Result<HttpResponse, ApiError>
     where
         T: Serialize,
 {
-    Ok(Json(data))
+    respond_with_status(StatusCode::OK, media_type, data, &None)
 }
 
+/// Helper function to reduce boilerplate of an OK response carrying the entity version as ETag
+pub fn respond_with_etag<T>(media_type: MediaType, data: T, version: &Option<i64>) -> Result<HttpResponse, ApiError>
+    where
+        T: Serialize,
+{
+    respond_with_status(StatusCode::OK, media_type, data, version)
+}
 
-pub fn string_vec_to_vec_str(list: &Vec<String>) -> Vec<&str> {
-    let mut pointer_list = Vec::<&str>::new();
-    for item in list {
-        pointer_list.push(item);
+/// Helper function encoding a response in the negotiated media type, with an optional ETag
+pub fn respond_with_status<T>(status: StatusCode, media_type: MediaType, data: T, version: &Option<i64>) -> Result<HttpResponse, ApiError>
+    where
+        T: Serialize,
+{
+    let body = media_type.encode(&data)?;
+
+    let mut response = HttpResponse::build(status);
+    response.content_type(media_type.content_type());
+    // the representation depends on the Accept header, caches must take it into account
+    response.insert_header((VARY, "Accept"));
+    if let Some(v) = version {
+        response.insert_header((ETAG, etag(*v)));
     }
-    pointer_list
+    Ok(response.body(body))
 }
 
+
 pub fn empty_find_sandwich() -> FindSandwich {
     FindSandwich {
         id: None,
         name: String::from(""),
-        ingredients: vec![]
+        ingredients: IngredientFilter::default()
     }
 }
\ No newline at end of file
@@ -2,15 +2,24 @@ extern crate core;
 
 use actix_web::{App, HttpServer, web};
 use actix_web::dev::Server;
-use actix_web::middleware::Logger;
+use actix_web::middleware::{from_fn, Logger};
 use actix_web::web::Data;
 
-use crate::config::parse_local_config;
+use crate::config::{ClassificationConfig, GraphQLConfig, IngredientsConfig, parse_local_config, PricingConfig};
+use crate::domain::backfill_sandwich_types::BackfillError;
+use crate::domain::classification::{BUNDLED_DICTIONARY, SandwichClassifier};
+use crate::domain::normalize_ingredients::NormalizeError;
 use crate::domain::sandwich::Sandwich;
+use crate::driven::ingredients::mongo_catalog::MongoIngredientCatalog;
+use crate::driven::ingredients::read_catalog;
+use crate::driven::persisted_queries::in_memory_store::InMemoryPersistedQueryStore;
+use crate::driven::prices::in_memory_catalog::InMemoryPriceCatalog;
 use crate::driven::repository::mongo_repository::SandwichMongoRepository;
 use crate::driven::repository::Repository;
 use crate::driven::repository::sql_repository::SandwichSqlRepository;
+use crate::driven::suggestions::in_memory_index::InMemorySuggestionIndex;
 use crate::driving::{graphql, rest_handler};
+use crate::driving::graphql::sandwiches::GraphQLPorts;
 use crate::driving::graphql::schema::create_schema;
 use crate::event_sourcing_emulation::event_sourcing_emulation_handler;
 
@@ -22,6 +31,13 @@ mod helpers;
 mod tests;
 mod event_sourcing_emulation;
 
+// run as `sandwich-recipes migrate-ingredients` to structure the ingredients stored as free-text lines, instead of serving
+const MIGRATE_INGREDIENTS: &str = "migrate-ingredients";
+// run as `sandwich-recipes backfill-sandwich-types` to type the stored sandwiches, instead of serving
+const BACKFILL_SANDWICH_TYPES: &str = "backfill-sandwich-types";
+// run as `sandwich-recipes normalize-ingredients` to rename the stored ingredients after the catalog, instead of serving
+const NORMALIZE_INGREDIENTS: &str = "normalize-ingredients";
+
 #[actix_web::main]
 async fn main() {
     std::env::set_var("RUST_LOG", "debug");
@@ -31,21 +47,133 @@ async fn main() {
     let mongo_repo = SandwichMongoRepository::new(&config.mongo_db).unwrap();
     let sql_repo = SandwichSqlRepository::new(&config.maria_db).unwrap();
 
-    create_server(mongo_repo, sql_repo).await.unwrap().await;
+    config.graphql.validate().unwrap();
+    let persisted_queries = match &config.graphql.allow_list_path {
+        Some(path) => InMemoryPersistedQueryStore::from_allow_list(path, config.graphql.max_persisted_queries).unwrap(),
+        None => InMemoryPersistedQueryStore::new(config.graphql.max_persisted_queries)
+    };
+
+    install_classifier(&config.classification).unwrap();
+
+    config.pricing.validate().unwrap();
+    let prices = price_catalog(&config.pricing).unwrap();
+    let ingredients = MongoIngredientCatalog::new(&config.mongo_db).unwrap();
+    // serving with the catalog as stored beats not serving: the seed is tried again at the next start
+    if let Err(e) = seed_ingredient_catalog(&ingredients, &config.ingredients).await {
+        eprintln!("Ingredient catalog not seeded: {}", e);
+    }
+
+    if std::env::args().nth(1).as_deref() == Some(MIGRATE_INGREDIENTS) {
+        // the read model is migrated too, for its filters to match the legacy rows before they are synced again
+        let migrated = match mongo_repo.migrate_ingredients().await {
+            Ok(written) => sql_repo.migrate_ingredients().await.map(|read| (written, read)),
+            Err(e) => Err(e),
+        };
+        match migrated {
+            Ok((written, read)) => println!("{} sandwiches migrated, {} in the read model", written, read),
+            Err(e) => {
+                eprintln!("Migration interrupted: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some(BACKFILL_SANDWICH_TYPES) {
+        // the read model picks the types up at the next sync of the databases
+        match domain::backfill_sandwich_types::backfill_sandwich_types(Data::new(mongo_repo)).await {
+            Ok(typed) => println!("{} sandwiches typed", typed),
+            Err(BackfillError::Unknown(e)) => {
+                eprintln!("Backfill interrupted: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some(NORMALIZE_INGREDIENTS) {
+        match domain::normalize_ingredients::normalize_ingredients(Data::new(mongo_repo), &ingredients).await {
+            Ok(renamed) => println!("{} sandwiches normalized", renamed),
+            Err(NormalizeError::Unknown(e)) => {
+                eprintln!("Normalization interrupted: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let suggestions = InMemorySuggestionIndex::new();
+    // serving with partial suggestions beats not serving: they fill up as sandwiches are written
+    if let Err(e) = domain::autocomplete::index_all_sandwiches(Data::new(mongo_repo.clone()), &suggestions).await {
+        eprintln!("Suggestions not indexed, starting with an empty index: {:?}", e);
+    }
+
+    create_server(mongo_repo, sql_repo, persisted_queries, suggestions, prices, ingredients, config.graphql).await.unwrap().await;
+}
+
+/// the classifier of the sandwich types, reading the configured dictionary or the bundled one
+fn install_classifier(config: &ClassificationConfig) -> Result<(), String> {
+    let dictionary = match &config.dictionary_path {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("Cannot read the ingredient dictionary {}: {}", path, e))?,
+        None => BUNDLED_DICTIONARY.to_string(),
+    };
+
+    domain::classification::install(SandwichClassifier::new(&dictionary, config.veggie_claim)?)
+}
+
+/// the ingredient prices, preloaded with the configured price list if any
+fn price_catalog(config: &PricingConfig) -> Result<InMemoryPriceCatalog, String> {
+    match &config.price_list_path {
+        Some(path) => InMemoryPriceCatalog::from_price_list(path, &config.currency),
+        None => Ok(InMemoryPriceCatalog::new(&config.currency)),
+    }
+}
+
+/// store the configured catalog of canonical ingredient names if none is stored yet
+async fn seed_ingredient_catalog(catalog: &MongoIngredientCatalog, config: &IngredientsConfig) -> Result<usize, String> {
+    let ingredients = match &config.catalog_path {
+        Some(path) => read_catalog(path)?,
+        None => return Ok(0),
+    };
+
+    catalog.seed(ingredients).await
+        .map_err(|e| format!("{:?}", e))
 }
 
 async fn create_server<T: Repository<Sandwich> + Send + Sync + 'static + Clone,
     U: Repository<Sandwich> + Send + Sync + 'static + Clone>(
     mongo_repo: T,
-    sql_repo: U
+    sql_repo: U,
+    persisted_queries: InMemoryPersistedQueryStore,
+    suggestions: InMemorySuggestionIndex,
+    prices: InMemoryPriceCatalog,
+    ingredients: MongoIngredientCatalog,
+    graphql_config: GraphQLConfig
 ) -> Result<Server, std::io::Error> {
 
+    // shared by all the workers, so that a query registered on one is visible to the others
+    let persisted_queries = Data::new(persisted_queries);
+    let suggestions = Data::new(suggestions);
+    let prices = Data::new(prices);
+    let ingredients = Data::new(ingredients);
+    let graphql_ports = Data::new(GraphQLPorts {
+        suggestions: suggestions.clone().into_inner(),
+        ingredients: ingredients.clone().into_inner(),
+    });
+
     let server = HttpServer::new(move || {
         App::new()
+            .wrap(from_fn(rest_handler::problem_details::problem_details))
             .wrap(Logger::default())
             .app_data(Data::new(mongo_repo.clone()))
             .app_data(Data::new(sql_repo.clone()))
             .app_data(web::Data::new(create_schema()))
+            .app_data(web::Data::new(graphql_config.clone()))
+            .app_data(persisted_queries.clone())
+            .app_data(suggestions.clone())
+            .app_data(prices.clone())
+            .app_data(ingredients.clone())
+            .app_data(graphql_ports.clone())
             .configure(routes)
     }).bind(("127.0.0.1", 8080))?
         .run();
@@ -55,24 +183,71 @@ async fn create_server<T: Repository<Sandwich> + Send + Sync + 'static + Clone,
 
 fn routes(cfg: &mut web::ServiceConfig) {
     cfg
+        // registered before the /recipes scope, which would otherwise swallow its paths
+        .service(rest_handler::openapi::swagger_ui())
         .service(
             web::scope("/recipes")
                 .service(
                     web::scope("/api/v1")
                         .service(
                             web::resource("sandwiches")
-                                .route(web::get().to(rest_handler::sandwiches::find_sandwiches::<SandwichSqlRepository>))
-                                .route(web::post().to(rest_handler::sandwiches::create_sandwich::<SandwichMongoRepository>))
-                                .route(web::put().to(rest_handler::sandwiches::update_sandwich::<SandwichMongoRepository>))
+                                .route(web::get().to(rest_handler::sandwiches::find_sandwiches::<SandwichSqlRepository, InMemoryPriceCatalog, MongoIngredientCatalog>))
+                                .route(web::post().to(rest_handler::sandwiches::create_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, MongoIngredientCatalog>))
+                                .route(web::put().to(rest_handler::sandwiches::update_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, MongoIngredientCatalog>))
                         ).service(
+                        web::resource("sandwiches:batch")
+                            .route(web::post().to(rest_handler::sandwiches::batch_sandwiches::<SandwichMongoRepository, InMemorySuggestionIndex, MongoIngredientCatalog>))
+                    ).service(
+                        // registered before sandwiches/{id}, which would match them too
+                        web::resource("sandwiches/export")
+                            .route(web::get().to(rest_handler::catalog::export_sandwiches::<SandwichMongoRepository>))
+                    ).service(
+                        web::resource("sandwiches/import")
+                            .app_data(web::PayloadConfig::new(rest_handler::catalog::MAX_IMPORT_SIZE))
+                            .route(web::post().to(rest_handler::catalog::import_sandwiches::<SandwichMongoRepository, InMemorySuggestionIndex, MongoIngredientCatalog>))
+                    ).service(
+                        web::resource("sandwiches/search")
+                            .route(web::get().to(rest_handler::sandwiches::search_sandwiches::<SandwichMongoRepository>))
+                    ).service(
+                        web::resource("sandwiches/makeable")
+                            .route(web::post().to(rest_handler::sandwiches::find_makeable_sandwiches::<SandwichSqlRepository>))
+                    ).service(
+                        web::resource("autocomplete")
+                            .route(web::get().to(rest_handler::autocomplete::autocomplete::<InMemorySuggestionIndex>))
+                    ).service(
+                        web::resource("sandwiches/{id}/nutrition")
+                            .route(web::get().to(rest_handler::sandwiches::get_nutrition::<SandwichMongoRepository>))
+                    ).service(
+                        web::resource("sandwiches/{id}/cost")
+                            .route(web::get().to(rest_handler::sandwiches::get_cost::<SandwichMongoRepository, InMemoryPriceCatalog>))
+                    ).service(
                         web::resource("sandwiches/{id}")
                             .route(web::get().to(rest_handler::sandwiches::get_by_id::<SandwichMongoRepository>))
-                            .route(web::delete().to(rest_handler::sandwiches::delete_one_sandwich::<SandwichMongoRepository>))
+                            .route(web::patch().to(rest_handler::sandwiches::patch_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex, MongoIngredientCatalog>))
+                            .route(web::delete().to(rest_handler::sandwiches::delete_one_sandwich::<SandwichMongoRepository, InMemorySuggestionIndex>))
+                    ).service(
+                        web::resource("prices")
+                            .route(web::get().to(rest_handler::prices::find_prices::<InMemoryPriceCatalog>))
+                    ).service(
+                        web::resource("prices/{ingredient}")
+                            .route(web::get().to(rest_handler::prices::get_price::<InMemoryPriceCatalog>))
+                            .route(web::put().to(rest_handler::prices::set_price::<InMemoryPriceCatalog>))
+                            .route(web::delete().to(rest_handler::prices::delete_price::<InMemoryPriceCatalog>))
+                    ).service(
+                        web::resource("ingredients")
+                            .route(web::get().to(rest_handler::ingredients::find_ingredients::<MongoIngredientCatalog>))
+                    ).service(
+                        web::resource("ingredients/{name}")
+                            .route(web::get().to(rest_handler::ingredients::get_ingredient::<MongoIngredientCatalog>))
+                            .route(web::put().to(rest_handler::ingredients::set_ingredient::<MongoIngredientCatalog>))
+                            .route(web::delete().to(rest_handler::ingredients::delete_ingredient::<MongoIngredientCatalog>))
                     )
                 )
                 // event sourcing emulation
                 .route("/emulate_event_sourcing", web::post().to(event_sourcing_emulation_handler::emulate_event_sourcing::<SandwichMongoRepository, SandwichSqlRepository>))
                 // graphql
-                .route("/graphql", web::get().to(graphql::sandwiches::sandwiches_graph::<SandwichSqlRepository>))
+                .route("/graphql", web::get().to(graphql::sandwiches::sandwiches_graph::<SandwichMongoRepository, SandwichSqlRepository, InMemoryPersistedQueryStore>))
+                .route("/graphql/schema.graphql", web::get().to(graphql::sandwiches::graphql_schema))
+                .route("/graphql", web::post().to(graphql::sandwiches::sandwiches_graph::<SandwichMongoRepository, SandwichSqlRepository, InMemoryPersistedQueryStore>))
         );
 }
\ No newline at end of file
@@ -1,13 +1,20 @@
 #[cfg(test)]
 pub mod repo_doble {
     use std::cell::RefCell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use async_trait::async_trait;
+    use futures::stream::{self, BoxStream, StreamExt};
 
     use crate::config::{Config, MongoDBConfig};
     use crate::domain::sandwich::Sandwich;
+    use crate::domain::search::SearchIndex;
     use crate::driven::repository::{FindSandwich, RepoCreateError, RepoDeleteError, RepoFindAllError, RepoSelectError, Repository, RepoUpdateError};
-    use crate::tests::test_utils::shared::{SANDWICH_ID, stub_cheeseburger, stub_sandwich};
+    use crate::helpers::empty_find_sandwich;
+    use crate::tests::test_utils::shared::{SANDWICH_ID, stub_cheeseburger, stub_sandwich, stub_stored_sandwich};
+
+    /// the version of the sandwiches the double finds, a write expecting another one is refused
+    const STORED_VERSION: i64 = 1;
 
     struct Wrap(RefCell<bool>);
 
@@ -15,18 +22,31 @@ pub mod repo_doble {
 
     pub struct SandwichRepoDouble {
         has_error: Wrap,
+        find_many_calls: AtomicUsize,
+        filters_ingredients: bool,
     }
 
     impl SandwichRepoDouble {
         pub fn new(_config: &MongoDBConfig) -> Result<Self, String> where Self: Sized {
             Ok(SandwichRepoDouble {
                 has_error: Wrap(RefCell::from(false)),
+                find_many_calls: AtomicUsize::new(0),
+                filters_ingredients: false,
             })
         }
 
         pub fn set_error(&mut self, value: bool) {
             *self.has_error.0.borrow_mut() = value;
         }
+
+        /// apply the ingredient filter of `find_all` as the databases do, on whole names case insensitively
+        pub fn set_filters_ingredients(&mut self, value: bool) {
+            self.filters_ingredients = value;
+        }
+
+        pub fn find_many_calls(&self) -> usize {
+            self.find_many_calls.load(Ordering::SeqCst)
+        }
     }
 
     #[async_trait]
@@ -40,9 +60,12 @@ pub mod repo_doble {
             let s = Sandwich::new(String::from(SANDWICH_ID),
                                   sandwich.name().value().clone(),
                                   sandwich.ingredients().value().clone(),
+                                  sandwich.steps().value().clone(),
                                   sandwich.sandwich_type().clone(),
                                   sandwich.stars().value())
-                .unwrap();
+                .unwrap()
+                .with_tags(sandwich.tags().clone())
+                .with_servings(*sandwich.servings());
 
             Ok(s)
         }
@@ -52,15 +75,53 @@ pub mod repo_doble {
                 return Err(RepoSelectError::Unknown(String::from("Error occurred")));
             }
 
-            Ok(stub_sandwich(false))
+            Ok(stub_stored_sandwich("").with_version(Some(STORED_VERSION)))
         }
 
-        async fn find_all(&self, _sandwich: FindSandwich) -> Result<Vec<Sandwich>, RepoFindAllError> {
+        async fn find_all(&self, sandwich: FindSandwich) -> Result<Vec<Sandwich>, RepoFindAllError> {
             if self.has_error.0.take() {
                 return Err(RepoFindAllError::Unknown(String::from("Error occurred")));
             }
 
-            Ok(vec![stub_sandwich(true), stub_cheeseburger()])
+            let mut all = vec![stub_sandwich(true), stub_cheeseburger()];
+            if self.filters_ingredients {
+                let filter = sandwich.ingredients;
+                let has = |s: &Sandwich, wanted: &String| s.ingredients().value().iter().any(|i| i.name().eq_ignore_ascii_case(wanted));
+                all.retain(|s| filter.all_of.iter().all(|w| has(s, w))
+                    && (filter.any_of.is_empty() || filter.any_of.iter().any(|w| has(s, w)))
+                    && !filter.none_of.iter().any(|w| has(s, w)));
+            }
+
+            Ok(all)
+        }
+
+        async fn find_many_by_ids(&self, ids: Vec<String>) -> Result<Vec<Sandwich>, RepoFindAllError> {
+            self.find_many_calls.fetch_add(1, Ordering::SeqCst);
+
+            if self.has_error.0.take() {
+                return Err(RepoFindAllError::Unknown(String::from("Error occurred")));
+            }
+
+            let sandwiches = ids.iter()
+                .map(|id| stub_stored_sandwich(id))
+                .collect();
+
+            Ok(sandwiches)
+        }
+
+        async fn stream_all(&self) -> Result<BoxStream<'static, Result<Sandwich, RepoFindAllError>>, RepoFindAllError> {
+            let all = self.find_all(empty_find_sandwich()).await?;
+            Ok(stream::iter(all.into_iter().map(Ok)).boxed())
+        }
+
+        async fn find_many_by_names(&self, names: Vec<String>) -> Result<Vec<Sandwich>, RepoFindAllError> {
+            let all = self.find_all(empty_find_sandwich()).await?;
+            Ok(all.into_iter().filter(|s| names.contains(s.name().value())).collect())
+        }
+
+        async fn search(&self, query: &str, limit: usize) -> Result<Vec<Sandwich>, RepoFindAllError> {
+            let all = self.find_all(empty_find_sandwich()).await?;
+            Ok(SearchIndex::new(all).search(query, limit))
         }
 
         async fn update(&self, sandwich: Sandwich) -> Result<Sandwich, RepoUpdateError> {
@@ -69,15 +130,47 @@ pub mod repo_doble {
                 return Err(RepoUpdateError::Unknown(String::from("Error occurred")));
             }
 
+            if sandwich.version().value().is_some_and(|v| v != STORED_VERSION) {
+                return Err(RepoUpdateError::VersionMismatch);
+            }
+
             Ok(sandwich.clone())
         }
 
-        async fn delete(&self, id: &str) -> Result<(), RepoDeleteError> {
+        async fn delete(&self, id: &str, version: Option<i64>) -> Result<(), RepoDeleteError> {
             if self.has_error.0.take() {
                 return Err(RepoDeleteError::Unknown(String::from("Error occurred")));
             }
 
+            if version.is_some_and(|v| v != STORED_VERSION) {
+                return Err(RepoDeleteError::VersionMismatch);
+            }
+
             Ok(())
         }
+
+        async fn create_many(&self, sandwiches: Vec<Sandwich>) -> Vec<Result<Sandwich, RepoCreateError>> {
+            let mut results = vec![];
+            for s in sandwiches {
+                results.push(self.create(s).await);
+            }
+            results
+        }
+
+        async fn update_many(&self, sandwiches: Vec<Sandwich>) -> Vec<Result<Sandwich, RepoUpdateError>> {
+            let mut results = vec![];
+            for s in sandwiches {
+                results.push(self.update(s).await);
+            }
+            results
+        }
+
+        async fn delete_many(&self, ids: Vec<(String, Option<i64>)>) -> Vec<Result<(), RepoDeleteError>> {
+            let mut results = vec![];
+            for (id, version) in ids {
+                results.push(self.delete(&id, version).await);
+            }
+            results
+        }
     }
 }
@@ -6,8 +6,9 @@ pub mod shared {
 
     use crate::{parse_local_config, Repository, SandwichMongoRepository};
     use crate::config::{Config, parse_config, MongoDBConfig};
-    use crate::domain::sandwich::{Sandwich, SandwichType};
-    use crate::driven::repository::FindSandwich;
+    use crate::domain::commands::SandwichCommand;
+    use crate::domain::sandwich::{PreparationStep, Sandwich, SandwichServings, SandwichTag, SandwichType};
+    use crate::driven::repository::{FindSandwich, IngredientFilter};
     use crate::driving::rest_handler::sandwiches::SandwichResponse;
     use crate::tests::sandwich_repo_double::repo_doble::SandwichRepoDouble;
 
@@ -26,7 +27,7 @@ pub mod shared {
             assert_eq!(actual.id().value().as_ref().unwrap(), expected.id().value().as_ref().unwrap());
         }
         assert_eq!(actual.name().value(), expected.name().value());
-        assert_on_ingredients(expected.ingredients().value(), actual.ingredients().value());
+        assert_on_ingredients(&expected.ingredients().lines(), &actual.ingredients().lines());
     }
 
     pub fn assert_on_ingredients(expected_ingredients: &Vec<String>, actual_ingredients: &Vec<String>) {
@@ -49,6 +50,7 @@ pub mod shared {
         let hot_dog = Sandwich::new(sandwich_id.to_string(),
                                     sandwich_name.to_string(),
                                     stub_ingredients(),
+                                    vec![],
                                     SANDWICH_TYPE,
                                     SANDWICH_STARS)
             .unwrap();
@@ -56,6 +58,31 @@ pub mod shared {
         hot_dog
     }
 
+    /// a sandwich as stored, with what only the full create and update carry
+    pub fn stub_stored_sandwich(id: &str) -> Sandwich {
+        let steps = vec![
+            PreparationStep::new(String::from("Grill the sausage"), Some(8), false),
+            PreparationStep::new(String::from("Fill the bun"), None, false),
+        ];
+
+        Sandwich::new(id.to_string(), SANDWICH_NAME.to_string(), stub_ingredients(), steps, SANDWICH_TYPE, SANDWICH_STARS)
+            .unwrap()
+            .with_tags(vec![SandwichTag::Kids])
+            .with_servings(SandwichServings::try_from(2).unwrap())
+    }
+
+    pub fn stub_sandwich_command(version: Option<i64>) -> SandwichCommand {
+        SandwichCommand {
+            name: SANDWICH_NAME.to_string(),
+            ingredients: stub_ingredients(),
+            steps: vec![],
+            sandwich_type: SANDWICH_TYPE,
+            tags: vec![],
+            servings: None,
+            version,
+        }
+    }
+
     pub fn stub_ingredients() -> Vec<String> {
         vec!["Wurst".to_string(), "Ketchup".to_string()]
     }
@@ -74,6 +101,7 @@ pub mod shared {
         Sandwich::new(String::from(""),
                       String::from(CHEESEBURGER_NAME),
                       ingredients.clone(),
+                      vec![],
                       SANDWICH_TYPE,
                       SANDWICH_STARS)
             .unwrap()
@@ -99,7 +127,7 @@ pub mod shared {
     }
 
     pub async fn delete_sandwich_from_sandwich_response<'a, T: Repository<Sandwich>>(repo: &T, resp: &SandwichResponse) {
-        repo.delete(&resp.id).await.unwrap();
+        repo.delete(&resp.id, None).await.unwrap();
     }
 
     pub fn create_sandwich_repo() -> SandwichMongoRepository {
@@ -111,7 +139,7 @@ pub mod shared {
         FindSandwich {
             id: None,
             name: "".to_string(),
-            ingredients: vec![],
+            ingredients: IngredientFilter::default(),
         }
     }
 